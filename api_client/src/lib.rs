@@ -0,0 +1,113 @@
+use std::{fmt, time::Duration};
+
+use reqwest::{Client, header::CONTENT_TYPE};
+use shared::{
+    ResponseStruct,
+    api::{Endpoint, Method},
+};
+
+pub enum ApiError {
+    Http(reqwest::Error),
+    /// The server answered with something that isn't a `ResponseStruct` for this endpoint.
+    Decode { status: u16, error: serde_json::Error },
+}
+
+impl From<reqwest::Error> for ApiError {
+    fn from(value: reqwest::Error) -> Self {
+        return ApiError::Http(value);
+    }
+}
+
+impl fmt::Display for ApiError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ApiError::Http(error) => write!(f, "{error}"),
+            ApiError::Decode { status, error } => {
+                write!(f, "Unexpected response from the server ({status}): {error}")
+            }
+        }
+    }
+}
+
+pub struct ApiResponse<T> {
+    pub status: u16,
+    pub body: ResponseStruct<T>,
+}
+
+pub struct ApiClientConfig {
+    pub base_url: String,
+    /// Whole request, from connecting until the body is read.
+    pub timeout: Duration,
+    pub connect_timeout: Duration,
+}
+
+impl Default for ApiClientConfig {
+    fn default() -> Self {
+        return ApiClientConfig {
+            base_url: "http://localhost:3000".into(),
+            timeout: Duration::from_secs(30),
+            connect_timeout: Duration::from_secs(5),
+        };
+    }
+}
+
+/// Calls `shared::api` endpoints. Paths and payload types come from the endpoint definitions,
+/// so a request the server doesn't understand fails to compile instead of failing at runtime.
+///
+/// Holds one connection pool; build it once and share it. Calls need a Tokio runtime.
+pub struct ApiClient {
+    base_url: String,
+    http: Client,
+}
+
+impl ApiClient {
+    pub fn new(config: ApiClientConfig) -> Self {
+        let http = Client::builder()
+            .timeout(config.timeout)
+            .connect_timeout(config.connect_timeout)
+            .build()
+            .unwrap();
+        return ApiClient {
+            base_url: config.base_url.trim_end_matches('/').into(),
+            http,
+        };
+    }
+
+    pub fn base_url(&self) -> &str {
+        return &self.base_url;
+    }
+
+    /// Sends `request` as the JSON body (POST) or query string (GET) and parses the response
+    /// envelope, whatever the status. `access_token` is sent as a bearer token when present.
+    pub async fn call<E: Endpoint>(
+        &self,
+        request: &E::Request,
+        access_token: Option<&str>,
+    ) -> Result<ApiResponse<E::Response>, ApiError> {
+        let url = format!("{}{}", self.base_url, E::path_for(request));
+        let builder = match E::METHOD {
+            Method::Get => self.http.get(url).query(request),
+            Method::Post => self
+                .http
+                .post(url)
+                .header(CONTENT_TYPE, "application/json")
+                .body(serde_json::to_string(request).unwrap()),
+            Method::Patch => self
+                .http
+                .patch(url)
+                .header(CONTENT_TYPE, "application/json")
+                .body(serde_json::to_string(request).unwrap()),
+        };
+        let builder = match access_token {
+            Some(token) => builder.bearer_auth(token),
+            None => builder,
+        };
+
+        let response = builder.send().await?;
+        let status = response.status().as_u16();
+        let text = response.text().await?;
+        let body = serde_json::from_str::<ResponseStruct<E::Response>>(&text)
+            .map_err(|error| ApiError::Decode { status, error })?;
+        return Ok(ApiResponse { status, body });
+    }
+}
@@ -1,35 +1,26 @@
-use std::{
-    thread,
-};
-use shared::{ResponseStruct, routes::auth::{login::{LoginRequest, LoginResponse}, refresh::RefreshResponse}};
+use shared::{api::Login, routes::auth::{login::{LoginRequest, LoginResponse}, refresh::RefreshResponse}};
 use ui::{
     components::{
         common::{Alignment, Component, Length},
         layout::Layout,
         text_layout::TextLayout,
     },
-    raylib::color::Color,
 };
 
-use crate::{UI_REBUILD_SIGNAL_SEND, app::auth::login_store::{LoginPageState, LoginState}, utils::{
-    fetch::{ClientModes, public_fetch}, popup::popup, router::{Route, Router}, session::Session, state::as_state, text_input::{TextInputType, text_input}
+use crate::{app::auth::login_store::{LoginPageState, LoginState}, utils::{
+    errors::error_message, fetch::fetch, popup::popup, router::{Route, Router}, session::Session, state::as_state, text_input::{TextInputType, text_input}, theme::Theme
 }};
 
 
 fn execute_login() {
     LoginState::set_loading(true);
-    let username = LoginState::username();
-    let password = LoginState::password();
-    thread::spawn(|| {
-        let req_body = LoginRequest {
-            email: username.into(),
-            password: password.into(),
-        };
-        let res = public_fetch(ClientModes::POST, "/auth/login", &Some(req_body));
+    let req_body = LoginRequest {
+        email: LoginState::username(),
+        password: LoginState::password(),
+    };
+    fetch::<Login>(req_body, |res| {
         match res {
-            Ok(body) => {
-                let body_text = body.text().unwrap();
-                let body_data = serde_json::from_str::<ResponseStruct<LoginResponse>>(&body_text).unwrap();
+            Ok(body_data) => {
                 if body_data.success {
                     let LoginResponse {access_token,refresh_token} = body_data.data.unwrap();
                     Session::set_token(RefreshResponse{
@@ -38,18 +29,14 @@ fn execute_login() {
                     });
                     Router::push("dashboard/conversations");
                 }else{
-                    let message = body_data.message;
-                    LoginState::set_error(Some(message));
-                    LoginState::set_loading(false);
+                    LoginState::set_error(Some(error_message(&body_data)));
                 }
             }
             Err(e) => {
-                LoginState::set_loading(false);
                 LoginState::set_error(Some(e.into()));
             }
         }
         LoginState::set_loading(false);
-        UI_REBUILD_SIGNAL_SEND.get().unwrap().send(()).unwrap();
     });
 }
 
@@ -95,11 +82,11 @@ fn login_page() -> Component {
                 execute_login();
                 false
             }))
-            .bg_color(Color::BEIGE)
+            .bg_color(Theme::palette().surface)
             .build(),
         TextLayout::get_builder()
             .padding((5, 5, 5, 5))
-            .bg_color(Color::BEIGE)
+            .bg_color(Theme::palette().surface)
             .dim((Length::FIT, Length::FIT))
             .wrap(false)
             .content("Signup Instead")
@@ -139,7 +126,7 @@ fn login_page() -> Component {
 
     return Layout::get_col_builder()
         .dim((Length::FILL, Length::FILL))
-        .bg_color(Color::RED)
+        .bg_color(Theme::palette().panel)
         .flex(9.5)
         .cross_align(Alignment::Center)
         .padding((10, 10, 10, 10))
@@ -1,12 +1,12 @@
 use ui::{components::{
     common::{Alignment, Component, Length},
     layout::Layout,
-}, raylib::color::Color};
+}};
 
 use crate::{
     app::auth::{login::login_route, signup::signup_route},
     no_op,
-    utils::router::{Route, outlet},
+    utils::{router::{Route, outlet}, theme::Theme},
 };
 
 mod login;
@@ -15,7 +15,7 @@ mod signup;
 mod signup_store;
 fn auth_screen() -> Component {
     Layout::get_row_builder()
-        .bg_color(Color::WHEAT)
+        .bg_color(Theme::palette().backdrop)
         .main_align(Alignment::Center)
         .children(vec![
             Layout::get_col_builder()
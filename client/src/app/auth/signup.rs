@@ -1,58 +1,45 @@
-use shared::{
-    ResponseStruct,
-    routes::auth::signup::{SignupRequest, SignupResponse},
-};
-use std::thread;
+use shared::{api::Signup, routes::auth::signup::SignupRequest};
 use ui::{
     components::{
         common::{Alignment, Component, Length},
         layout::Layout,
         text_layout::TextLayout,
     },
-    raylib::color::Color,
 };
 
 use crate::{
-    UI_REBUILD_SIGNAL_SEND,
     app::auth::signup_store::{SignupPageState, SignupState},
     no_op,
     utils::{
-        fetch::{ClientModes, fetch, public_fetch},
+        errors::error_message,
+        fetch::fetch,
         popup::popup,
         router::{Route, Router},
         state::as_state,
         text_input::{TextInputType, text_input},
+        theme::Theme,
     },
 };
 
 fn execute_signup() {
     SignupState::set_loading(true);
-    let username = SignupState::username();
-    let password = SignupState::password();
-    thread::spawn(|| {
-        let req_body = SignupRequest {
-            email: username.into(),
-            password: password.into(),
-        };
-        let res = public_fetch(ClientModes::POST, "/auth/signup", &Some(req_body));
-        match res {
-            Ok(res) => {
-                let body = res.text().unwrap();
-                println!("Signup response body: {}", body);
-                let res = serde_json::from_str::<ResponseStruct<SignupResponse>>(&body).unwrap();
-                if res.success {
-                    Router::push("auth/login");
-                } else {
-                    SignupState::set_error(Some(res.message));
-                    SignupState::set_loading(false);
-                }
-            }
-            Err(e) => {
+    let req_body = SignupRequest {
+        email: SignupState::username(),
+        password: SignupState::password(),
+    };
+    fetch::<Signup>(req_body, |res| match res {
+        Ok(res) => {
+            if res.success {
+                Router::push("auth/login");
+            } else {
+                SignupState::set_error(Some(error_message(&res)));
                 SignupState::set_loading(false);
-                SignupState::set_error(Some(e.into()));
             }
         }
-        UI_REBUILD_SIGNAL_SEND.get().unwrap().send(()).unwrap();
+        Err(e) => {
+            SignupState::set_loading(false);
+            SignupState::set_error(Some(e.into()));
+        }
     });
 }
 
@@ -101,11 +88,11 @@ fn signup_page() -> Component {
                 execute_signup();
                 false
             }))
-            .bg_color(Color::BEIGE)
+            .bg_color(Theme::palette().surface)
             .build(),
         TextLayout::get_builder()
             .padding((5, 5, 5, 5))
-            .bg_color(Color::BEIGE)
+            .bg_color(Theme::palette().surface)
             .dim((Length::FIT, Length::FIT))
             .wrap(false)
             .content("Login Instead")
@@ -145,7 +132,7 @@ fn signup_page() -> Component {
 
     return Layout::get_col_builder()
         .dim((Length::FILL, Length::FILL))
-        .bg_color(Color::RED)
+        .bg_color(Theme::palette().panel)
         .flex(9.5)
         .cross_align(Alignment::Center)
         .padding((10, 10, 10, 10))
@@ -0,0 +1,232 @@
+use shared::{
+    api::{GetMe, ListContacts, RemoveContact, RespondContact, UpdateMe},
+    routes::users::{
+        contacts::{Contact, ContactRequest, ContactStatus, RespondContactRequest},
+        profile::UpdateProfileRequest,
+    },
+};
+use ui::components::{
+    common::{Alignment, Component, Length},
+    layout::Layout,
+    text_layout::TextLayout,
+};
+
+use crate::utils::{errors::error_message, fetch::fetch, router::Route, theme::Theme};
+
+use super::contacts_store::ContactsState;
+
+fn load_contacts() {
+    fetch::<ListContacts>((), |res| {
+        match res {
+            Ok(res_json) => {
+                if res_json.success {
+                    ContactsState::set_contacts(res_json.data.unwrap().contacts);
+                } else {
+                    ContactsState::set_error(Some(error_message(&res_json)));
+                }
+            }
+            Err(e) => {
+                ContactsState::set_error(Some(e.into()));
+            }
+        }
+        ContactsState::set_loading(false);
+    });
+}
+
+fn load_contacts_only() {
+    fetch::<GetMe>((), |res| {
+        if let Ok(res_json) = res {
+            if let Some(me) = res_json.data {
+                ContactsState::set_contacts_only(me.contacts_only_dms);
+            }
+        }
+    });
+}
+
+fn set_contacts_only(contacts_only: bool) {
+    let req_body = UpdateProfileRequest { contacts_only_dms: Some(contacts_only), ..Default::default() };
+    fetch::<UpdateMe>(req_body, |res| match res {
+        Ok(res_json) => {
+            if res_json.success {
+                ContactsState::set_contacts_only(res_json.data.unwrap().contacts_only_dms);
+            } else {
+                ContactsState::set_error(Some(error_message(&res_json)));
+            }
+        }
+        Err(e) => {
+            ContactsState::set_error(Some(e.into()));
+        }
+    });
+}
+
+fn respond(user_id: i32, accept: bool) {
+    fetch::<RespondContact>(RespondContactRequest { user_id, accept }, |res| match res {
+        Ok(res_json) => {
+            if res_json.success {
+                ContactsState::upsert(res_json.data.unwrap());
+            } else {
+                ContactsState::set_error(Some(error_message(&res_json)));
+            }
+        }
+        Err(e) => {
+            ContactsState::set_error(Some(e.into()));
+        }
+    });
+}
+
+/// Removes a contact, withdraws a sent request or forgets a received one.
+fn remove(user_id: i32) {
+    fetch::<RemoveContact>(ContactRequest { user_id }, move |res| match res {
+        Ok(res_json) => {
+            if res_json.success {
+                ContactsState::remove(user_id);
+            } else {
+                ContactsState::set_error(Some(error_message(&res_json)));
+            }
+        }
+        Err(e) => {
+            ContactsState::set_error(Some(e.into()));
+        }
+    });
+}
+
+fn row_button(label: &str, on_click: impl Fn() + 'static) -> Component {
+    TextLayout::get_builder()
+        .dim((Length::FIT, Length::FIT))
+        .padding((5, 10, 5, 10))
+        .content(label)
+        .font_size(20)
+        .bg_color(Theme::palette().muted)
+        .on_click(Box::new(move |_| {
+            on_click();
+            false
+        }))
+        .build()
+}
+
+fn contact_row(contact: &Contact) -> Component {
+    let user_id = contact.user.id;
+    let label = match &contact.user.display_name {
+        Some(_) => format!("{} (@{})", contact.user.name(), contact.user.username),
+        None => contact.user.username.clone(),
+    };
+    let mut children = vec![
+        TextLayout::get_builder()
+            .dim((Length::FILL, Length::FIT))
+            .content(&label)
+            .font_size(24)
+            .build() as Component,
+    ];
+    match (contact.status, contact.incoming) {
+        (ContactStatus::Accepted, _) => children.push(row_button("Remove", move || remove(user_id))),
+        (ContactStatus::Pending, true) => {
+            children.push(row_button("Accept", move || respond(user_id, true)));
+            children.push(row_button("Decline", move || respond(user_id, false)));
+        }
+        (ContactStatus::Declined, true) => {
+            children.push(row_button("Accept", move || respond(user_id, true)));
+            children.push(row_button("Forget", move || remove(user_id)));
+        }
+        (_, false) => children.push(row_button("Cancel", move || remove(user_id))),
+    }
+    Layout::get_row_builder()
+        .dim((Length::FILL, Length::FIT))
+        .bg_color(Theme::palette().accent)
+        .cross_align(Alignment::Center)
+        .padding((5, 10, 5, 10))
+        .gap(10)
+        .children(children)
+        .build()
+}
+
+fn section(title: &str, contacts: Vec<&Contact>) -> Vec<Component> {
+    if contacts.is_empty() {
+        return vec![];
+    }
+    let mut children = vec![
+        TextLayout::get_builder()
+            .dim((Length::FILL, Length::FIT))
+            .content(title)
+            .font_size(28)
+            .build() as Component,
+    ];
+    children.extend(contacts.into_iter().map(contact_row));
+    children
+}
+
+fn settings_row(contacts_only: Option<bool>) -> Component {
+    let mut children = vec![
+        TextLayout::get_builder()
+            .dim((Length::FILL, Length::FIT))
+            .content("Only contacts can start conversations with me")
+            .font_size(20)
+            .build() as Component,
+    ];
+    if let Some(contacts_only) = contacts_only {
+        let label = if contacts_only { "On" } else { "Off" };
+        children.push(row_button(label, move || set_contacts_only(!contacts_only)));
+    }
+    Layout::get_row_builder()
+        .dim((Length::FILL, Length::FIT))
+        .bg_color(Theme::palette().muted)
+        .cross_align(Alignment::Center)
+        .padding((5, 10, 5, 10))
+        .children(children)
+        .build()
+}
+
+fn contacts_layout() -> Component {
+    let state = ContactsState::read_state();
+    let mut children = vec![settings_row(state.contacts_only)];
+    if let Some(err) = state.error {
+        children.push(
+            TextLayout::get_builder()
+                .content(&format!("Error: {}", err))
+                .font_size(20)
+                .build(),
+        );
+    } else if state.loading {
+        children.push(
+            TextLayout::get_builder()
+                .content("Loading...")
+                .font_size(20)
+                .build(),
+        );
+    } else if state.contacts.is_empty() {
+        children.push(
+            TextLayout::get_builder()
+                .content("No contacts yet. Add some from Search.")
+                .font_size(20)
+                .build(),
+        );
+    } else {
+        let contacts = &state.contacts;
+        children.extend(section("Requests", contacts.iter().filter(|c| c.incoming && c.status != ContactStatus::Accepted).collect()));
+        children.extend(section("Contacts", contacts.iter().filter(|c| c.status == ContactStatus::Accepted).collect()));
+        children.extend(section("Sent", contacts.iter().filter(|c| !c.incoming && c.status != ContactStatus::Accepted).collect()));
+    }
+    Layout::get_col_builder()
+        .dim((Length::FillPer(50), Length::FILL))
+        .bg_color(Theme::palette().surface)
+        .main_align(Alignment::Start)
+        .padding((10, 10, 10, 10))
+        .gap(10)
+        .children(children)
+        .build()
+}
+
+pub fn contacts_route() -> Route {
+    Route::leaf(
+        "contacts",
+        Box::new(|| {
+            ContactsState::init();
+            ContactsState::set_loading(true);
+            load_contacts_only();
+            load_contacts();
+        }),
+        Box::new(|| {
+            ContactsState::de_init();
+        }),
+        Box::new(|| contacts_layout()),
+    )
+}
@@ -0,0 +1,106 @@
+use std::sync::{OnceLock, RwLock};
+use shared::routes::users::contacts::Contact;
+
+pub struct ContactsPageState {
+    pub contacts: Vec<Contact>,
+    /// Mirrors the signed-in user's `contacts_only_dms` setting once fetched.
+    pub contacts_only: Option<bool>,
+    pub loading: bool,
+    pub error: Option<String>,
+}
+
+impl ContactsPageState {
+    fn new() -> Self {
+        Self {
+            contacts: vec![],
+            contacts_only: None,
+            loading: false,
+            error: None,
+        }
+    }
+}
+
+static CONTACTS_PAGE_STATE: OnceLock<RwLock<Option<ContactsPageState>>> = OnceLock::new();
+
+pub struct ContactsState;
+
+impl ContactsState {
+    pub fn init() {
+        match CONTACTS_PAGE_STATE.get() {
+            Some(v) => {
+                let has_state = {
+                    let state = v.read().unwrap();
+                    state.is_some()
+                };
+                if !has_state {
+                    let mut state = v.write().unwrap();
+                    state.replace(ContactsPageState::new());
+                }
+            }
+            None => {
+                CONTACTS_PAGE_STATE
+                    .set(RwLock::new(Some(ContactsPageState::new())))
+                    .ok()
+                    .unwrap();
+            }
+        }
+    }
+
+    pub fn de_init() {
+        match CONTACTS_PAGE_STATE.get() {
+            Some(v) => {
+                let mut state = v.write().unwrap();
+                state.take();
+            }
+            None => {}
+        }
+    }
+
+    /// Runs `update` when the page is still open; responses may arrive after it closed.
+    fn update(update: impl FnOnce(&mut ContactsPageState)) {
+        if let Some(v) = CONTACTS_PAGE_STATE.get() {
+            if let Some(state) = v.write().unwrap().as_mut() {
+                update(state);
+            }
+        }
+    }
+
+    pub fn set_contacts(contacts: Vec<Contact>) {
+        Self::update(|state| state.contacts = contacts);
+    }
+
+    /// Replaces the entry for the same user, or adds it.
+    pub fn upsert(contact: Contact) {
+        Self::update(|state| {
+            state.contacts.retain(|c| c.user.id != contact.user.id);
+            state.contacts.push(contact);
+        });
+    }
+
+    pub fn remove(user_id: i32) {
+        Self::update(|state| state.contacts.retain(|c| c.user.id != user_id));
+    }
+
+    pub fn set_contacts_only(contacts_only: bool) {
+        Self::update(|state| state.contacts_only = Some(contacts_only));
+    }
+
+    pub fn set_loading(is_loading: bool) {
+        Self::update(|state| state.loading = is_loading);
+    }
+
+    pub fn set_error(new_error: Option<String>) {
+        Self::update(|state| state.error = new_error);
+    }
+
+    pub fn read_state() -> ContactsPageState {
+        let state = CONTACTS_PAGE_STATE.get().unwrap().read().unwrap();
+        let state = state.as_ref().unwrap();
+        ContactsPageState {
+            contacts: state.contacts.clone(),
+            contacts_only: state.contacts_only,
+            loading: state.loading,
+            error: state.error.clone(),
+        }
+    }
+}
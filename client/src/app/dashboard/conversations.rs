@@ -1,25 +1,297 @@
-use ui::{components::{common::Component, layout::Layout, text_layout::TextLayout}, raylib::color::Color};
+use shared::{
+    ResponseStruct,
+    api::{BlockUser, GetMe, ListBlocked, ListConversations, MuteConversation, UnblockUser},
+    routes::{
+        chat::conversation::{ConversationListItem, ConversationMember, MuteConversationRequest},
+        users::{block::BlockRequest, presence::PresenceStatus},
+    },
+};
+use ui::components::{
+    common::{Alignment, Component, Length},
+    layout::Layout,
+    text_layout::TextLayout,
+};
 
-use crate::{no_op, utils::router::Route};
+use crate::utils::{errors::error_message, fetch::{NetErr, fetch}, router::Route, theme::Theme};
 
-fn conversation_layout() -> Component {
-    Layout::get_col_builder()
+use super::conversations_store::ConversationsState;
+
+/// Fetches the conversation list into the page state. Also run by every heartbeat while the page
+/// is open, which is what keeps the presence dots current.
+pub fn load_conversations() {
+    fetch::<ListConversations>((), |res| {
+        match res {
+            Ok(res_json) => {
+                if res_json.success {
+                    ConversationsState::set_conversations(res_json.data.unwrap().conversations);
+                    ConversationsState::set_error(None);
+                } else {
+                    ConversationsState::set_error(Some(error_message(&res_json)));
+                }
+            }
+            Err(e) => {
+                ConversationsState::set_error(Some(e.into()));
+            }
+        }
+        ConversationsState::set_loading(false);
+    });
+}
+
+fn load_my_id() {
+    fetch::<GetMe>((), |res| {
+        if let Ok(res_json) = res {
+            if let Some(me) = res_json.data {
+                ConversationsState::set_my_id(me.profile.id);
+            }
+        }
+    });
+}
+
+fn load_blocked() {
+    fetch::<ListBlocked>((), |res| {
+        if let Ok(res_json) = res {
+            if let Some(blocked) = res_json.data {
+                ConversationsState::set_blocked(blocked.users.iter().map(|u| u.id).collect());
+            }
+        }
+    });
+}
+
+fn set_muted(conversation_id: i32, muted: bool) {
+    fetch::<MuteConversation>(MuteConversationRequest { conversation_id, muted }, move |res| match res {
+        Ok(res_json) => {
+            if res_json.success {
+                ConversationsState::set_muted(conversation_id, muted);
+            } else {
+                ConversationsState::set_error(Some(error_message(&res_json)));
+            }
+        }
+        Err(e) => {
+            ConversationsState::set_error(Some(e.into()));
+        }
+    });
+}
+
+fn block_changed(res: Result<ResponseStruct<()>, NetErr>) {
+    match res {
+        Ok(res_json) => {
+            if res_json.success {
+                load_blocked();
+            } else {
+                ConversationsState::set_error(Some(error_message(&res_json)));
+            }
+        }
+        Err(e) => {
+            ConversationsState::set_error(Some(e.into()));
+        }
+    }
+}
+
+fn set_blocked(user_id: i32, blocked: bool) {
+    if blocked {
+        fetch::<BlockUser>(BlockRequest { user_id }, block_changed);
+    } else {
+        fetch::<UnblockUser>(BlockRequest { user_id }, block_changed);
+    }
+}
+
+fn presence_dot(status: PresenceStatus) -> Component {
+    let color = match status {
+        PresenceStatus::Online => Theme::palette().online,
+        PresenceStatus::Away => Theme::palette().away,
+        PresenceStatus::Offline => Theme::palette().offline,
+    };
+    Layout::get_row_builder()
+        .dim((Length::FIXED(10), Length::FIXED(10)))
+        .bg_color(color)
+        .build()
+}
+
+fn member_row(member: &ConversationMember) -> Component {
+    Layout::get_row_builder()
+        .dim((Length::FILL, Length::FIT))
+        .cross_align(Alignment::Center)
+        .gap(6)
         .children(vec![
+            presence_dot(member.presence.status),
             TextLayout::get_builder()
-                .content("Conversations Page")
-                .text_color(Color::WHITE)
-                .font_size(32)
+                .dim((Length::FILL, Length::FIT))
+                .content(member.name())
+                .font_size(18)
                 .build(),
-            // Additional conversation components can be added here
         ])
         .build()
 }
 
+fn conversation_entry(conversation: &ConversationListItem, my_id: Option<i32>) -> Component {
+    let others = conversation
+        .members
+        .iter()
+        .filter(|m| Some(m.user_id) != my_id)
+        .collect::<Vec<_>>();
+    let mut children = vec![];
+    let title = match (&conversation.title, conversation.muted) {
+        (Some(title), true) => Some(format!("{title} (muted)")),
+        (Some(title), false) => Some(title.clone()),
+        (None, true) => Some("(muted)".to_string()),
+        (None, false) => None,
+    };
+    if let Some(title) = title {
+        children.push(
+            TextLayout::get_builder()
+                .dim((Length::FILL, Length::FIT))
+                .content(&title)
+                .font_size(22)
+                .build() as Component,
+        );
+    }
+    children.extend(others.into_iter().map(member_row));
+    let id = conversation.id;
+    // Muted conversations are dimmed as well as listed last.
+    let background = if conversation.muted { Theme::palette().muted } else { Theme::palette().accent };
+    Layout::get_col_builder()
+        .dim((Length::FILL, Length::FIT))
+        .bg_color(background)
+        .padding((5, 10, 5, 10))
+        .gap(4)
+        .on_click(Box::new(move |_| {
+            ConversationsState::set_selected(id);
+            false
+        }))
+        .children(children)
+        .build()
+}
+
+fn header_button(label: &str, on_click: impl Fn() + 'static) -> Component {
+    TextLayout::get_builder()
+        .dim((Length::FIT, Length::FIT))
+        .padding((5, 10, 5, 10))
+        .content(label)
+        .font_size(20)
+        .bg_color(Theme::palette().muted)
+        .on_click(Box::new(move |_| {
+            on_click();
+            false
+        }))
+        .build()
+}
+
+/// Names the conversation and offers muting it, plus blocking the other member of a direct one.
+fn conversation_header(conversation: &ConversationListItem, my_id: Option<i32>, blocked: &[i32]) -> Component {
+    let others = conversation
+        .members
+        .iter()
+        .filter(|m| Some(m.user_id) != my_id)
+        .collect::<Vec<_>>();
+    let name = match &conversation.title {
+        Some(title) => title.clone(),
+        None => others.iter().map(|m| m.name()).collect::<Vec<_>>().join(", "),
+    };
+    let (id, muted) = (conversation.id, conversation.muted);
+    let mut children = vec![
+        TextLayout::get_builder()
+            .dim((Length::FILL, Length::FIT))
+            .content(&name)
+            .font_size(28)
+            .build() as Component,
+        header_button(if muted { "Unmute" } else { "Mute" }, move || set_muted(id, !muted)),
+    ];
+    if let [other] = others.as_slice() {
+        let (user_id, is_blocked) = (other.user_id, blocked.contains(&other.user_id));
+        let label = if is_blocked { format!("Unblock {}", other.name()) } else { format!("Block {}", other.name()) };
+        children.push(header_button(&label, move || set_blocked(user_id, !is_blocked)));
+    }
+    Layout::get_row_builder()
+        .dim((Length::FILL, Length::FIT))
+        .bg_color(Theme::palette().surface)
+        .cross_align(Alignment::Center)
+        .padding((5, 10, 5, 10))
+        .gap(10)
+        .children(children)
+        .build()
+}
+
+fn sidebar() -> Component {
+    let state = ConversationsState::read_state();
+    let children = if let Some(err) = state.error {
+        vec![
+            TextLayout::get_builder()
+                .content(&format!("Error: {}", err))
+                .font_size(20)
+                .build() as Component,
+        ]
+    } else if state.loading && state.conversations.is_empty() {
+        vec![
+            TextLayout::get_builder()
+                .content("Loading...")
+                .font_size(20)
+                .build() as Component,
+        ]
+    } else if state.conversations.is_empty() {
+        vec![
+            TextLayout::get_builder()
+                .content("No conversations yet. Start one from Search.")
+                .wrap(true)
+                .font_size(20)
+                .build() as Component,
+        ]
+    } else {
+        state
+            .conversations
+            .iter()
+            .map(|conversation| conversation_entry(conversation, state.my_id))
+            .collect()
+    };
+    Layout::get_col_builder()
+        .dim((Length::FillPer(30), Length::FILL))
+        .bg_color(Theme::palette().muted)
+        .main_align(Alignment::Start)
+        .padding((5, 5, 5, 5))
+        .gap(5)
+        .children(children)
+        .build()
+}
+
+fn conversation_pane() -> Component {
+    let state = ConversationsState::read_state();
+    let selected = state.conversations.iter().find(|c| Some(c.id) == state.selected);
+    let Some(conversation) = selected else {
+        return TextLayout::get_builder()
+            .dim((Length::FILL, Length::FILL))
+            .main_align(Alignment::Center)
+            .cross_align(Alignment::Center)
+            .content("Pick a conversation")
+            .text_color(Theme::palette().window_text)
+            .font_size(32)
+            .build();
+    };
+    Layout::get_col_builder()
+        .dim((Length::FILL, Length::FILL))
+        .main_align(Alignment::Start)
+        .children(vec![conversation_header(conversation, state.my_id, &state.blocked)])
+        .build()
+}
+
+fn conversation_layout() -> Component {
+    Layout::get_row_builder()
+        .dim((Length::FILL, Length::FILL))
+        .children(vec![sidebar(), conversation_pane()])
+        .build()
+}
+
 pub fn conversations_route() -> Route {
     Route::leaf(
         "conversations",
-        no_op(),
-        no_op(),
+        Box::new(|| {
+            ConversationsState::init();
+            ConversationsState::set_loading(true);
+            load_my_id();
+            load_blocked();
+            load_conversations();
+        }),
+        Box::new(|| {
+            ConversationsState::de_init();
+        }),
         Box::new(|| conversation_layout()),
     )
-}
\ No newline at end of file
+}
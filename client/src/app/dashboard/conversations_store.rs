@@ -0,0 +1,129 @@
+use std::sync::{OnceLock, RwLock};
+use shared::routes::chat::conversation::ConversationListItem;
+
+pub struct ConversationsPageState {
+    /// The signed-in user, left out of member lists once known.
+    pub my_id: Option<i32>,
+    pub conversations: Vec<ConversationListItem>,
+    /// The conversation whose header is showing.
+    pub selected: Option<i32>,
+    /// Users the signed-in user blocked.
+    pub blocked: Vec<i32>,
+    pub loading: bool,
+    pub error: Option<String>,
+}
+
+impl ConversationsPageState {
+    fn new() -> Self {
+        Self {
+            my_id: None,
+            conversations: vec![],
+            selected: None,
+            blocked: vec![],
+            loading: false,
+            error: None,
+        }
+    }
+}
+
+static CONVERSATIONS_PAGE_STATE: OnceLock<RwLock<Option<ConversationsPageState>>> = OnceLock::new();
+
+pub struct ConversationsState;
+
+impl ConversationsState {
+    pub fn init() {
+        match CONVERSATIONS_PAGE_STATE.get() {
+            Some(v) => {
+                let has_state = {
+                    let state = v.read().unwrap();
+                    state.is_some()
+                };
+                if !has_state {
+                    let mut state = v.write().unwrap();
+                    state.replace(ConversationsPageState::new());
+                }
+            }
+            None => {
+                CONVERSATIONS_PAGE_STATE
+                    .set(RwLock::new(Some(ConversationsPageState::new())))
+                    .ok()
+                    .unwrap();
+            }
+        }
+    }
+
+    pub fn de_init() {
+        match CONVERSATIONS_PAGE_STATE.get() {
+            Some(v) => {
+                let mut state = v.write().unwrap();
+                state.take();
+            }
+            None => {}
+        }
+    }
+
+    /// Whether the conversations page is showing, so background refreshes know to skip it.
+    pub fn is_open() -> bool {
+        match CONVERSATIONS_PAGE_STATE.get() {
+            Some(v) => v.read().unwrap().is_some(),
+            None => false,
+        }
+    }
+
+    /// Runs `update` when the page is still open; responses may arrive after it closed.
+    fn update(update: impl FnOnce(&mut ConversationsPageState)) {
+        if let Some(v) = CONVERSATIONS_PAGE_STATE.get() {
+            if let Some(state) = v.write().unwrap().as_mut() {
+                update(state);
+            }
+        }
+    }
+
+    pub fn set_my_id(id: i32) {
+        Self::update(|state| state.my_id = Some(id));
+    }
+
+    /// Muted conversations go last, otherwise the server's order is kept.
+    pub fn set_conversations(mut conversations: Vec<ConversationListItem>) {
+        conversations.sort_by_key(|c| c.muted);
+        Self::update(|state| state.conversations = conversations);
+    }
+
+    pub fn set_selected(id: i32) {
+        Self::update(|state| state.selected = Some(id));
+    }
+
+    pub fn set_blocked(blocked: Vec<i32>) {
+        Self::update(|state| state.blocked = blocked);
+    }
+
+    pub fn set_muted(conversation_id: i32, muted: bool) {
+        Self::update(|state| {
+            if let Some(conversation) = state.conversations.iter_mut().find(|c| c.id == conversation_id) {
+                conversation.muted = muted;
+            }
+            state.conversations.sort_by_key(|c| c.muted);
+        });
+    }
+
+    pub fn set_loading(is_loading: bool) {
+        Self::update(|state| state.loading = is_loading);
+    }
+
+    pub fn set_error(new_error: Option<String>) {
+        Self::update(|state| state.error = new_error);
+    }
+
+    pub fn read_state() -> ConversationsPageState {
+        let state = CONVERSATIONS_PAGE_STATE.get().unwrap().read().unwrap();
+        let state = state.as_ref().unwrap();
+        ConversationsPageState {
+            my_id: state.my_id,
+            conversations: state.conversations.clone(),
+            selected: state.selected,
+            blocked: state.blocked.clone(),
+            loading: state.loading,
+            error: state.error.clone(),
+        }
+    }
+}
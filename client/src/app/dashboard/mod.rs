@@ -6,20 +6,24 @@ use ui::{
         layout::Layout,
         text_layout::TextLayout,
     },
-    raylib::color::Color,
 };
 
 use crate::{
-    app::dashboard::{conversations::conversations_route, search::search_route}, no_op, utils::{router::{Route, Router, outlet}, session::Session}
+    app::dashboard::{contacts::contacts_route, conversations::conversations_route, search::search_route}, no_op, utils::{router::{Route, Router, outlet}, session::Session, theme::Theme}
 };
 
 mod search;
 mod search_store;
 mod conversations;
+mod conversations_store;
+mod presence;
+mod contacts;
+mod contacts_store;
 #[derive(Clone,Copy,PartialEq)]
 pub enum Menu {
     Conversations,
-    Search
+    Search,
+    Contacts,
 }
 
 struct DashboardStateT {
@@ -94,7 +98,7 @@ fn menu_bar() -> Component {
     let current_menu = DashboardState::menu();
 
     Layout::get_row_builder()
-        .bg_color(Color::LIGHTGRAY)
+        .bg_color(Theme::palette().muted)
         .dim((Length::FILL, Length::FILL))
         .flex(4.0)
         .padding((5,5,5,5))
@@ -108,9 +112,9 @@ fn menu_bar() -> Component {
                 .font_size(18)
                 .bg_color({
                     if current_menu == Menu::Conversations {
-                        Color::GRAY
+                        Theme::palette().selected
                     } else {
-                        Color::LIGHTGRAY
+                        Theme::palette().muted
                     }
                 })
                 .on_click(Box::new(|_|{
@@ -128,9 +132,9 @@ fn menu_bar() -> Component {
                 .font_size(18)
                 .bg_color({
                     if current_menu == Menu::Search {
-                        Color::GRAY
+                        Theme::palette().selected
                     } else {
-                        Color::LIGHTGRAY
+                        Theme::palette().muted
                     }
                 })
                 .padding((5,2,5,2))
@@ -142,6 +146,40 @@ fn menu_bar() -> Component {
                 }))
                 .font_size(24)
                 .build(),
+            TextLayout::get_builder()
+                .dim((Length::FIT,Length::FILL))
+                .main_align(Alignment::Center)
+                .content("Contacts")
+                .bg_color({
+                    if current_menu == Menu::Contacts {
+                        Theme::palette().selected
+                    } else {
+                        Theme::palette().muted
+                    }
+                })
+                .padding((5,2,5,2))
+                .on_click(Box::new(|_|{
+                    Router::push("dashboard/contacts");
+                    DashboardState::set_menu(Menu::Contacts);
+                    false
+                }))
+                .font_size(24)
+                .build(),
+            TextLayout::get_builder()
+                .dim((Length::FIT,Length::FILL))
+                .main_align(Alignment::Center)
+                .content("Logout")
+                .bg_color(Theme::palette().muted)
+                .padding((5,2,5,2))
+                .on_click(Box::new(|_|{
+                    // Also removes the saved session file.
+                    Session::set_access(None);
+                    Session::set_refresh(None);
+                    Router::set("auth/login");
+                    false
+                }))
+                .font_size(24)
+                .build(),
         ])
         .build()
 }
@@ -158,12 +196,13 @@ pub fn dashboard_route() -> Route {
         "dashboard",
         Box::new(||{
             DashboardState::init();
+            presence::start_heartbeats();
         }),
         Box::new(||{
             DashboardState::de_init();
         }),
         "dashboard_outlet",
         Box::new(|| dashboard()),
-        vec![search_route(),conversations_route()],
+        vec![search_route(),conversations_route(),contacts_route()],
     )
 }
@@ -0,0 +1,38 @@
+use std::{sync::Once, time::Duration};
+
+use shared::{api::Heartbeat, routes::users::presence::HeartbeatRequest};
+use ui::components::root::UIRoot;
+
+use crate::utils::{fetch::{Net, fetch}, session::Session};
+
+use super::{conversations::load_conversations, conversations_store::ConversationsState};
+
+/// Well within the server's default 90 second heartbeat timeout.
+const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(30);
+/// Without input for this long, heartbeats say the user is idle and the server shows them away.
+const IDLE_AFTER: Duration = Duration::from_secs(120);
+
+static HEARTBEATS: Once = Once::new();
+
+fn beat() {
+    if Session::get_tokens().0.is_none() {
+        return;
+    }
+    let active = UIRoot::idle_time() < IDLE_AFTER;
+    fetch::<Heartbeat>(HeartbeatRequest { active }, |res| {
+        if let Err(e) = res {
+            let e: String = e.into();
+            println!("Heartbeat failed: {e}");
+        }
+        if ConversationsState::is_open() {
+            load_conversations();
+        }
+    });
+}
+
+/// Beats now, and from then on every [`HEARTBEAT_INTERVAL`] for as long as the client runs, which
+/// keeps the signed-in user online and the presence dots of an open conversation list fresh.
+pub fn start_heartbeats() {
+    beat();
+    HEARTBEATS.call_once(|| Net::every(HEARTBEAT_INTERVAL, beat));
+}
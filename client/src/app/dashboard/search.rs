@@ -1,8 +1,6 @@
-use std::thread;
-
 use shared::{
-    ResponseStruct,
-    routes::{chat::conversation::{CreateConversationRequest, CreateConversationResponse}, users::search::{SearchQuery, SearchUserResult}},
+    api::{BlockUser, CreateConversation, RequestContact, SearchUsers},
+    routes::{chat::conversation::CreateConversationRequest, users::{block::BlockRequest, contacts::ContactRequest, search::SearchQuery}},
 };
 use ui::{
     components::{
@@ -10,94 +8,114 @@ use ui::{
         layout::Layout,
         text_layout::TextLayout,
     },
-    raylib::color::Color,
 };
 
 use crate::{
-    UI_REBUILD_SIGNAL_SEND,
     utils::{
-        fetch::{ClientModes, fetch},
+        errors::error_message,
+        fetch::fetch,
         router::Route,
         state::as_state,
         text_input::{TextInputType, text_input},
+        theme::Theme,
     },
 };
 
 use super::search_store::SearchState;
 
-fn execute_search() {
+/// Runs a new search, or with `cursor` fetches the next page of the current one.
+fn execute_search(cursor: Option<String>) {
     let query = SearchState::search_query();
     if query.trim().is_empty() {
         return;
     }
+    let more = cursor.is_some();
     SearchState::set_loading(true);
-    thread::spawn(move || {
-        let res = fetch(
-            ClientModes::GET,
-            "/users/search",
-            &Some(SearchQuery {
-                name: query.to_string(),
-                limit: 20,
-                page: 1,
-            }),
-        );
+    let search_query = SearchQuery {
+        name: query.to_string(),
+        limit: 20,
+        cursor,
+    };
+    fetch::<SearchUsers>(search_query, move |res| {
         match res {
-            Ok(response) => {
-                let text = response.text().unwrap();
-                let res_json =
-                    serde_json::from_str::<ResponseStruct<SearchUserResult>>(&text).unwrap();
+            Ok(res_json) => {
                 if res_json.success {
                     let result = res_json.data.unwrap();
-                    println!("Search result text");
-                    println!("{text}");
-                    SearchState::set_results(result.result);
+                    if more {
+                        SearchState::add_results(result.result);
+                    } else {
+                        SearchState::set_results(result.result);
+                    }
+                    SearchState::set_next_cursor(result.next_cursor);
                 } else {
                     SearchState::set_results(vec![]);
+                    SearchState::set_next_cursor(None);
                 }
             }
             Err(_) => {
                 SearchState::set_results(vec![]);
+                SearchState::set_next_cursor(None);
             }
         }
         SearchState::set_loading(false);
-        UI_REBUILD_SIGNAL_SEND.get().unwrap().send(()).unwrap();
     });
 }
 
 fn create_conversation_with_user(user_id: i32) {
-    thread::spawn(move || {
-        let res = fetch(
-            ClientModes::POST,
-            "/chat/conversation/create",
-            &Some(
-                CreateConversationRequest{
-                    participant_ids: vec![user_id],
-                }
-            )
-        );
-        match res {
-            Ok(response) => {
-                let text = response.text().unwrap();
-                let res_json =
-                    serde_json::from_str::<ResponseStruct<CreateConversationResponse>>(&text).unwrap();
-                if res_json.success {
-                    let conversation_details = res_json.data.unwrap();
-                    println!("Created conversation with ID: {}", conversation_details.conversation_id);
-                    println!("Created conversation between users: {:?}", conversation_details.members.iter().map(|v|{v.username.clone()}).collect::<Vec<_>>());
-                } else {
-                    SearchState::set_error(Some(res_json.message));
-                }
+    let req_body = CreateConversationRequest {
+        participant_ids: vec![user_id],
+        first_message: None,
+    };
+    fetch::<CreateConversation>(req_body, |res| match res {
+        Ok(res_json) => {
+            if res_json.success {
+                let conversation_details = res_json.data.unwrap();
+                println!("Created conversation with ID: {}", conversation_details.conversation_id);
+                println!("Created conversation between users: {:?}", conversation_details.members.iter().map(|v|{v.name().to_string()}).collect::<Vec<_>>());
+            } else {
+                SearchState::set_error(Some(error_message(&res_json)));
             }
-            Err(e) => {
-                SearchState::set_error(Some(e.into()));
+        }
+        Err(e) => {
+            SearchState::set_error(Some(e.into()));
+        }
+    });
+}
+
+/// Blocked users never show up in search, so the row goes away once the block succeeds.
+fn block_user(user_id: i32) {
+    fetch::<BlockUser>(BlockRequest { user_id }, move |res| match res {
+        Ok(res_json) => {
+            if res_json.success {
+                SearchState::remove_result(user_id);
+            } else {
+                SearchState::set_error(Some(error_message(&res_json)));
             }
         }
+        Err(e) => {
+            SearchState::set_error(Some(e.into()));
+        }
+    });
+}
+
+fn request_contact(user_id: i32) {
+    fetch::<RequestContact>(ContactRequest { user_id }, move |res| match res {
+        Ok(res_json) => {
+            if res_json.success {
+                SearchState::mark_requested(user_id);
+            } else {
+                SearchState::set_error(Some(error_message(&res_json)));
+            }
+        }
+        Err(e) => {
+            SearchState::set_error(Some(e.into()));
+        }
     });
 }
 
 fn search_layout() -> Component {
     Layout::get_col_builder()
-        .bg_color(Color::BEIGE)
+        .bg_color(Theme::palette().surface)
         .cross_align(Alignment::Center)
         .children(vec![search_bar(), search_results()])
         .build()
@@ -132,12 +150,12 @@ fn search_bar() -> Component {
                     if loading {
                         return false;
                     }
-                    execute_search();
+                    execute_search(None);
                     false
                 }))
                 .font_size(24)
                 .flex(8.0)
-                .bg_color(Color::LIGHTGRAY)
+                .bg_color(Theme::palette().muted)
                 .build(),
         ])
         .build()
@@ -196,7 +214,7 @@ fn search_results_results() -> Component {
             let mut children = vec![
                 Layout::get_row_builder()
                     .dim((Length::FILL, Length::FIT))
-                    .bg_color(Color::GRAY)
+                    .bg_color(Theme::palette().selected)
                     .cross_align(Alignment::Center)
                     .overflow_y(false)
                     .children(vec![TextLayout::get_builder()
@@ -212,30 +230,86 @@ fn search_results_results() -> Component {
                 results
                     .into_iter()
                     .map(|res| {
+                        let label = match &res.display_name {
+                            Some(_) => format!("{} (@{})", res.name(), res.username),
+                            None => res.username.clone(),
+                        };
+                        let user_id = res.id;
+                        let requested = SearchState::requested(user_id);
                         Layout::get_row_builder()
                             .dim((Length::FILL, Length::FIT))
-                            .bg_color(Color::CYAN)
+                            .bg_color(Theme::palette().accent)
                             .cross_align(Alignment::Center)
                             .overflow_y(false)
-                            .on_click(Box::new(move |_|{
-                                let loading = SearchState::loading();
-                                if loading {
-                                    return false;
-                                }
-                                create_conversation_with_user(res.id);
-                                false
-                            }))
-                            .children(vec![TextLayout::get_builder()
-                                .dim((Length::FILL, Length::FIT))
-                                .padding((5, 10, 5, 10))
-                                .content(&res.username)
-                                .wrap(true)
-                                .font_size(50)
-                                .build()])
+                            .children(vec![
+                                TextLayout::get_builder()
+                                    .dim((Length::FILL, Length::FIT))
+                                    .padding((5, 10, 5, 10))
+                                    .content(&label)
+                                    .wrap(true)
+                                    .font_size(50)
+                                    .flex(70.0)
+                                    .on_click(Box::new(move |_|{
+                                        let loading = SearchState::loading();
+                                        if loading {
+                                            return false;
+                                        }
+                                        create_conversation_with_user(user_id);
+                                        false
+                                    }))
+                                    .build(),
+                                TextLayout::get_builder()
+                                    .dim((Length::FILL, Length::FIT))
+                                    .padding((5, 10, 5, 10))
+                                    .main_align(Alignment::Center)
+                                    .content(if requested { "Requested" } else { "Add contact" })
+                                    .font_size(20)
+                                    .flex(15.0)
+                                    .bg_color(Theme::palette().selected)
+                                    .on_click(Box::new(move |_|{
+                                        if !requested {
+                                            request_contact(user_id);
+                                        }
+                                        false
+                                    }))
+                                    .build(),
+                                TextLayout::get_builder()
+                                    .dim((Length::FILL, Length::FIT))
+                                    .padding((5, 10, 5, 10))
+                                    .main_align(Alignment::Center)
+                                    .content("Block")
+                                    .font_size(20)
+                                    .flex(15.0)
+                                    .bg_color(Theme::palette().muted)
+                                    .on_click(Box::new(move |_|{
+                                        block_user(user_id);
+                                        false
+                                    }))
+                                    .build(),
+                            ])
                             .build() as Component
                     })
                     .collect::<Vec<Component>>(),
             );
+            if let Some(cursor) = SearchState::next_cursor() {
+                children.push(
+                    TextLayout::get_builder()
+                        .dim((Length::FILL, Length::FIT))
+                        .padding((5, 10, 5, 10))
+                        .main_align(Alignment::Center)
+                        .content("More results")
+                        .font_size(20)
+                        .bg_color(Theme::palette().muted)
+                        .on_click(Box::new(move |_|{
+                            if SearchState::loading() {
+                                return false;
+                            }
+                            execute_search(Some(cursor.clone()));
+                            false
+                        }))
+                        .build(),
+                );
+            }
 
             children
         })
@@ -4,6 +4,10 @@ use shared::routes::users::search::SearchUser;
 pub struct SearchPageState {
     pub search_query: String,
     pub results: Vec<SearchUser>,
+    /// Where the next page of results starts, when there is one.
+    pub next_cursor: Option<String>,
+    /// Results the user sent a contact request to from this page.
+    pub requested: Vec<i32>,
     pub loading: bool,
     pub error: Option<String>,
 }
@@ -13,6 +17,8 @@ impl SearchPageState {
         Self {
             search_query: String::new(),
             results: vec![],
+            next_cursor: None,
+            requested: vec![],
             loading: false,
             error: None,
         }
@@ -79,6 +85,44 @@ impl SearchState {
         state.results = new_results;
     }
 
+    /// Appends a further page of results.
+    pub fn add_results(more_results: Vec<SearchUser>) {
+        let mut state = Self::state().write().unwrap();
+        let state = state.as_mut().unwrap();
+        state.results.extend(more_results);
+    }
+
+    pub fn set_next_cursor(cursor: Option<String>) {
+        let mut state = Self::state().write().unwrap();
+        let state = state.as_mut().unwrap();
+        state.next_cursor = cursor;
+    }
+
+    pub fn next_cursor() -> Option<String> {
+        let state = Self::state().read().unwrap();
+        let state = state.as_ref().unwrap();
+        state.next_cursor.clone()
+    }
+
+    /// Drops a user from the results, e.g. once they are blocked.
+    pub fn remove_result(user_id: i32) {
+        let mut state = Self::state().write().unwrap();
+        let state = state.as_mut().unwrap();
+        state.results.retain(|user| user.id != user_id);
+    }
+
+    pub fn mark_requested(user_id: i32) {
+        let mut state = Self::state().write().unwrap();
+        let state = state.as_mut().unwrap();
+        state.requested.push(user_id);
+    }
+
+    pub fn requested(user_id: i32) -> bool {
+        let state = Self::state().read().unwrap();
+        let state = state.as_ref().unwrap();
+        state.requested.contains(&user_id)
+    }
+
     pub fn set_error(new_error: Option<String>) {
         let mut state = Self::state().write().unwrap();
         let state = state.as_mut().unwrap();
@@ -115,6 +159,8 @@ impl SearchState {
         SearchPageState {
             search_query: state.search_query.clone(),
             results: state.results.clone(),
+            next_cursor: state.next_cursor.clone(),
+            requested: state.requested.clone(),
             loading: state.loading,
             error: state.error.clone(),
         }
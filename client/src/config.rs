@@ -0,0 +1,259 @@
+use std::{
+    env, fmt, fs, io,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use ui::raylib::ffi::TraceLogLevel;
+
+use crate::utils::theme::Theme;
+
+/// Settings in the order `--help` lists them: (key, description). Each one can be set as `key` in
+/// the config file, as `CLIENT_<KEY>` in the environment or as `--<key>` on the command line, with
+/// underscores written as dashes. Later sources win.
+const SETTINGS: [(&str, &str); 10] = [
+    ("server_url", "API base URL, e.g. http://localhost:3000"),
+    ("request_timeout", "Seconds a request may take in total"),
+    ("connect_timeout", "Seconds to wait for a connection to the server"),
+    ("window_width", "Window width in pixels"),
+    ("window_height", "Window height in pixels"),
+    ("font_size", "Default text size"),
+    ("font_scale", "Multiplier for every text size"),
+    ("theme", "light or dark"),
+    ("log_level", "trace, debug, info, warn, error or none"),
+    ("session_secret", "Encrypts the saved login with a key derived from this secret"),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LogLevel {
+    Trace,
+    Debug,
+    Info,
+    Warn,
+    Error,
+    None,
+}
+
+impl FromStr for LogLevel {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "trace" => Ok(LogLevel::Trace),
+            "debug" => Ok(LogLevel::Debug),
+            "info" => Ok(LogLevel::Info),
+            "warn" => Ok(LogLevel::Warn),
+            "error" => Ok(LogLevel::Error),
+            "none" => Ok(LogLevel::None),
+            other => Err(format!(
+                "unknown log level `{other}`, expected trace, debug, info, warn, error or none"
+            )),
+        }
+    }
+}
+
+impl From<LogLevel> for TraceLogLevel {
+    fn from(value: LogLevel) -> Self {
+        match value {
+            LogLevel::Trace => TraceLogLevel::LOG_TRACE,
+            LogLevel::Debug => TraceLogLevel::LOG_DEBUG,
+            LogLevel::Info => TraceLogLevel::LOG_INFO,
+            LogLevel::Warn => TraceLogLevel::LOG_WARNING,
+            LogLevel::Error => TraceLogLevel::LOG_ERROR,
+            LogLevel::None => TraceLogLevel::LOG_NONE,
+        }
+    }
+}
+
+pub struct ClientConfig {
+    pub server_url: String,
+    /// Seconds, see `ApiClientConfig::timeout`.
+    pub request_timeout: u64,
+    pub connect_timeout: u64,
+    pub window_width: i32,
+    pub window_height: i32,
+    pub font_size: i32,
+    pub font_scale: f32,
+    pub theme: Theme,
+    pub log_level: LogLevel,
+    pub session_secret: Option<String>,
+}
+
+impl Default for ClientConfig {
+    fn default() -> Self {
+        return ClientConfig {
+            server_url: "http://localhost:3000".into(),
+            request_timeout: 30,
+            connect_timeout: 5,
+            window_width: 1920,
+            window_height: 1000,
+            font_size: 24,
+            font_scale: 1.0,
+            theme: Theme::Light,
+            log_level: LogLevel::Info,
+            session_secret: None,
+        };
+    }
+}
+
+pub enum ConfigError {
+    Read { path: PathBuf, error: io::Error },
+    Parse { path: PathBuf, error: toml::de::Error },
+    /// `source` names the file, variable or flag the value came from.
+    Invalid { source: String, message: String },
+    UnknownFlag(String),
+    MissingValue(String),
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::Read { path, error } => write!(f, "can't read {}: {error}", path.display()),
+            ConfigError::Parse { path, error } => write!(f, "{} is not valid TOML: {error}", path.display()),
+            ConfigError::Invalid { source, message } => write!(f, "{source}: {message}"),
+            ConfigError::UnknownFlag(flag) => write!(f, "unknown option {flag}, see --help"),
+            ConfigError::MissingValue(flag) => write!(f, "{flag} needs a value"),
+        }
+    }
+}
+
+fn env_name(key: &str) -> String {
+    return format!("CLIENT_{}", key.to_uppercase());
+}
+
+fn flag_name(key: &str) -> String {
+    return format!("--{}", key.replace('_', "-"));
+}
+
+fn parse<T: FromStr + PartialOrd + fmt::Display>(value: &str, min: T, max: T) -> Result<T, String> {
+    let Ok(parsed) = value.parse::<T>() else {
+        return Err(format!("`{value}` is not a number"));
+    };
+    if parsed < min || parsed > max {
+        return Err(format!("{parsed} is out of range, expected {min} to {max}"));
+    }
+    return Ok(parsed);
+}
+
+impl ClientConfig {
+    /// Sets one setting from its text form, rejecting values the client can't run with.
+    fn set(&mut self, key: &str, value: &str) -> Result<(), String> {
+        match key {
+            "server_url" => {
+                let url = value.trim_end_matches('/');
+                let host = url.strip_prefix("http://").or_else(|| url.strip_prefix("https://"));
+                if host.is_none_or(|host| host.is_empty()) {
+                    return Err(format!("`{value}` is not an http:// or https:// URL"));
+                }
+                self.server_url = url.into();
+            }
+            "request_timeout" => self.request_timeout = parse(value, 1, 600)?,
+            "connect_timeout" => self.connect_timeout = parse(value, 1, 120)?,
+            "window_width" => self.window_width = parse(value, 320, 16384)?,
+            "window_height" => self.window_height = parse(value, 240, 16384)?,
+            "font_size" => self.font_size = parse(value, 8, 200)?,
+            "font_scale" => self.font_scale = parse(value, 0.25, 4.0)?,
+            "theme" => self.theme = value.parse()?,
+            "log_level" => self.log_level = value.parse()?,
+            "session_secret" => self.session_secret = Some(value.to_string()).filter(|s| !s.is_empty()),
+            other => return Err(format!("unknown setting `{other}`")),
+        }
+        return Ok(());
+    }
+
+    fn apply_file(&mut self, path: &Path) -> Result<(), ConfigError> {
+        let text = fs::read_to_string(path).map_err(|error| ConfigError::Read {
+            path: path.into(),
+            error,
+        })?;
+        let table = toml::from_str::<toml::Table>(&text).map_err(|error| ConfigError::Parse {
+            path: path.into(),
+            error,
+        })?;
+        for (key, value) in table {
+            let value = match value {
+                toml::Value::String(s) => s,
+                other => other.to_string(),
+            };
+            self.set(&key, &value).map_err(|message| ConfigError::Invalid {
+                source: format!("{} ({key})", path.display()),
+                message,
+            })?;
+        }
+        return Ok(());
+    }
+}
+
+fn usage() -> String {
+    let mut usage = String::from("Usage: client [--config <path>] [options]\n\nOptions:\n");
+    for (key, description) in SETTINGS {
+        usage += &format!("  {:<18} {description} (env {})\n", flag_name(key), env_name(key));
+    }
+    usage += &format!(
+        "\nThe config file defaults to {} (env CLIENT_CONFIG).\n",
+        default_path().map(|p| p.display().to_string()).unwrap_or("<none>".into())
+    );
+    return usage;
+}
+
+fn default_path() -> Option<PathBuf> {
+    return dirs::config_dir().map(|dir| dir.join("rocket-chat").join("client.toml"));
+}
+
+/// Builds the config from defaults, the config file, `CLIENT_*` variables and `args` (without the
+/// program name), in that order. Prints usage and exits on `--help`.
+pub fn load(args: impl IntoIterator<Item = String>) -> Result<ClientConfig, ConfigError> {
+    let mut config_path = env::var("CLIENT_CONFIG").ok().map(PathBuf::from);
+    let mut flags = vec![];
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        if arg == "--help" || arg == "-h" {
+            print!("{}", usage());
+            std::process::exit(0);
+        }
+        let (flag, inline_value) = match arg.split_once('=') {
+            Some((flag, value)) => (flag.to_string(), Some(value.to_string())),
+            None => (arg, None),
+        };
+        let key = match flag.strip_prefix("--") {
+            Some(key) => key.replace('-', "_"),
+            None => return Err(ConfigError::UnknownFlag(flag)),
+        };
+        if key != "config" && !SETTINGS.iter().any(|(name, _)| *name == key) {
+            return Err(ConfigError::UnknownFlag(flag));
+        }
+        let Some(value) = inline_value.or_else(|| args.next()) else {
+            return Err(ConfigError::MissingValue(flag));
+        };
+        if key == "config" {
+            config_path = Some(value.into());
+        } else {
+            flags.push((flag, key, value));
+        }
+    }
+
+    let mut config = ClientConfig::default();
+    match config_path {
+        // Asked for explicitly, so it has to exist.
+        Some(path) => config.apply_file(&path)?,
+        None => {
+            if let Some(path) = default_path().filter(|p| p.exists()) {
+                config.apply_file(&path)?;
+            }
+        }
+    }
+    for (key, _) in SETTINGS {
+        let name = env_name(key);
+        if let Ok(value) = env::var(&name) {
+            config
+                .set(key, &value)
+                .map_err(|message| ConfigError::Invalid { source: name, message })?;
+        }
+    }
+    for (flag, key, value) in flags {
+        config
+            .set(&key, &value)
+            .map_err(|message| ConfigError::Invalid { source: flag, message })?;
+    }
+    return Ok(config);
+}
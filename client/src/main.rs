@@ -1,18 +1,29 @@
-use std::sync::{
-    OnceLock,
-    mpsc::{self, Receiver, Sender},
+use std::{
+    sync::{
+        OnceLock,
+        mpsc::{self, Receiver, Sender},
+    },
+    time::Duration,
+};
+use api_client::ApiClientConfig;
+use ui::components::{
+    common::TextStyle,
+    root::{UIRoot, WindowOptions},
 };
-use ui::components::root::UIRoot;
 
 use crate::{
     app::app_route,
     utils::{
+        fetch::Net,
         router::{Router, build_route},
         session::Session,
+        session_file::SessionFile,
+        theme::Theme,
     },
 };
 
 mod app;
+mod config;
 mod utils;
 
 extern crate ui;
@@ -30,11 +41,28 @@ fn no_op() -> Box<dyn Fn() -> ()> {
 }
 
 fn main() {
+    let config = config::load(std::env::args().skip(1)).unwrap_or_else(|e| {
+        eprintln!("Invalid client configuration: {e}");
+        std::process::exit(2);
+    });
     let ui_rebuild_signal_recv = init_channel();
-    Session::init();
+    config.theme.init();
+    Session::init(SessionFile::default_path().map(|path| SessionFile::new(path, config.session_secret.clone())));
+    Net::init(ApiClientConfig {
+        base_url: config.server_url.clone(),
+        timeout: Duration::from_secs(config.request_timeout),
+        connect_timeout: Duration::from_secs(config.connect_timeout),
+    });
     Router::init("auth/login");
+    Net::restore_session(|| {
+        // Leaves alone anyone who already logged in by hand while the refresh was in flight.
+        if Router::current_path().first().is_some_and(|segment| segment == "auth") {
+            Router::set("dashboard/conversations");
+        }
+    });
     UIRoot::start(
         Box::new(move || {
+            Net::run_completions();
             let r = app_route();
             let path = {
                 let current_path = Router::current_path();
@@ -46,8 +74,17 @@ fn main() {
             Router::reset_path_changed();
             c
         }),
-        (1920, 1000),
-        "Raylib Rocket Chat Client",
+        WindowOptions {
+            dim: (config.window_width, config.window_height),
+            title: "Raylib Rocket Chat Client".into(),
+            background: Theme::palette().window,
+            text_style: TextStyle {
+                font_size: config.font_size,
+                font_scale: config.font_scale,
+                text_color: Theme::palette().text,
+            },
+            log_level: config.log_level.into(),
+        },
         ui_rebuild_signal_recv,
     );
 }
@@ -0,0 +1,24 @@
+use shared::{ErrorCode, ResponseStruct};
+
+/// User facing text for a failed response, picked by error code rather than the server message.
+pub fn error_message<T>(res: &ResponseStruct<T>) -> String {
+    match res.code {
+        Some(ErrorCode::InvalidCredentials) => "Wrong username or password".into(),
+        Some(ErrorCode::AccountDisabled) => "This account has been disabled".into(),
+        Some(ErrorCode::UserAlreadyExists) => "That username is already taken".into(),
+        Some(ErrorCode::ConversationInvalidMembers) => "That user no longer exists".into(),
+        Some(ErrorCode::UserBlocked) => "You blocked this user, unblock them to chat".into(),
+        Some(ErrorCode::ContactsOnly) => "This user only chats with their contacts, send a contact request first".into(),
+        Some(ErrorCode::Internal) => "Something went wrong on the server, please try again".into(),
+        Some(ErrorCode::RateLimited) => "Too many attempts, please wait a minute".into(),
+        Some(ErrorCode::ValidationFailed) => match &res.details {
+            Some(details) if !details.is_empty() => details
+                .iter()
+                .map(|d| format!("{} {}", d.field, d.message))
+                .collect::<Vec<_>>()
+                .join("\n"),
+            _ => res.message.clone(),
+        },
+        _ => res.message.clone(),
+    }
+}
@@ -1,165 +1,220 @@
-use reqwest::Error;
-use serde::Serialize;
-use shared::routes::auth::refresh::{RefreshRequest, RefreshResponse};
+use std::{
+    sync::{
+        Mutex, OnceLock,
+        mpsc::{self, Receiver, Sender},
+    },
+    time::Duration,
+};
+
+use api_client::{ApiClient, ApiClientConfig, ApiError, ApiResponse};
+use shared::{
+    ErrorCode, ResponseStruct,
+    api::{Endpoint, Refresh},
+    routes::auth::refresh::RefreshRequest,
+};
+use tokio::runtime::{self, Runtime};
+
+use crate::{
+    UI_REBUILD_SIGNAL_SEND,
+    utils::{router::Router, session::Session},
+};
+
+type Completion = Box<dyn FnOnce() + Send>;
+
+struct NetT {
+    runtime: Runtime,
+    api: ApiClient,
+    /// Held while rotating the refresh token, so requests that expire together refresh once.
+    refresh_lock: tokio::sync::Mutex<()>,
+    completion_send: Sender<Completion>,
+    completion_recv: Mutex<Receiver<Completion>>,
+}
+
+static NET: OnceLock<NetT> = OnceLock::new();
+
+/// Shared HTTP client running on a small background runtime.
+pub struct Net;
+
+impl Net {
+    fn net() -> &'static NetT {
+        NET.get().expect("Net not initialized")
+    }
 
-use crate::utils::{router::Router, session::Session};
+    pub fn init(config: ApiClientConfig) {
+        let runtime = runtime::Builder::new_multi_thread()
+            .worker_threads(2)
+            .thread_name("net")
+            .enable_all()
+            .build()
+            .unwrap();
+        let (completion_send, completion_recv) = mpsc::channel();
+        NET.set(NetT {
+            runtime,
+            api: ApiClient::new(config),
+            refresh_lock: tokio::sync::Mutex::new(()),
+            completion_send,
+            completion_recv: Mutex::new(completion_recv),
+        })
+        .ok()
+        .expect("Net already initialized");
+    }
 
-pub static BASE_URL: &str = "http://localhost:8000";
+    /// Refreshes the saved session in the background, so a returning user skips the login screen
+    /// without the first frame waiting on the server. `on_done` runs on the UI thread once the
+    /// session turned out usable; without a saved session nothing happens.
+    pub fn restore_session(on_done: impl FnOnce() + Send + 'static) {
+        let (_, refresh_token) = Session::get_tokens();
+        let Some(refresh_token) = refresh_token else {
+            return;
+        };
+        Self::net().runtime.spawn(async move {
+            let _guard = Net::net().refresh_lock.lock().await;
+            if refresh_with(refresh_token).await.is_ok() {
+                complete(Box::new(on_done));
+            }
+        });
+    }
 
-#[derive(Clone, Copy)]
-pub enum ClientModes {
-    POST,
-    GET,
+    /// Calls `tick` on the network runtime every `period`, first after one period.
+    pub fn every(period: Duration, tick: impl Fn() + Send + 'static) {
+        Self::net().runtime.spawn(async move {
+            let mut interval = tokio::time::interval_at(tokio::time::Instant::now() + period, period);
+            loop {
+                interval.tick().await;
+                tick();
+            }
+        });
+    }
+
+    /// Runs the handlers of finished requests. Called by the UI thread before each rebuild.
+    pub fn run_completions() {
+        let completions = Self::net().completion_recv.lock().unwrap();
+        while let Ok(completion) = completions.try_recv() {
+            completion();
+        }
+    }
 }
 
 pub enum NetErr {
-    Reqwest(Error),
+    Api(ApiError),
     Refresh,
+    Unauthorized(Option<ErrorCode>),
 }
 
-impl From<Error> for NetErr {
-    fn from(value: Error) -> Self {
-        NetErr::Reqwest(value)
+impl From<ApiError> for NetErr {
+    fn from(value: ApiError) -> Self {
+        NetErr::Api(value)
     }
 }
 
 impl Into<String> for NetErr {
     fn into(self) -> String {
         match self {
-            NetErr::Reqwest(error) => error.to_string(),
+            NetErr::Api(error) => error.to_string(),
             NetErr::Refresh => "Error occurred while refreshing tokens".into(),
+            NetErr::Unauthorized(_) => "Session expired, please log in again".into(),
         }
     }
 }
 
-fn get_client<Body>(
-    mode: ClientModes,
-    path: &str,
-    body: &Option<Body>,
-    access_token: Option<String>,
-) -> reqwest::blocking::RequestBuilder
-where
-    Body: Serialize,
-{
-    let client = match mode {
-        ClientModes::POST => reqwest::blocking::Client::new().post(format!("{BASE_URL}{path}")),
-        ClientModes::GET => reqwest::blocking::Client::new().get(format!("{BASE_URL}{path}")),
-    };
-
-    let client = if let Some(access_token) = access_token {
-        client.bearer_auth(access_token)
-    } else {
-        client
-    };
-
-    if let Some(body) = body {
-        match mode {
-            ClientModes::POST => {
-                let req_body = serde_json::to_string(&body).unwrap();
-                client.body(req_body)
-            }
-            ClientModes::GET => client.query(&body),
-        }
-    } else {
-        client
-    }
+fn logout() {
+    Session::set_access(None);
+    Session::set_refresh(None);
+    Router::set("auth/login");
 }
 
-fn refresh_the_token(refresh_token: Option<String>) -> Result<(), ()> {
-    match refresh_token {
-        Some(refresh_token) => {
-            let res = reqwest::blocking::Client::new()
-                .post(format!("{BASE_URL}/refresh"))
-                .body(serde_json::to_string(&RefreshRequest { refresh_token }).unwrap())
-                .send();
-
-            match res {
-                Ok(res) => {
-                    let body = res.text();
-                    match body {
-                        Ok(body) => match serde_json::from_str::<RefreshResponse>(&body) {
-                            Ok(tokens) => {
-                                Session::set_token(tokens);
-                                Ok(())
-                            }
-                            Err(e) => {
-                                println!("Error parsing json {}", e.to_string());
-                                Err(())
-                            }
-                        },
-                        Err(err) => {
-                            println!("Invalid Response from the server {}", err.to_string());
-                            Router::set("auth/login");
-                            Err(())
-                        }
-                    }
-                }
-                Err(e) => {
-                    println!("Error occurred while attempting refresh {}", e.to_string());
-                    Router::set("auth/login");
-                    Err(())
-                }
+/// Trades `refresh_token` for a new pair. A rejected token logs out; a network error keeps the
+/// session as is. Either way the session is only touched while it still holds `refresh_token`, so
+/// a login made in the meantime stays.
+async fn refresh_with(refresh_token: String) -> Result<(), NetErr> {
+    let res = Net::net()
+        .api
+        .call::<Refresh>(&RefreshRequest { refresh_token: refresh_token.clone() }, None)
+        .await;
+    match res {
+        Ok(ApiResponse {
+            body: ResponseStruct {
+                data: Some(tokens), ..
+            },
+            ..
+        }) => {
+            Session::replace_if_current(&refresh_token, Some(tokens));
+            Ok(())
+        }
+        Ok(ApiResponse { body, .. }) => {
+            // The refresh token itself is expired, revoked or invalid. Only a new login helps.
+            println!("Refresh rejected: {}", body.message);
+            let on_auth_screen = Router::current_path().first().is_some_and(|segment| segment == "auth");
+            if Session::replace_if_current(&refresh_token, None) && !on_auth_screen {
+                Router::set("auth/login");
             }
+            Err(NetErr::Unauthorized(body.code))
         }
-        None => {
-            println!("No refresh token found navigate to login");
-            Router::set("auth/login");
-            Err(())
+        Err(e) => {
+            println!("Error occurred while attempting refresh {}", e);
+            Err(e.into())
         }
     }
 }
 
-fn inner_fetch<Body>(
-    method: ClientModes,
-    path: &str,
-    body: &Option<Body>,
-    attempt: usize,
-) -> Result<reqwest::blocking::Response, NetErr>
-where
-    Body: Serialize,
-{
+/// `rejected_access` is the access token the server turned down. If another request already
+/// replaced it while this one waited for the lock, the new one is used as is.
+async fn refresh_the_token(rejected_access: Option<String>) -> Result<(), NetErr> {
+    let _guard = Net::net().refresh_lock.lock().await;
     let (access_token, refresh_token) = Session::get_tokens();
+    if access_token != rejected_access {
+        return Ok(());
+    }
+    let Some(refresh_token) = refresh_token else {
+        println!("No refresh token found navigate to login");
+        logout();
+        return Err(NetErr::Refresh);
+    };
+    refresh_with(refresh_token).await
+}
 
-    let client = get_client(method, &path, body, access_token);
-
-    let res = client.send()?;
-    if res.status().as_u16() == 401 {
-        println!("UNAUTHORIZED ATTEMPTING REFRESH");
-        if attempt < 3 {
-            match refresh_the_token(refresh_token) {
-                Ok(_) => inner_fetch(method, path, body, attempt + 1),
-                Err(_) => Err(NetErr::Refresh),
+async fn call<E: Endpoint>(request: &E::Request) -> Result<ResponseStruct<E::Response>, NetErr> {
+    let api = &Net::net().api;
+    if !E::AUTH {
+        return Ok(api.call::<E>(request, None).await?.body);
+    }
+    for _ in 0..3 {
+        let (access_token, _) = Session::get_tokens();
+        let res = api.call::<E>(request, access_token.as_deref()).await?;
+        if res.status != 401 {
+            return Ok(res.body);
+        }
+        match res.body.code {
+            Some(ErrorCode::AuthTokenExpired) => {
+                println!("ACCESS TOKEN EXPIRED ATTEMPTING REFRESH");
+                refresh_the_token(access_token).await?;
+            }
+            code => {
+                println!("UNAUTHORIZED ({:?}) NAVIGATING TO LOGIN", code);
+                logout();
+                return Err(NetErr::Unauthorized(code));
             }
-        } else {
-            println!("Max retries reached for refresh");
-            Err(NetErr::Refresh)
         }
-    } else {
-        Ok(res)
     }
+    println!("Max retries reached for refresh");
+    return Err(NetErr::Refresh);
 }
 
-
-pub fn public_fetch<Body>(
-    method: ClientModes,
-    path: &str,
-    body: &Option<Body>,
-) -> Result<reqwest::blocking::Response, NetErr>
-where
-    Body: Serialize,
-{
-    let client = get_client(method, &path, body, None);
-    let res = client.send()?;
-    Ok(res)
+/// Queues `completion` for the UI thread and wakes it up.
+fn complete(completion: Completion) {
+    Net::net().completion_send.send(completion).unwrap();
+    UI_REBUILD_SIGNAL_SEND.get().unwrap().send(()).unwrap();
 }
 
-pub fn fetch<Body>(
-    method: ClientModes,
-    path: &str,
-    body: &Option<Body>,
-) -> Result<reqwest::blocking::Response, NetErr>
-where
-    Body: Serialize,
-{
-    inner_fetch(method, path, body, 0)
+/// Calls endpoint `E` in the background. Endpoints that need auth get the session's access token
+/// and a refresh when it has expired. `on_done` runs on the UI thread, in the rebuild the response
+/// triggers.
+pub fn fetch<E: Endpoint>(
+    request: E::Request,
+    on_done: impl FnOnce(Result<ResponseStruct<E::Response>, NetErr>) + Send + 'static,
+) {
+    Net::net().runtime.spawn(async move {
+        let res = call::<E>(&request).await;
+        complete(Box::new(move || on_done(res)));
+    });
 }
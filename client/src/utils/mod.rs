@@ -2,5 +2,8 @@ pub mod router;
 pub mod state;
 pub mod text_input;
 pub mod session;
+pub mod session_file;
 pub mod fetch;
-pub mod popup;
\ No newline at end of file
+pub mod popup;
+pub mod errors;
+pub mod theme;
\ No newline at end of file
@@ -7,6 +7,8 @@ use ui::{
     raylib::color::Color,
 };
 
+use crate::utils::theme::Theme;
+
 pub fn popup(message: &str, close: Box<dyn Fn()>) -> Component {
     Layout::get_col_builder()
         .set_position(Position::Abs(0, 0))
@@ -31,7 +33,7 @@ pub fn popup(message: &str, close: Box<dyn Fn()>) -> Component {
                         .dim((Length::FILL, Length::FIT))
                         .build(),
                     TextLayout::get_builder()
-                        .bg_color(Color::BEIGE)
+                        .bg_color(Theme::palette().surface)
                         .dim((Length::FIT, Length::FIT))
                         .wrap(false)
                         .padding((5, 5, 5, 5))
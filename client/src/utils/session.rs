@@ -2,9 +2,25 @@ use std::sync::{OnceLock, RwLock};
 
 use shared::routes::auth::refresh::RefreshResponse;
 
+use crate::utils::session_file::SessionFile;
+
 struct SessionT {
     access_token: Option<String>,
     refresh_token: Option<String>,
+    /// Mirrors `refresh_token` on disk when set.
+    file: Option<SessionFile>,
+}
+
+impl SessionT {
+    fn store_refresh(&mut self, token: Option<String>) {
+        if let Some(file) = &self.file {
+            match &token {
+                Some(token) => file.save(token),
+                None => file.clear(),
+            }
+        }
+        self.refresh_token = token;
+    }
 }
 
 static SESSION: OnceLock<RwLock<SessionT>> = OnceLock::new();
@@ -17,11 +33,14 @@ impl Session {
         SESSION.get().expect("Session not initialized")
     }
 
-    pub fn init() {
+    /// Starts with the refresh token saved in `file`, if any.
+    pub fn init(file: Option<SessionFile>) {
+        let refresh_token = file.as_ref().and_then(|file| file.load());
         SESSION
             .set(RwLock::new(SessionT {
                 access_token: None,
-                refresh_token: None,
+                refresh_token,
+                file,
             }))
             .ok()
             .expect("Session already initialized");
@@ -37,12 +56,29 @@ impl Session {
     }
 
     pub fn set_refresh(token: Option<String>) {
-        Self::session().write().unwrap().refresh_token = token;
+        let mut session = Self::session().write().unwrap();
+        session.store_refresh(token);
     }
 
-    pub fn set_token(tokens: RefreshResponse) {
+    /// Swaps in `tokens`, or clears the session for `None`, but only while it still holds
+    /// `refresh_token`. A refresh that finishes late then can't undo a login made meanwhile.
+    /// Returns whether anything changed.
+    pub fn replace_if_current(refresh_token: &str, tokens: Option<RefreshResponse>) -> bool {
         let mut session = Self::session().write().unwrap();
-        session.access_token = Some(tokens.access_token);
-        session.refresh_token = Some(tokens.refresh_token);
+        if session.refresh_token.as_deref() != Some(refresh_token) {
+            return false;
+        }
+        let (access_token, refresh_token) = match tokens {
+            Some(tokens) => (Some(tokens.access_token), Some(tokens.refresh_token)),
+            None => (None, None),
+        };
+        session.store_refresh(refresh_token);
+        session.access_token = access_token;
+        true
+    }
+
+    pub fn set_token(tokens: RefreshResponse) {
+        Self::set_refresh(Some(tokens.refresh_token));
+        Self::set_access(Some(tokens.access_token));
     }
 }
@@ -0,0 +1,129 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use argon2::Argon2;
+use base64::{Engine, engine::general_purpose::STANDARD};
+use chacha20poly1305::{
+    AeadCore, KeyInit, XChaCha20Poly1305, XNonce,
+    aead::{Aead, OsRng, rand_core::RngCore},
+};
+
+const PLAIN: &str = "plain";
+const ENCRYPTED: &str = "xchacha20poly1305";
+
+/// The refresh token on disk, so a restart doesn't mean logging in again.
+///
+/// The file holds one line: `plain:<token>`, or with a secret
+/// `xchacha20poly1305:<salt>:<nonce>:<ciphertext>` (base64), keyed by Argon2 over the secret and
+/// salt. Only the owner can read or write it.
+pub struct SessionFile {
+    path: PathBuf,
+    secret: Option<String>,
+}
+
+impl SessionFile {
+    pub fn new(path: PathBuf, secret: Option<String>) -> Self {
+        SessionFile { path, secret }
+    }
+
+    pub fn default_path() -> Option<PathBuf> {
+        dirs::data_local_dir().map(|dir| dir.join("rocket-chat").join("session"))
+    }
+
+    fn key(&self, secret: &str, salt: &[u8]) -> XChaCha20Poly1305 {
+        let mut key = [0u8; 32];
+        Argon2::default()
+            .hash_password_into(secret.as_bytes(), salt, &mut key)
+            .unwrap();
+        XChaCha20Poly1305::new(&key.into())
+    }
+
+    fn encode(&self, token: &str) -> String {
+        let Some(secret) = &self.secret else {
+            return format!("{PLAIN}:{token}");
+        };
+        let mut salt = [0u8; 16];
+        OsRng.fill_bytes(&mut salt);
+        let nonce = XChaCha20Poly1305::generate_nonce(&mut OsRng);
+        let ciphertext = self
+            .key(secret, &salt)
+            .encrypt(&nonce, token.as_bytes())
+            .unwrap();
+        format!(
+            "{ENCRYPTED}:{}:{}:{}",
+            STANDARD.encode(salt),
+            STANDARD.encode(nonce),
+            STANDARD.encode(ciphertext)
+        )
+    }
+
+    fn decode(&self, contents: &str) -> Option<String> {
+        let (format, rest) = contents.trim().split_once(':')?;
+        match format {
+            PLAIN => Some(rest.to_string()),
+            ENCRYPTED => {
+                let secret = self.secret.as_ref()?;
+                let mut parts = rest.split(':').map(|part| STANDARD.decode(part).ok());
+                let (salt, nonce, ciphertext) = (parts.next()??, parts.next()??, parts.next()??);
+                if nonce.len() != 24 {
+                    return None;
+                }
+                let token = self
+                    .key(secret, &salt)
+                    .decrypt(XNonce::from_slice(&nonce), ciphertext.as_slice())
+                    .ok()?;
+                String::from_utf8(token).ok()
+            }
+            _ => None,
+        }
+    }
+
+    /// The saved token. A file that can't be read back (corrupt, or written with another secret)
+    /// is removed and treated as no session.
+    pub fn load(&self) -> Option<String> {
+        let contents = fs::read_to_string(&self.path).ok()?;
+        let token = self.decode(&contents);
+        if token.is_none() {
+            println!("Discarding unreadable session file {}", self.path.display());
+            self.clear();
+        }
+        token
+    }
+
+    pub fn save(&self, token: &str) {
+        if let Err(e) = write_private(&self.path, &self.encode(token)) {
+            println!("Unable to save session to {}: {e}", self.path.display());
+        }
+    }
+
+    pub fn clear(&self) {
+        match fs::remove_file(&self.path) {
+            Err(e) if e.kind() != io::ErrorKind::NotFound => {
+                println!("Unable to remove session file {}: {e}", self.path.display());
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Writes through a temporary file created with mode 0600, then renames it over `path`, so the
+/// token is never readable by others, even briefly.
+fn write_private(path: &Path, contents: &str) -> io::Result<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let tmp = path.with_extension("tmp");
+    // The mode only applies to new files.
+    let _ = fs::remove_file(&tmp);
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    io::Write::write_all(&mut options.open(&tmp)?, contents.as_bytes())?;
+    fs::rename(&tmp, path)
+}
@@ -3,7 +3,7 @@ use ui::components::{
     text_input::TextInput,
 };
 
-use crate::utils::state::State;
+use crate::utils::{state::State, theme::Theme};
 
 pub enum TextInputType {
     Password,
@@ -27,6 +27,7 @@ pub fn text_input(
         .content(content)
         .main_align(Alignment::Center)
         .dim((Length::FILL, Length::FitPer(180)))
+        .bg_color(Theme::palette().surface)
         .font_size(26)
         .padding((5, 0, 5, 0))
         .wrap(true)
@@ -0,0 +1,101 @@
+use std::{fmt, str::FromStr, sync::OnceLock};
+
+use ui::raylib::color::Color;
+
+/// Colors by role, so screens don't pick raw colors.
+pub struct Palette {
+    /// Behind everything, where no layout paints.
+    pub window: Color,
+    /// Text drawn straight on `window`.
+    pub window_text: Color,
+    pub backdrop: Color,
+    pub panel: Color,
+    /// Buttons and content areas.
+    pub surface: Color,
+    /// Bars and idle tabs.
+    pub muted: Color,
+    /// Active tab, headers.
+    pub selected: Color,
+    /// Clickable list rows.
+    pub accent: Color,
+    pub text: Color,
+    /// Presence dots.
+    pub online: Color,
+    pub away: Color,
+    pub offline: Color,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Theme {
+    Light,
+    Dark,
+}
+
+impl FromStr for Theme {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "light" => Ok(Theme::Light),
+            "dark" => Ok(Theme::Dark),
+            other => Err(format!("unknown theme `{other}`, expected `light` or `dark`")),
+        }
+    }
+}
+
+impl fmt::Display for Theme {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Theme::Light => write!(f, "light"),
+            Theme::Dark => write!(f, "dark"),
+        }
+    }
+}
+
+static PALETTE: OnceLock<Palette> = OnceLock::new();
+
+impl Theme {
+    pub fn init(self) {
+        PALETTE
+            .set(self.colors())
+            .ok()
+            .expect("Theme already initialized");
+    }
+
+    pub fn palette() -> &'static Palette {
+        PALETTE.get().expect("Theme not initialized")
+    }
+
+    fn colors(self) -> Palette {
+        match self {
+            Theme::Light => Palette {
+                window: Color::BLACK,
+                window_text: Color::WHITE,
+                backdrop: Color::WHEAT,
+                panel: Color::RED,
+                surface: Color::BEIGE,
+                muted: Color::LIGHTGRAY,
+                selected: Color::GRAY,
+                accent: Color::CYAN,
+                text: Color::BLACK,
+                online: Color::GREEN,
+                away: Color::ORANGE,
+                offline: Color::DARKGRAY,
+            },
+            Theme::Dark => Palette {
+                window: Color::new(18, 18, 20, 255),
+                window_text: Color::new(235, 235, 235, 255),
+                backdrop: Color::new(32, 32, 38, 255),
+                panel: Color::new(96, 28, 36, 255),
+                surface: Color::new(58, 58, 66, 255),
+                muted: Color::new(44, 44, 50, 255),
+                selected: Color::new(84, 84, 96, 255),
+                accent: Color::new(0, 110, 130, 255),
+                text: Color::new(230, 230, 230, 255),
+                online: Color::new(60, 190, 90, 255),
+                away: Color::new(230, 160, 40, 255),
+                offline: Color::new(110, 110, 120, 255),
+            },
+        }
+    }
+}
@@ -1,16 +1,43 @@
 use proc_macro::TokenStream;
 use quote::quote;
-use syn::{Attribute, FnArg, ItemEnum, ItemFn, Variant, parse_macro_input, parse_quote};
+use syn::{Attribute, FnArg, spanned::Spanned, ItemEnum, ItemFn, LitInt, Variant, parse_macro_input, parse_quote};
 
+/// Injects the database handle as the first argument.
+///
+/// `#[db_func]` adds `pool: &sqlx::PgPool`. `#[db_func(conn)]` adds
+/// `conn: &mut sqlx::PgConnection` instead, so the function can run inside a transaction the
+/// caller owns (`&mut *txn`) and compose with other `conn` functions atomically.
+///
+/// Each call runs in a debug-level `db` span named after the function. The crate using it needs
+/// `tracing` as a dependency.
 #[proc_macro_attribute]
-pub fn db_func(_attr: TokenStream, item: TokenStream) -> TokenStream {
+pub fn db_func(attr: TokenStream, item: TokenStream) -> TokenStream {
     let mut input = parse_macro_input!(item as ItemFn);
+    let mode = if attr.is_empty() {
+        None
+    } else {
+        Some(parse_macro_input!(attr as syn::Ident))
+    };
 
     // Create the new argument
-    let pool_arg: FnArg = parse_quote! { pool: &sqlx::PgPool };
+    let db_arg: FnArg = match mode {
+        None => parse_quote! { pool: &sqlx::PgPool },
+        Some(mode) if mode == "conn" => parse_quote! { conn: &mut sqlx::PgConnection },
+        Some(mode) => {
+            return syn::Error::new_spanned(mode, "expected `#[db_func]` or `#[db_func(conn)]`")
+                .to_compile_error()
+                .into();
+        }
+    };
 
     // Insert it at the beginning of the inputs
-    input.sig.inputs.insert(0, pool_arg);
+    input.sig.inputs.insert(0, db_arg);
+
+    // A span per call, without arguments: they can hold passwords, tokens or search terms.
+    let func = input.sig.ident.to_string();
+    input.attrs.push(parse_quote! {
+        #[::tracing::instrument(level = "debug", name = "db", skip_all, fields(func = #func))]
+    });
 
     // Generate the output
     let output = quote! {
@@ -20,15 +47,91 @@ pub fn db_func(_attr: TokenStream, item: TokenStream) -> TokenStream {
     output.into()
 }
 
+/// How one error variant is sent to clients, read from `#[response(...)]`.
+struct VariantResponse {
+    status: u16,
+    code: syn::Ident,
+    message: syn::LitStr,
+}
+
+fn parse_variant_response(variant: &mut Variant) -> syn::Result<VariantResponse> {
+    let index = variant
+        .attrs
+        .iter()
+        .position(|attr| attr.path().is_ident("response"))
+        .ok_or_else(|| {
+            syn::Error::new_spanned(
+                &variant.ident,
+                "db_err variants need #[response(status = .., code = .., message = \"..\")]",
+            )
+        })?;
+    let attr = variant.attrs.remove(index);
+    let mut status = None;
+    let mut code = None;
+    let mut message = None;
+    attr.parse_nested_meta(|meta| {
+        if meta.path.is_ident("status") {
+            let lit: LitInt = meta.value()?.parse()?;
+            status = Some(lit.base10_parse::<u16>()?);
+        } else if meta.path.is_ident("code") {
+            code = Some(meta.value()?.parse::<syn::Ident>()?);
+        } else if meta.path.is_ident("message") {
+            message = Some(meta.value()?.parse::<syn::LitStr>()?);
+        } else {
+            return Err(meta.error("expected `status`, `code` or `message`"));
+        }
+        Ok(())
+    })?;
+    let missing = |name: &str| syn::Error::new_spanned(&attr, format!("missing `{name}`"));
+    Ok(VariantResponse {
+        status: status.ok_or_else(|| missing("status"))?,
+        code: code.ok_or_else(|| missing("code"))?,
+        message: message.ok_or_else(|| missing("message"))?,
+    })
+}
+
+/// Adds a `Sqlx(sqlx::Error)` variant and generates `code()` plus `From<Self> for shared::Response<T>`.
+///
+/// Every declared variant needs `#[response(status = 400, code = SomeErrorCode, message = "...")]`
+/// where `code` names a `shared::ErrorCode` variant and `status` is 400, 401, 404 or 500. The
+/// `Sqlx` variant always becomes a generic 500 and the underlying error is only logged.
 #[proc_macro_attribute]
 pub fn db_err(_attr:TokenStream, item: TokenStream) -> TokenStream{
     let mut input = parse_macro_input!(item as ItemEnum);
-    let enum_name = &input.ident;
+    let enum_name = input.ident.clone();
+
+    let mut code_arms = vec![];
+    let mut response_arms = vec![];
+    for variant in input.variants.iter_mut() {
+        let response = match parse_variant_response(variant) {
+            Ok(response) => response,
+            Err(e) => return e.to_compile_error().into(),
+        };
+        let ident = &variant.ident;
+        let VariantResponse { status, code, message } = response;
+        let constructor = match status {
+            400 => quote! { bad_request },
+            401 => quote! { unauthorized },
+            404 => quote! { not_found },
+            500 => quote! { internal_error },
+            _ => {
+                return syn::Error::new_spanned(ident, "status must be 400, 401, 404 or 500")
+                    .to_compile_error()
+                    .into();
+            }
+        };
+        code_arms.push(quote! {
+            #enum_name::#ident { .. } => ::shared::ErrorCode::#code,
+        });
+        response_arms.push(quote! {
+            #enum_name::#ident { .. } => ::shared::Response::#constructor(#message, None).with_code(code),
+        });
+    }
+
     let sqlx_error_variant: Variant = parse_quote! { Sqlx(::sqlx::Error)};
     let any_cast_attr: Attribute = parse_quote! {
         #[::macros::any_cast]
     };
-    // input.variants.push(x);
     input.variants.push(sqlx_error_variant);
     input.attrs.push(any_cast_attr);
     let output = quote! {
@@ -38,6 +141,29 @@ pub fn db_err(_attr:TokenStream, item: TokenStream) -> TokenStream{
                 #enum_name::Sqlx(err)
             }
         }
+        impl #enum_name {
+            pub fn code(&self) -> ::shared::ErrorCode {
+                match self {
+                    #(#code_arms)*
+                    #enum_name::Sqlx(_) => ::shared::ErrorCode::Internal,
+                }
+            }
+        }
+        impl<T> From<#enum_name> for ::shared::Response<T>
+        where
+            T: ::serde::Serialize + ::serde::de::DeserializeOwned,
+        {
+            fn from(value: #enum_name) -> Self {
+                let code = value.code();
+                match value {
+                    #(#response_arms)*
+                    #enum_name::Sqlx(err) => {
+                        ::tracing::error!(error = %err, "{}: database error", stringify!(#enum_name));
+                        ::shared::Response::internal_error("Internal server error", None).with_code(code)
+                    }
+                }
+            }
+        }
     };
     output.into()
 }
@@ -56,3 +182,218 @@ pub fn any_cast(_attr:TokenStream,item:TokenStream) -> TokenStream {
     };
     output.into()
 }
+
+/// Declares the Rocket handler for a `shared::api` endpoint:
+/// `#[api_route(Login, data = "<payload>")]`.
+///
+/// The method and path come from `shared::endpoint!`, so they can't drift from what the client
+/// calls. The handler has to take `Json<Req<E>>` as its data (or `Req<E>` as its query), return
+/// `ApiResult<Res<E>>` and, for `auth` endpoints, take a `Claims` guard; anything else fails to
+/// compile. It also implements `shared::api::ApiRoute` for the generated route so the server can
+/// mount it at the endpoint's scope, and runs the handler in a `request` span carrying the
+/// endpoint and `crate::request_id::RequestId`, never the arguments.
+#[proc_macro_attribute]
+pub fn api_route(attr: TokenStream, item: TokenStream) -> TokenStream {
+    let attr = proc_macro2::TokenStream::from(attr);
+    let item = proc_macro2::TokenStream::from(item);
+    let mut tokens = attr.into_iter();
+    let endpoint = match tokens.next() {
+        Some(proc_macro2::TokenTree::Ident(endpoint)) => endpoint,
+        _ => {
+            return syn::Error::new(proc_macro2::Span::call_site(), "expected `#[api_route(Endpoint, ...)]`")
+                .to_compile_error()
+                .into();
+        }
+    };
+    // Everything after the endpoint name is forwarded to the Rocket attribute as is.
+    let args = tokens.skip(1).collect::<proc_macro2::TokenStream>();
+
+    let output = quote! {
+        ::shared::endpoint!(#endpoint => macros::__api_route_expand { [#args] #item });
+    };
+    output.into()
+}
+
+/// What `shared::endpoint!` hands back to `api_route` for one endpoint.
+struct ApiRouteSpec {
+    endpoint: syn::Ident,
+    method: syn::Ident,
+    route: syn::LitStr,
+    args: proc_macro2::TokenStream,
+    item: ItemFn,
+}
+
+impl syn::parse::Parse for ApiRouteSpec {
+    fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
+        let endpoint = input.parse()?;
+        let method = input.parse()?;
+        let _auth: syn::Ident = input.parse()?;
+        let _scope: syn::LitStr = input.parse()?;
+        let route = input.parse()?;
+        // Checked against the handler through `Req<E>`/`Res<E>` instead, which name the same types.
+        let request;
+        syn::parenthesized!(request in input);
+        request.parse::<proc_macro2::TokenStream>()?;
+        input.parse::<syn::Token![->]>()?;
+        let response;
+        syn::parenthesized!(response in input);
+        response.parse::<proc_macro2::TokenStream>()?;
+        let body;
+        syn::braced!(body in input);
+        let args;
+        syn::bracketed!(args in body);
+        let item = body.parse()?;
+        Ok(ApiRouteSpec {
+            endpoint,
+            method,
+            route,
+            args: args.parse()?,
+            item,
+        })
+    }
+}
+
+#[doc(hidden)]
+#[proc_macro]
+pub fn __api_route_expand(input: TokenStream) -> TokenStream {
+    let ApiRouteSpec {
+        endpoint,
+        method,
+        route,
+        args,
+        item,
+    } = parse_macro_input!(input as ApiRouteSpec);
+    let mut item = item;
+    let checks = match signature_checks(&endpoint, &route, &args, &item) {
+        Ok(checks) => checks,
+        Err(err) => return err.to_compile_error().into(),
+    };
+
+    // Rocket resolves the request bindings it generates against the attribute's span, so the
+    // attribute has to look like the handler author wrote it.
+    let span = item.sig.ident.span();
+    let method = syn::Ident::new(&method.to_string(), span);
+    let endpoint_name = endpoint.to_string();
+    item.sig
+        .inputs
+        .insert(0, syn::parse_quote_spanned! {span=> __request_id: crate::request_id::RequestId });
+    item.attrs.push(syn::parse_quote_spanned! {span=>
+        #[::tracing::instrument(
+            name = "request",
+            skip_all,
+            fields(endpoint = #endpoint_name, request_id = %__request_id)
+        )]
+    });
+    let handler = &item.sig.ident;
+    let args = if args.is_empty() {
+        quote! {}
+    } else {
+        quote! { , #args }
+    };
+
+    let output = quote::quote_spanned! {span=>
+        #[::rocket::#method(#route #args)]
+        #item
+
+        impl ::shared::api::ApiRoute for #handler {
+            type Endpoint = ::shared::api::#endpoint;
+        }
+
+        #checks
+    };
+    output.into()
+}
+
+/// The name inside `"<name>"` or `"<name..>"`.
+fn binding_name(segment: &str) -> Option<String> {
+    let name = segment.trim().strip_prefix('<')?.strip_suffix('>')?;
+    Some(name.trim_end_matches("..").to_string())
+}
+
+/// The handler argument bound with that name, and its type.
+fn find_arg<'a>(item: &'a ItemFn, name: &str) -> Option<&'a syn::Type> {
+    item.sig.inputs.iter().find_map(|input| match input {
+        FnArg::Typed(arg) => match &*arg.pat {
+            syn::Pat::Ident(pat) if pat.ident == name => Some(&*arg.ty),
+            _ => None,
+        },
+        FnArg::Receiver(_) => None,
+    })
+}
+
+/// Fails to compile unless `actual` and `expected` are the same type, pointing at `actual`.
+fn assert_same_type(actual: &syn::Type, expected: proc_macro2::TokenStream) -> proc_macro2::TokenStream {
+    quote::quote_spanned! {actual.span()=>
+        const _: () = {
+            fn __same_type<T: ?Sized>(_: ::core::marker::PhantomData<T>, _: ::core::marker::PhantomData<T>) {}
+            fn __check() {
+                __same_type(::core::marker::PhantomData::<#actual>, ::core::marker::PhantomData::<#expected>);
+            }
+        };
+    }
+}
+
+/// Ties the handler's data or query argument, return type and auth guard to the endpoint.
+fn signature_checks(
+    endpoint: &syn::Ident,
+    route: &syn::LitStr,
+    args: &proc_macro2::TokenStream,
+    item: &ItemFn,
+) -> syn::Result<proc_macro2::TokenStream> {
+    let mut checks = quote! {};
+    let request = quote! { ::shared::api::Req<::shared::api::#endpoint> };
+
+    let mut data = None;
+    let mut tokens = args.clone().into_iter().peekable();
+    while let Some(token) = tokens.next() {
+        if let proc_macro2::TokenTree::Ident(ident) = &token {
+            if ident == "data" {
+                tokens.next();
+                if let Some(proc_macro2::TokenTree::Literal(lit)) = tokens.next() {
+                    data = binding_name(lit.to_string().trim_matches('"'));
+                }
+            }
+        }
+    }
+    let route = route.value();
+    let query = route.split_once('?').and_then(|(_, query)| binding_name(query));
+    if let Some(name) = &data {
+        if let Some(ty) = find_arg(item, name) {
+            checks.extend(assert_same_type(ty, quote! { ::rocket::serde::json::Json<#request> }));
+        }
+    } else if let Some(name) = &query {
+        if let Some(ty) = find_arg(item, name) {
+            checks.extend(assert_same_type(ty, request.clone()));
+        }
+    } else if !route.contains('<') {
+        // Nothing carries the request, so the endpoint can't expect one.
+        let unit: syn::Type = syn::parse_quote_spanned! {item.sig.ident.span()=> () };
+        checks.extend(assert_same_type(&unit, request.clone()));
+    }
+
+    match &item.sig.output {
+        syn::ReturnType::Type(_, ty) => {
+            checks.extend(assert_same_type(ty, quote! { ::shared::ApiResult<::shared::api::Res<::shared::api::#endpoint>> }));
+        }
+        syn::ReturnType::Default => {
+            return Err(syn::Error::new(item.sig.ident.span(), format!("`{endpoint}` handlers must return `ApiResult<Res<{endpoint}>>`")));
+        }
+    }
+
+    let claims = item.sig.inputs.iter().find_map(|input| match input {
+        FnArg::Typed(arg) => match &*arg.ty {
+            syn::Type::Path(path) if path.path.segments.last().is_some_and(|s| s.ident == "Claims") => Some(&*arg.ty),
+            _ => None,
+        },
+        FnArg::Receiver(_) => None,
+    });
+    if let Some(ty) = claims {
+        checks.extend(assert_same_type(ty, quote! { crate::db::auth::jwt::Claims }));
+    }
+    let has_claims = claims.is_some();
+    let message = format!("`{endpoint}` needs auth, so its handler must take a `Claims` guard");
+    checks.extend(quote::quote_spanned! {item.sig.ident.span()=>
+        const _: () = ::core::assert!(!<::shared::api::#endpoint as ::shared::api::Endpoint>::AUTH || #has_claims, #message);
+    });
+    Ok(checks)
+}
@@ -1,5 +1,24 @@
 // generated by `sqlx migrate build-script`
+use std::{fs, process::Command};
+
 fn main() {
     // trigger recompilation when a new migration is added
     println!("cargo:rerun-if-changed=migrations");
+    println!("cargo:rerun-if-changed=migrations_sqlite");
+
+    // Commit reported by `/version`. Builds outside a git checkout report "unknown".
+    let git_hash = Command::new("git")
+        .args(["rev-parse", "--short=12", "HEAD"])
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .and_then(|output| String::from_utf8(output.stdout).ok())
+        .map(|hash| hash.trim().to_string())
+        .unwrap_or_else(|| "unknown".into());
+    println!("cargo:rustc-env=GIT_HASH={git_hash}");
+    println!("cargo:rerun-if-changed=../.git/HEAD");
+    let head = fs::read_to_string("../.git/HEAD").unwrap_or_default();
+    if let Some(branch) = head.trim().strip_prefix("ref: ") {
+        println!("cargo:rerun-if-changed=../.git/{branch}");
+    }
 }
@@ -0,0 +1,3 @@
+-- Disabled accounts keep their data but can no longer log in.
+ALTER TABLE users
+    ADD COLUMN disabled_at TIMESTAMPTZ;
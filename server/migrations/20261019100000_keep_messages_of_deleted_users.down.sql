@@ -0,0 +1,8 @@
+DELETE FROM message WHERE sender_member_id IS NULL;
+ALTER TABLE message
+    DROP CONSTRAINT message_sender_member_id_fkey;
+ALTER TABLE message
+    ADD CONSTRAINT message_sender_member_id_fkey
+    FOREIGN KEY (sender_member_id) REFERENCES conversation_member(id) ON DELETE CASCADE;
+ALTER TABLE message
+    ALTER COLUMN sender_member_id SET NOT NULL;
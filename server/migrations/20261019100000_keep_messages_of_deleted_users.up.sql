@@ -0,0 +1,9 @@
+-- Messages outlive their sender: removing a member (e.g. when the account is deleted) leaves
+-- the message in place with no sender instead of deleting it from everyone's history.
+ALTER TABLE message
+    ALTER COLUMN sender_member_id DROP NOT NULL;
+ALTER TABLE message
+    DROP CONSTRAINT message_sender_member_id_fkey;
+ALTER TABLE message
+    ADD CONSTRAINT message_sender_member_id_fkey
+    FOREIGN KEY (sender_member_id) REFERENCES conversation_member(id) ON DELETE SET NULL;
@@ -0,0 +1,6 @@
+DROP TABLE IF EXISTS user_avatar;
+ALTER TABLE users
+    DROP COLUMN display_name,
+    DROP COLUMN bio,
+    DROP COLUMN status_text,
+    DROP COLUMN avatar_updated_at;
@@ -0,0 +1,13 @@
+ALTER TABLE users
+    ADD COLUMN display_name TEXT,
+    ADD COLUMN bio TEXT,
+    ADD COLUMN status_text TEXT,
+    ADD COLUMN avatar_updated_at TIMESTAMPTZ;
+
+-- Kept out of `users` so listing users never reads image bytes.
+CREATE TABLE IF NOT EXISTS user_avatar (
+    user_id INTEGER NOT NULL PRIMARY KEY,
+    content_type TEXT NOT NULL,
+    data BYTEA NOT NULL,
+    FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE
+);
@@ -0,0 +1,13 @@
+CREATE TABLE IF NOT EXISTS user_block (
+    blocker_id INTEGER NOT NULL,
+    blocked_id INTEGER NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (blocker_id, blocked_id),
+    FOREIGN KEY (blocker_id) REFERENCES users(id) ON DELETE CASCADE,
+    FOREIGN KEY (blocked_id) REFERENCES users(id) ON DELETE CASCADE
+);
+-- Looked up from the blocked side when they try to reach the blocker.
+CREATE INDEX IF NOT EXISTS user_block_blocked_id ON user_block (blocked_id);
+
+-- Per member, so muting a conversation only affects the member who muted it.
+ALTER TABLE conversation_member ADD COLUMN muted BOOLEAN NOT NULL DEFAULT FALSE;
@@ -0,0 +1,15 @@
+-- Points from whoever asked to whoever was asked; `contact_pair` keeps it to one row per pair.
+CREATE TABLE IF NOT EXISTS contact (
+    requester_id INTEGER NOT NULL,
+    addressee_id INTEGER NOT NULL,
+    status TEXT NOT NULL CHECK (status IN ('pending', 'accepted', 'declined')),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (requester_id, addressee_id),
+    FOREIGN KEY (requester_id) REFERENCES users(id) ON DELETE CASCADE,
+    FOREIGN KEY (addressee_id) REFERENCES users(id) ON DELETE CASCADE
+);
+CREATE UNIQUE INDEX IF NOT EXISTS contact_pair ON contact (LEAST(requester_id, addressee_id), GREATEST(requester_id, addressee_id));
+CREATE INDEX IF NOT EXISTS contact_addressee_id ON contact (addressee_id);
+
+ALTER TABLE users ADD COLUMN contacts_only_dms BOOLEAN NOT NULL DEFAULT FALSE;
@@ -0,0 +1,2 @@
+DROP INDEX IF EXISTS users_username_trgm;
+DROP EXTENSION IF EXISTS pg_trgm;
@@ -0,0 +1,3 @@
+-- Lets `ILIKE '%name%'` use an index instead of scanning every user.
+CREATE EXTENSION IF NOT EXISTS pg_trgm;
+CREATE INDEX IF NOT EXISTS users_username_trgm ON users USING gin (username gin_trgm_ops);
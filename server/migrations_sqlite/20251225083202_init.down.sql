@@ -0,0 +1,4 @@
+DROP TABLE IF EXISTS token_family_rel;
+DROP TABLE IF EXISTS token;
+DROP TABLE IF EXISTS token_family;
+DROP TABLE IF EXISTS users;
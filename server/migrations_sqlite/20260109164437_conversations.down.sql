@@ -0,0 +1,4 @@
+DROP TABLE IF EXISTS message;
+DROP TABLE IF EXISTS text_message_content;
+DROP TABLE IF EXISTS conversation_member;
+DROP TABLE IF EXISTS conversation;
@@ -0,0 +1,3 @@
+-- SQLite version of migrations/20261019090000_user_disabled.up.sql.
+ALTER TABLE users
+    ADD COLUMN disabled_at TEXT;
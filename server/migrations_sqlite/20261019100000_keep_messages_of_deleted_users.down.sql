@@ -0,0 +1,15 @@
+CREATE TABLE message_old (
+    id INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+    conversation_id INTEGER NOT NULL,
+    sender_member_id INTEGER NOT NULL,
+    message_type TEXT NOT NULL,
+    message_content_id INTEGER NOT NULL,
+    created_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%fZ', 'now')),
+    updated_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%fZ', 'now')),
+    FOREIGN KEY (conversation_id) REFERENCES conversation(id) ON DELETE CASCADE,
+    FOREIGN KEY (sender_member_id) REFERENCES conversation_member(id) ON DELETE CASCADE,
+    FOREIGN KEY (message_content_id) REFERENCES text_message_content(id) ON DELETE CASCADE
+);
+INSERT INTO message_old SELECT id, conversation_id, sender_member_id, message_type, message_content_id, created_at, updated_at FROM message WHERE sender_member_id IS NOT NULL;
+DROP TABLE message;
+ALTER TABLE message_old RENAME TO message;
@@ -0,0 +1,17 @@
+-- SQLite version of migrations/20261019100000_keep_messages_of_deleted_users.up.sql. SQLite
+-- cannot change a foreign key in place, so the table is rebuilt. Nothing references message.
+CREATE TABLE message_new (
+    id INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+    conversation_id INTEGER NOT NULL,
+    sender_member_id INTEGER,
+    message_type TEXT NOT NULL,
+    message_content_id INTEGER NOT NULL,
+    created_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%fZ', 'now')),
+    updated_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%fZ', 'now')),
+    FOREIGN KEY (conversation_id) REFERENCES conversation(id) ON DELETE CASCADE,
+    FOREIGN KEY (sender_member_id) REFERENCES conversation_member(id) ON DELETE SET NULL,
+    FOREIGN KEY (message_content_id) REFERENCES text_message_content(id) ON DELETE CASCADE
+);
+INSERT INTO message_new SELECT id, conversation_id, sender_member_id, message_type, message_content_id, created_at, updated_at FROM message;
+DROP TABLE message;
+ALTER TABLE message_new RENAME TO message;
@@ -0,0 +1,5 @@
+DROP TABLE IF EXISTS user_avatar;
+ALTER TABLE users DROP COLUMN display_name;
+ALTER TABLE users DROP COLUMN bio;
+ALTER TABLE users DROP COLUMN status_text;
+ALTER TABLE users DROP COLUMN avatar_updated_at;
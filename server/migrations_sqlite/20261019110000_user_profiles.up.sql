@@ -0,0 +1,12 @@
+-- SQLite version of migrations/20261019110000_user_profiles.up.sql.
+ALTER TABLE users ADD COLUMN display_name TEXT;
+ALTER TABLE users ADD COLUMN bio TEXT;
+ALTER TABLE users ADD COLUMN status_text TEXT;
+ALTER TABLE users ADD COLUMN avatar_updated_at TEXT;
+
+CREATE TABLE IF NOT EXISTS user_avatar (
+    user_id INTEGER NOT NULL PRIMARY KEY,
+    content_type TEXT NOT NULL,
+    data BLOB NOT NULL,
+    FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE
+);
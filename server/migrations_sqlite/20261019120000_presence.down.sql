@@ -0,0 +1,2 @@
+ALTER TABLE users DROP COLUMN last_seen_at;
+ALTER TABLE users DROP COLUMN hide_last_seen;
@@ -0,0 +1,3 @@
+-- SQLite version of migrations/20261019120000_presence.up.sql.
+ALTER TABLE users ADD COLUMN last_seen_at TEXT;
+ALTER TABLE users ADD COLUMN hide_last_seen BOOLEAN NOT NULL DEFAULT FALSE;
@@ -0,0 +1,2 @@
+ALTER TABLE conversation_member DROP COLUMN muted;
+DROP TABLE IF EXISTS user_block;
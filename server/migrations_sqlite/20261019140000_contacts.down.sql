@@ -0,0 +1,2 @@
+ALTER TABLE users DROP COLUMN contacts_only_dms;
+DROP TABLE IF EXISTS contact;
@@ -0,0 +1,15 @@
+-- SQLite version of migrations/20261019140000_contacts.up.sql.
+CREATE TABLE IF NOT EXISTS contact (
+    requester_id INTEGER NOT NULL,
+    addressee_id INTEGER NOT NULL,
+    status TEXT NOT NULL CHECK (status IN ('pending', 'accepted', 'declined')),
+    created_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%fZ', 'now')),
+    updated_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%fZ', 'now')),
+    PRIMARY KEY (requester_id, addressee_id),
+    FOREIGN KEY (requester_id) REFERENCES users(id) ON DELETE CASCADE,
+    FOREIGN KEY (addressee_id) REFERENCES users(id) ON DELETE CASCADE
+);
+CREATE UNIQUE INDEX IF NOT EXISTS contact_pair ON contact (min(requester_id, addressee_id), max(requester_id, addressee_id));
+CREATE INDEX IF NOT EXISTS contact_addressee_id ON contact (addressee_id);
+
+ALTER TABLE users ADD COLUMN contacts_only_dms BOOLEAN NOT NULL DEFAULT FALSE;
@@ -0,0 +1,360 @@
+//! The `admin` binary: user, conversation, token and migration operations run straight against
+//! the configured database, without going through the HTTP API.
+
+use uuid::Uuid;
+
+use crate::{config::Settings, db::{auth::signup::SignupError, chat::conversation::{ConversationSummary, CreateConversationError}, store::Store}, jobs::token_cleanup, telemetry::Redacted};
+
+pub const USAGE: &str = "Usage: admin <command>
+
+Commands:
+  user create <username> [--password <password>]   Prints a generated password when none is given
+  user disable <username>                          Blocks login and revokes every refresh token
+  user enable <username>
+  user delete <username> --yes                     Removes the user; their messages stay, without a sender
+  user reset-password <username> [--password <password>]
+  user revoke-tokens <username>                    Logs the user out everywhere
+  conversations list [--user <username>]
+  tokens cleanup                                   Deletes rotated and expired refresh tokens now
+  migrate status
+  migrate up
+  migrate down [--to <version>] --yes              Reverts the newest migration, or every one after <version>
+  seed                                             Adds demo users and conversations";
+
+const DEMO_USERS: [&str; 4] = ["alice", "bob", "carol", "dave"];
+const DEMO_PASSWORD: &str = "demo-password";
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Command {
+    CreateUser { username: String, password: Option<Redacted<String>> },
+    DisableUser { username: String },
+    EnableUser { username: String },
+    DeleteUser { username: String },
+    ResetPassword { username: String, password: Option<Redacted<String>> },
+    RevokeTokens { username: String },
+    ListConversations { username: Option<String> },
+    CleanupTokens,
+    MigrateStatus,
+    MigrateUp,
+    MigrateDown { target: Option<i64> },
+    Seed,
+}
+
+/// Positional arguments plus `--flag value` options, in any order.
+struct Args {
+    positional: Vec<String>,
+    options: Vec<(String, Option<String>)>,
+}
+
+impl Args {
+    fn parse(args: &[String]) -> Result<Self, String> {
+        let mut positional = vec![];
+        let mut options = vec![];
+        let mut iter = args.iter();
+        while let Some(arg) = iter.next() {
+            match arg.strip_prefix("--") {
+                Some("yes") => options.push(("yes".to_string(), None)),
+                Some(name) => {
+                    let Some(value) = iter.next() else {
+                        return Err(format!("--{name} needs a value"));
+                    };
+                    options.push((name.to_string(), Some(value.clone())));
+                }
+                None => positional.push(arg.clone()),
+            }
+        }
+        return Ok(Args { positional, options });
+    }
+
+    fn has(&self, name: &str) -> bool {
+        return self.options.iter().any(|(option, _)| option == name);
+    }
+
+    fn value(&self, name: &str) -> Option<String> {
+        return self
+            .options
+            .iter()
+            .find(|(option, _)| option == name)
+            .and_then(|(_, value)| value.clone());
+    }
+
+    /// Rejects options the command doesn't take, so a typo can't be silently ignored.
+    fn only(&self, allowed: &[&str]) -> Result<(), String> {
+        match self.options.iter().find(|(option, _)| !allowed.contains(&option.as_str())) {
+            Some((option, _)) => return Err(format!("unexpected option --{option}")),
+            None => return Ok(()),
+        }
+    }
+}
+
+impl Command {
+    pub fn parse(args: &[String]) -> Result<Self, String> {
+        let args = Args::parse(args)?;
+        let words = args.positional.iter().map(String::as_str).collect::<Vec<_>>();
+        let command = match words.as_slice() {
+            ["user", "create", username] => {
+                args.only(&["password"])?;
+                Command::CreateUser { username: username.to_string(), password: args.value("password").map(Redacted) }
+            }
+            ["user", "disable", username] => {
+                args.only(&[])?;
+                Command::DisableUser { username: username.to_string() }
+            }
+            ["user", "enable", username] => {
+                args.only(&[])?;
+                Command::EnableUser { username: username.to_string() }
+            }
+            ["user", "delete", username] => {
+                args.only(&["yes"])?;
+                if !args.has("yes") {
+                    return Err("user delete cannot be undone; pass --yes to confirm".into());
+                }
+                Command::DeleteUser { username: username.to_string() }
+            }
+            ["user", "reset-password", username] => {
+                args.only(&["password"])?;
+                Command::ResetPassword { username: username.to_string(), password: args.value("password").map(Redacted) }
+            }
+            ["user", "revoke-tokens", username] => {
+                args.only(&[])?;
+                Command::RevokeTokens { username: username.to_string() }
+            }
+            ["conversations", "list"] => {
+                args.only(&["user"])?;
+                Command::ListConversations { username: args.value("user") }
+            }
+            ["tokens", "cleanup"] => {
+                args.only(&[])?;
+                Command::CleanupTokens
+            }
+            ["migrate", "status"] => {
+                args.only(&[])?;
+                Command::MigrateStatus
+            }
+            ["migrate", "up"] => {
+                args.only(&[])?;
+                Command::MigrateUp
+            }
+            ["migrate", "down"] => {
+                args.only(&["to", "yes"])?;
+                if !args.has("yes") {
+                    return Err("migrate down can drop data; pass --yes to confirm".into());
+                }
+                let target = match args.value("to") {
+                    Some(to) => Some(to.parse::<i64>().map_err(|_| format!("--to expects a migration version, got {to}"))?),
+                    None => None,
+                };
+                Command::MigrateDown { target }
+            }
+            ["seed"] => {
+                args.only(&[])?;
+                Command::Seed
+            }
+            _ => return Err(format!("unknown command: {}", words.join(" "))),
+        };
+        return Ok(command);
+    }
+}
+
+async fn user_id(store: &Store, username: &str) -> Result<i32, String> {
+    match store.find_user(username).await {
+        Ok(Some(user)) => return Ok(user.id),
+        Ok(None) => return Err(format!("no user named {username}")),
+        Err(e) => return Err(format!("could not look up {username}: {e}")),
+    }
+}
+
+fn checked_password(password: Option<Redacted<String>>) -> Result<(String, bool), String> {
+    match password {
+        Some(Redacted(password)) if password.is_empty() => return Err("--password must not be empty".into()),
+        Some(Redacted(password)) => return Ok((password, false)),
+        None => return Ok((Uuid::new_v4().simple().to_string(), true)),
+    }
+}
+
+fn create_error(username: &str, e: SignupError) -> String {
+    match e {
+        SignupError::UserAlreadyExists => return format!("a user named {username} already exists"),
+        SignupError::Sqlx(e) => return format!("could not create {username}: {e}"),
+    }
+}
+
+fn hash(settings: &Settings, password: &str) -> String {
+    return bcrypt::hash(password, settings.bcrypt_cost).unwrap();
+}
+
+fn describe(conversation: &ConversationSummary) -> String {
+    let title = match &conversation.title {
+        Some(title) => format!("{title:?}"),
+        None => "untitled".into(),
+    };
+    let members = conversation
+        .members
+        .iter()
+        .map(|m| format!("{} ({})", m.username, m.user_id))
+        .collect::<Vec<_>>()
+        .join(", ");
+    return format!("#{} {title}: {members}", conversation.id);
+}
+
+async fn migration_report(store: &Store) -> Result<String, String> {
+    let status = store
+        .migration_status()
+        .await
+        .map_err(|e| format!("could not read migration status: {e}"))?;
+    let latest = match status.latest_applied {
+        Some(version) => version.to_string(),
+        None => "none".into(),
+    };
+    return Ok(format!("Latest applied migration: {latest}\nPending migrations: {}", status.pending));
+}
+
+/// Safe to run again: existing demo users are reused and a conversation is only added when no
+/// conversation with the same members exists yet.
+async fn seed(store: &Store, settings: &Settings) -> Result<String, String> {
+    let hash_password = hash(settings, DEMO_PASSWORD);
+    let mut ids = vec![];
+    let mut created_users = vec![];
+    for username in DEMO_USERS {
+        let existing = store
+            .find_user(username)
+            .await
+            .map_err(|e| format!("could not look up {username}: {e}"))?;
+        let id = match existing {
+            Some(user) => user.id,
+            None => {
+                created_users.push(username);
+                store
+                    .create_user(username, &hash_password)
+                    .await
+                    .map_err(|e| create_error(username, e))?
+                    .id
+            }
+        };
+        ids.push(id);
+    }
+    let [alice, bob, carol, dave] = ids[..] else {
+        unreachable!();
+    };
+    let conversations = [
+        (None, vec![alice, bob], Some((alice, "Hey Bob, welcome to the chat!"))),
+        (Some("Weekend plans"), vec![alice, bob, carol], Some((carol, "Hiking on Saturday?"))),
+        (None, vec![bob, dave], None),
+    ];
+    let existing = store.list_conversations(None).await.map_err(|e| e.to_string())?;
+    let mut created_conversations = 0;
+    for (title, members, first_message) in conversations {
+        let exists = existing.iter().any(|c| {
+            c.members.len() == members.len() && c.members.iter().all(|m| members.contains(&m.user_id))
+        });
+        if exists {
+            continue;
+        }
+        store
+            .create_conversation(
+                title.map(String::from),
+                members,
+                first_message.map(|(sender, text)| (sender, text.to_string())),
+            )
+            .await
+            .map_err(|e| match e {
+                CreateConversationError::InvalidUsers => "a demo user disappeared while seeding".to_string(),
+                CreateConversationError::Sqlx(e) => format!("could not create a demo conversation: {e}"),
+                CreateConversationError::BlockedUser => "a demo user blocked another one".to_string(),
+                CreateConversationError::ContactsOnly => "a demo user only accepts conversations from contacts".to_string(),
+            })?;
+        created_conversations += 1;
+    }
+    let users = if created_users.is_empty() {
+        "No new users".to_string()
+    } else {
+        format!("Created {} with password {DEMO_PASSWORD:?}", created_users.join(", "))
+    };
+    return Ok(format!("{users}, {created_conversations} new conversations"));
+}
+
+/// Runs one command and returns what to print, or an error message.
+pub async fn run(store: &Store, settings: &Settings, command: Command) -> Result<String, String> {
+    match command {
+        Command::CreateUser { username, password } => {
+            if username.trim().is_empty() {
+                return Err("username must not be empty".into());
+            }
+            let (password, generated) = checked_password(password)?;
+            let id = store
+                .create_user(&username, &hash(settings, &password))
+                .await
+                .map_err(|e| create_error(&username, e))?
+                .id;
+            if generated {
+                return Ok(format!("Created {username} (id {id}) with password {password}"));
+            }
+            return Ok(format!("Created {username} (id {id})"));
+        }
+        Command::DisableUser { username } => {
+            let id = user_id(store, &username).await?;
+            store.set_user_disabled(id, true).await.map_err(|e| e.to_string())?;
+            return Ok(format!("Disabled {username} and revoked their refresh tokens"));
+        }
+        Command::EnableUser { username } => {
+            let id = user_id(store, &username).await?;
+            store.set_user_disabled(id, false).await.map_err(|e| e.to_string())?;
+            return Ok(format!("Enabled {username}"));
+        }
+        Command::DeleteUser { username } => {
+            let id = user_id(store, &username).await?;
+            if !store.delete_user(id).await.map_err(|e| e.to_string())? {
+                return Err(format!("no user named {username}"));
+            }
+            return Ok(format!("Deleted {username}"));
+        }
+        Command::ResetPassword { username, password } => {
+            let id = user_id(store, &username).await?;
+            let (password, generated) = checked_password(password)?;
+            store
+                .set_password(id, &hash(settings, &password))
+                .await
+                .map_err(|e| e.to_string())?;
+            if generated {
+                return Ok(format!("New password for {username}: {password}"));
+            }
+            return Ok(format!("Changed the password of {username} and revoked their refresh tokens"));
+        }
+        Command::RevokeTokens { username } => {
+            let id = user_id(store, &username).await?;
+            let revoked = store.revoke_token_families(id).await.map_err(|e| e.to_string())?;
+            return Ok(format!("Revoked {revoked} token families of {username}"));
+        }
+        Command::ListConversations { username } => {
+            let member = match username {
+                Some(username) => Some(user_id(store, &username).await?),
+                None => None,
+            };
+            let conversations = store.list_conversations(member).await.map_err(|e| e.to_string())?;
+            if conversations.is_empty() {
+                return Ok("No conversations".into());
+            }
+            return Ok(conversations.iter().map(describe).collect::<Vec<_>>().join("\n"));
+        }
+        Command::CleanupTokens => {
+            let stats = token_cleanup::run_once(store, &settings.tokens)
+                .await
+                .map_err(|e| format!("token cleanup failed: {e}"))?;
+            return Ok(format!("Removed {} tokens and {} token families", stats.tokens_removed, stats.families_removed));
+        }
+        Command::MigrateStatus => {
+            return migration_report(store).await;
+        }
+        Command::MigrateUp => {
+            store.migrate_up().await.map_err(|e| format!("migration failed: {e}"))?;
+            return migration_report(store).await;
+        }
+        Command::MigrateDown { target } => {
+            store.migrate_down(target).await.map_err(|e| format!("revert failed: {e}"))?;
+            return migration_report(store).await;
+        }
+        Command::Seed => {
+            return seed(store, settings).await;
+        }
+    }
+}
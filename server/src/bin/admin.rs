@@ -0,0 +1,35 @@
+use std::env;
+
+use dotenvy::dotenv;
+
+use server::admin::{self, Command, USAGE};
+use server::config::Settings;
+use server::db::store;
+
+/// Reads the same configuration as the server. Migrations are left alone: `migrate` is one of
+/// the commands.
+#[rocket::main]
+async fn main() {
+    let _ = dotenv();
+    let args = env::args().skip(1).collect::<Vec<_>>();
+    if args.is_empty() || args.iter().any(|arg| arg == "--help" || arg == "-h") {
+        println!("{USAGE}");
+        return;
+    }
+    let command = Command::parse(&args).unwrap_or_else(|e| {
+        eprintln!("{e}\n\n{USAGE}");
+        std::process::exit(2);
+    });
+    let settings = Settings::from_figment(&Settings::figment()).unwrap_or_else(|e| {
+        eprintln!("Invalid configuration: {e}");
+        std::process::exit(1);
+    });
+    let store = store::open(&settings).await;
+    match admin::run(&store, &settings, command).await {
+        Ok(output) => println!("{output}"),
+        Err(e) => {
+            eprintln!("{e}");
+            std::process::exit(1);
+        }
+    }
+}
@@ -0,0 +1,283 @@
+use std::time::Duration;
+
+use rocket::{
+    figment::{Figment, providers::Env},
+    serde::Deserialize,
+};
+use tracing_subscriber::EnvFilter;
+
+/// bcrypt's own bounds, which the crate doesn't export.
+const BCRYPT_COST_RANGE: std::ops::RangeInclusive<u32> = 4..=31;
+
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[serde(crate = "rocket::serde", default)]
+pub struct DatabaseSettings {
+    pub max_connections: u32,
+    pub min_connections: u32,
+    /// How long a request waits for a free connection before failing.
+    pub acquire_timeout_secs: u64,
+}
+
+impl Default for DatabaseSettings {
+    fn default() -> Self {
+        return DatabaseSettings {
+            max_connections: 10,
+            min_connections: 0,
+            acquire_timeout_secs: 5,
+        };
+    }
+}
+
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[serde(crate = "rocket::serde", default)]
+pub struct TokenSettings {
+    pub access_ttl_secs: u64,
+    pub refresh_ttl_secs: u64,
+    pub cleanup_interval_secs: u64,
+    /// How long rotated or expired refresh tokens are kept before cleanup deletes them.
+    pub retention_hours: u64,
+}
+
+impl Default for TokenSettings {
+    fn default() -> Self {
+        return TokenSettings {
+            access_ttl_secs: 15 * 60,
+            refresh_ttl_secs: 3 * 24 * 60 * 60,
+            cleanup_interval_secs: 60 * 60,
+            retention_hours: 24,
+        };
+    }
+}
+
+impl TokenSettings {
+    pub fn access_ttl(&self) -> chrono::Duration {
+        return chrono::Duration::seconds(self.access_ttl_secs as i64);
+    }
+
+    pub fn refresh_ttl(&self) -> chrono::Duration {
+        return chrono::Duration::seconds(self.refresh_ttl_secs as i64);
+    }
+
+    pub fn cleanup_interval(&self) -> Duration {
+        return Duration::from_secs(self.cleanup_interval_secs);
+    }
+
+    pub fn retention(&self) -> chrono::Duration {
+        return chrono::Duration::hours(self.retention_hours as i64);
+    }
+}
+
+#[derive(Deserialize, Debug, Clone, PartialEq, Default)]
+#[serde(crate = "rocket::serde", default)]
+pub struct CorsSettings {
+    /// Origins allowed to call the API from a browser, e.g. `https://chat.example.com`, or `*`.
+    /// Empty sends no CORS headers at all.
+    pub allowed_origins: Vec<String>,
+}
+
+/// Requests per client IP per minute. `0` turns a limit off.
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[serde(crate = "rocket::serde", default)]
+pub struct RateLimitSettings {
+    /// Signup, login and refresh.
+    pub auth_per_minute: u32,
+    /// Every other API route.
+    pub api_per_minute: u32,
+}
+
+impl Default for RateLimitSettings {
+    fn default() -> Self {
+        return RateLimitSettings {
+            auth_per_minute: 30,
+            api_per_minute: 300,
+        };
+    }
+}
+
+#[derive(Deserialize, Debug, Clone, PartialEq, Default)]
+#[serde(crate = "rocket::serde", default)]
+pub struct MetricsSettings {
+    /// When set, `/metrics` requires `Authorization: Bearer <token>`.
+    pub bearer_token: Option<String>,
+}
+
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[serde(crate = "rocket::serde", default)]
+pub struct PresenceSettings {
+    /// A connected user who hasn't been active for this long shows as away.
+    pub away_after_secs: u64,
+    /// How long one heartbeat keeps a user connected. Clients should beat well within it.
+    pub heartbeat_timeout_secs: u64,
+}
+
+impl Default for PresenceSettings {
+    fn default() -> Self {
+        return PresenceSettings {
+            away_after_secs: 5 * 60,
+            heartbeat_timeout_secs: 90,
+        };
+    }
+}
+
+impl PresenceSettings {
+    pub fn away_after(&self) -> Duration {
+        return Duration::from_secs(self.away_after_secs);
+    }
+
+    pub fn heartbeat_timeout(&self) -> Duration {
+        return Duration::from_secs(self.heartbeat_timeout_secs);
+    }
+
+    /// How often a realtime stream pings, so a dropped connection is noticed within the timeout.
+    pub fn ping_interval(&self) -> Duration {
+        return Duration::from_secs((self.heartbeat_timeout_secs / 3).max(1));
+    }
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(crate = "rocket::serde", rename_all = "lowercase")]
+pub enum LogFormat {
+    /// One JSON object per line, for log shippers.
+    Json,
+    /// Human readable, for local development.
+    Text,
+}
+
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[serde(crate = "rocket::serde", default)]
+pub struct LogSettings {
+    pub format: LogFormat,
+    /// `RUST_LOG` syntax, e.g. `info,server=debug`. `RUST_LOG` itself wins when set.
+    pub filter: String,
+}
+
+impl Default for LogSettings {
+    fn default() -> Self {
+        return LogSettings {
+            format: LogFormat::Json,
+            filter: "info".into(),
+        };
+    }
+}
+
+/// Server settings, read once at startup from the same figment as Rocket's own config
+/// (`Rocket.toml` plus `ROCKET_*` variables), and managed as Rocket state.
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[serde(crate = "rocket::serde")]
+pub struct Settings {
+    pub database_url: String,
+    #[serde(default)]
+    pub database: DatabaseSettings,
+    #[serde(default)]
+    pub tokens: TokenSettings,
+    #[serde(default = "default_bcrypt_cost")]
+    pub bcrypt_cost: u32,
+    #[serde(default)]
+    pub cors: CorsSettings,
+    #[serde(default)]
+    pub rate_limits: RateLimitSettings,
+    #[serde(default)]
+    pub metrics: MetricsSettings,
+    #[serde(default)]
+    pub logging: LogSettings,
+    #[serde(default)]
+    pub presence: PresenceSettings,
+}
+
+fn default_bcrypt_cost() -> u32 {
+    return bcrypt::DEFAULT_COST;
+}
+
+impl Settings {
+    /// Defaults for everything but the database.
+    pub fn new(database_url: &str) -> Self {
+        return Settings {
+            database_url: database_url.into(),
+            database: DatabaseSettings::default(),
+            tokens: TokenSettings::default(),
+            bcrypt_cost: default_bcrypt_cost(),
+            cors: CorsSettings::default(),
+            rate_limits: RateLimitSettings::default(),
+            metrics: MetricsSettings::default(),
+            logging: LogSettings::default(),
+            presence: PresenceSettings::default(),
+        };
+    }
+
+    /// Rocket's figment plus the plain variables the server has always read: `DATABASE_URL`,
+    /// `TOKEN_CLEANUP_INTERVAL_SECS` and `TOKEN_RETENTION_HOURS`.
+    pub fn figment() -> Figment {
+        let legacy_env = Env::raw().filter_map(|key| match key.as_str().to_ascii_lowercase().as_str() {
+            "database_url" => Some("database_url".into()),
+            "token_cleanup_interval_secs" => Some("tokens.cleanup_interval_secs".into()),
+            "token_retention_hours" => Some("tokens.retention_hours".into()),
+            _ => None,
+        });
+        return rocket::Config::figment().merge(legacy_env);
+    }
+
+    /// Extracts and validates the settings, with a message naming the bad key on failure.
+    pub fn from_figment(figment: &Figment) -> Result<Self, String> {
+        let settings = figment
+            .extract::<Settings>()
+            .map_err(|e| e.to_string())?;
+        settings.validate()?;
+        return Ok(settings);
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        if self.database_url.trim().is_empty() {
+            return Err("database_url must not be empty".into());
+        }
+        let database = &self.database;
+        if database.max_connections == 0 {
+            return Err("database.max_connections must be at least 1".into());
+        }
+        if database.min_connections > database.max_connections {
+            return Err(format!(
+                "database.min_connections ({}) is above database.max_connections ({})",
+                database.min_connections, database.max_connections
+            ));
+        }
+        let tokens = &self.tokens;
+        if tokens.access_ttl_secs == 0 {
+            return Err("tokens.access_ttl_secs must be at least 1".into());
+        }
+        if tokens.refresh_ttl_secs <= tokens.access_ttl_secs {
+            return Err("tokens.refresh_ttl_secs must be longer than tokens.access_ttl_secs".into());
+        }
+        if tokens.cleanup_interval_secs == 0 {
+            return Err("tokens.cleanup_interval_secs must be at least 1".into());
+        }
+        if !BCRYPT_COST_RANGE.contains(&self.bcrypt_cost) {
+            return Err(format!(
+                "bcrypt_cost must be between {} and {}, got {}",
+                BCRYPT_COST_RANGE.start(),
+                BCRYPT_COST_RANGE.end(),
+                self.bcrypt_cost
+            ));
+        }
+        for origin in self.cors.allowed_origins.iter() {
+            let valid = origin == "*"
+                || ((origin.starts_with("http://") || origin.starts_with("https://")) && !origin.ends_with('/'));
+            if !valid {
+                return Err(format!(
+                    "cors.allowed_origins entry {origin:?} must be `*` or look like https://host[:port]"
+                ));
+            }
+        }
+        if self.metrics.bearer_token.as_ref().is_some_and(|token| token.trim().is_empty()) {
+            return Err("metrics.bearer_token must not be empty when set".into());
+        }
+        if self.presence.away_after_secs == 0 {
+            return Err("presence.away_after_secs must be at least 1".into());
+        }
+        if self.presence.heartbeat_timeout_secs == 0 {
+            return Err("presence.heartbeat_timeout_secs must be at least 1".into());
+        }
+        if let Err(e) = EnvFilter::try_new(&self.logging.filter) {
+            return Err(format!("logging.filter is not a valid filter: {e}"));
+        }
+        return Ok(());
+    }
+}
@@ -0,0 +1,57 @@
+use std::io::Cursor;
+
+use rocket::{
+    Request, Response,
+    fairing::{Fairing, Info, Kind},
+    http::{Method, Status},
+};
+
+use crate::config::CorsSettings;
+
+/// Adds CORS headers for the configured origins and answers browser preflight requests.
+pub struct Cors {
+    allowed_origins: Vec<String>,
+}
+
+impl Cors {
+    pub fn new(settings: &CorsSettings) -> Self {
+        return Cors {
+            allowed_origins: settings.allowed_origins.clone(),
+        };
+    }
+}
+
+#[async_trait]
+impl Fairing for Cors {
+    fn info(&self) -> Info {
+        return Info {
+            name: "CORS",
+            kind: Kind::Response,
+        };
+    }
+
+    async fn on_response<'r>(&self, req: &'r Request<'_>, res: &mut Response<'r>) {
+        let Some(origin) = req.headers().get_one("Origin") else {
+            return;
+        };
+        let any = self.allowed_origins.iter().any(|allowed| allowed == "*");
+        if !any && !self.allowed_origins.iter().any(|allowed| allowed == origin) {
+            return;
+        }
+        let allow_origin = if any { "*".to_string() } else { origin.to_string() };
+        res.set_raw_header("Access-Control-Allow-Origin", allow_origin);
+        res.set_raw_header("Vary", "Origin");
+        res.set_raw_header("Access-Control-Expose-Headers", "Retry-After");
+
+        // No route handles OPTIONS, so a preflight arrives here as a 404.
+        let preflight = req.method() == Method::Options
+            && req.headers().contains("Access-Control-Request-Method");
+        if preflight {
+            res.set_raw_header("Access-Control-Allow-Methods", "GET, POST, PUT, PATCH, DELETE");
+            res.set_raw_header("Access-Control-Allow-Headers", "Authorization, Content-Type");
+            res.set_raw_header("Access-Control-Max-Age", "600");
+            res.set_status(Status::NoContent);
+            res.set_sized_body(0, Cursor::new(""));
+        }
+    }
+}
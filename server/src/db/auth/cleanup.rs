@@ -0,0 +1,51 @@
+use chrono::{Duration, Utc};
+use macros::db_func;
+use sqlx::query;
+
+#[derive(Debug, Default, Clone, Copy)]
+pub struct CleanupStats {
+    pub tokens_removed: u64,
+    pub families_removed: u64,
+}
+
+/// Deletes refresh tokens that can no longer be used and the families left without tokens.
+///
+/// A token is removed once it is older than `refresh_ttl` plus `retention`, or once it has been
+/// rotated out (no `ACTIVE` relation left) for longer than `retention`. The `token_family_rel`
+/// rows go with it through `ON DELETE CASCADE`.
+#[db_func]
+pub async fn cleanup_tokens(refresh_ttl: Duration, retention: Duration) -> Result<CleanupStats, sqlx::Error> {
+    let retention_cutoff = Utc::now() - retention;
+    let expiry_cutoff = retention_cutoff - refresh_ttl;
+
+    let mut txn = pool.begin().await?;
+    let tokens_removed = query!(
+        "DELETE FROM token t
+        WHERE t.created_at < $1
+        OR (
+            t.created_at < $2
+            AND NOT EXISTS (SELECT 1 FROM token_family_rel tfr WHERE tfr.token_id = t.id AND tfr.status = 'ACTIVE')
+        )",
+        expiry_cutoff,
+        retention_cutoff
+    )
+    .execute(&mut *txn)
+    .await?
+    .rows_affected();
+
+    let families_removed = query!(
+        "DELETE FROM token_family tf
+        WHERE tf.created_at < $1
+        AND NOT EXISTS (SELECT 1 FROM token_family_rel tfr WHERE tfr.token_family_id = tf.id)",
+        retention_cutoff
+    )
+    .execute(&mut *txn)
+    .await?
+    .rows_affected();
+    txn.commit().await?;
+
+    return Ok(CleanupStats {
+        tokens_removed,
+        families_removed,
+    });
+}
@@ -0,0 +1,80 @@
+use tracing::error;
+use serde::{Serialize, de::DeserializeOwned};
+use shared::{ErrorCode, Response};
+
+use crate::db::auth::jwt::{JWTError, RefreshRefreshTokenErr};
+
+/// Why a request could not be authenticated. Each variant maps to a stable [`ErrorCode`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AuthError {
+    MissingToken,
+    MalformedToken,
+    ExpiredToken,
+    RevokedToken,
+    InvalidToken,
+    UnsupportedVersion,
+    Internal,
+}
+
+impl AuthError {
+    pub fn code(&self) -> Option<ErrorCode> {
+        match self {
+            AuthError::MissingToken => Some(ErrorCode::AuthTokenMissing),
+            AuthError::MalformedToken => Some(ErrorCode::AuthTokenMalformed),
+            AuthError::ExpiredToken => Some(ErrorCode::AuthTokenExpired),
+            AuthError::RevokedToken => Some(ErrorCode::AuthTokenRevoked),
+            AuthError::InvalidToken => Some(ErrorCode::AuthTokenInvalid),
+            AuthError::UnsupportedVersion => Some(ErrorCode::AuthTokenUnsupportedVersion),
+            AuthError::Internal => None,
+        }
+    }
+
+    pub fn message(&self) -> &'static str {
+        match self {
+            AuthError::MissingToken => "Missing bearer token",
+            AuthError::MalformedToken => "Malformed token",
+            AuthError::ExpiredToken => "Token expired",
+            AuthError::RevokedToken => "Token revoked",
+            AuthError::InvalidToken => "Invalid token",
+            AuthError::UnsupportedVersion => "Token version no longer supported, log in again",
+            AuthError::Internal => "Could not verify token",
+        }
+    }
+}
+
+impl From<JWTError> for AuthError {
+    fn from(value: JWTError) -> Self {
+        match value {
+            JWTError::Expired => AuthError::ExpiredToken,
+            JWTError::Malformed => AuthError::MalformedToken,
+            JWTError::UnsupportedVersion(_) => AuthError::UnsupportedVersion,
+            JWTError::WrongTokenType | JWTError::Other => AuthError::InvalidToken,
+        }
+    }
+}
+
+impl From<RefreshRefreshTokenErr> for AuthError {
+    fn from(value: RefreshRefreshTokenErr) -> Self {
+        match value {
+            RefreshRefreshTokenErr::Jwt(e) => e.into(),
+            RefreshRefreshTokenErr::RevokedToken => AuthError::RevokedToken,
+            RefreshRefreshTokenErr::InvalidToken => AuthError::InvalidToken,
+            RefreshRefreshTokenErr::Sqlx(error) => {
+                error!(error = %error, "database error while refreshing token");
+                AuthError::Internal
+            }
+        }
+    }
+}
+
+impl<T> From<AuthError> for Response<T>
+where
+    T: Serialize + DeserializeOwned,
+{
+    fn from(value: AuthError) -> Self {
+        match value.code() {
+            Some(code) => Response::unauthorized(value.message(), None).with_code(code),
+            None => Response::internal_error(value.message(), None),
+        }
+    }
+}
@@ -1,228 +1,227 @@
 use chrono::Utc;
-use macros::{any_cast, db_err, db_func};
+use macros::{db_err, db_func};
 use rocket::{
-    Request, error, http::Status, outcome::Outcome, request::{self, FromRequest}
+    Request, http::Status, outcome::Outcome, request::{self, FromRequest}
 };
+use tracing::{error, warn};
 use serde::{Deserialize, Serialize};
 use shared::AnyErr;
 use shared::db::signup::IdOnly;
 use sqlx::{query, query_as};
 
-use crate::db::auth::jwt;
+use crate::{config::TokenSettings, db::{auth::{error::AuthError, jwt, keys::{JwtKeys, KeySet}}, store::Store}};
 
 
+/// Bump when the claim layout changes and teach `get_*_claims` about the old version.
+pub const CLAIMS_VERSION: i64 = 2;
+pub const ISSUER: &str = "rust-chat";
+pub const AUDIENCE: &str = "rust-chat-api";
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum TokenType {
+    Access,
+    Refresh,
+}
+
 #[derive(Serialize, Deserialize)]
 pub struct Claims {
     pub version: i64,
     pub user_id: i32,
-    #[serde(with = "chrono::serde::ts_nanoseconds")]
+    pub iss: String,
+    pub aud: String,
+    pub jti: String,
+    pub token_type: TokenType,
+    #[serde(with = "chrono::serde::ts_seconds")]
+    pub iat: chrono::DateTime<chrono::Utc>,
+    #[serde(with = "chrono::serde::ts_seconds")]
     pub exp: chrono::DateTime<chrono::Utc>,
 }
+
+#[derive(Deserialize)]
+struct ClaimsVersion {
+    version: Option<i64>,
+}
+
 #[async_trait]
 impl<'r> FromRequest<'r> for Claims {
-    type Error = AnyErr;
+    type Error = AuthError;
 
+    /// Failures are also stashed in the request-local cache so the 401 catcher can report them.
     async fn from_request(req: &'r Request<'_>) -> request::Outcome<Self, Self::Error> {
-        let authorization = req.headers().get_one("Authorization");
-        if let Some(authorization) = authorization {
-            let token = authorization.split(" ").collect::<Vec<_>>();
-            if token.len() == 2 {
-                let claims = jwt::get_access_claims(token[1]);
-                match claims {
-                    Ok(c) => {
-                        return Outcome::Success(c);
-                    },
-                    Err(_) => {
-                        return Outcome::Error((Status::from_code(401).unwrap(),AnyErr(())));
-                    },
-                }
+        let claims = match req.headers().get_one("Authorization") {
+            Some(authorization) => match authorization.split_once(' ') {
+                Some(("Bearer", token)) => jwt::get_access_claims(token).map_err(AuthError::from),
+                _ => Err(AuthError::MalformedToken),
+            },
+            None => Err(AuthError::MissingToken),
+        };
+        match claims {
+            Ok(c) => {
+                return Outcome::Success(c);
+            }
+            Err(e) => {
+                req.local_cache(|| Some(e));
+                return Outcome::Error((Status::Unauthorized, e));
             }
         }
-        return Outcome::Error((Status::from_code(401).unwrap(),AnyErr(())));
     }
 }
 
 impl Claims {
-    pub fn new_v1(user_id: i32, exp: chrono::DateTime<chrono::Utc>) -> Self {
+    pub fn new_v2(user_id: i32, token_type: TokenType, exp: chrono::DateTime<chrono::Utc>) -> Self {
         return Claims {
-            version: 1,
-            user_id: user_id,
+            version: CLAIMS_VERSION,
+            user_id,
+            iss: ISSUER.into(),
+            aud: AUDIENCE.into(),
+            jti: uuid::Uuid::new_v4().to_string(),
+            token_type,
+            iat: Utc::now(),
             exp,
         };
     }
 }
 
-pub enum CompareRefreshError {
-    NotFound,
-    Expired,
-    Revoked,
-    Other,
-}
-
 #[derive(Debug, PartialEq)]
-#[any_cast]
 pub enum JWTError {
     Expired,
+    Malformed,
+    UnsupportedVersion(Option<i64>),
+    WrongTokenType,
     Other,
 }
 
-pub fn get_refresh_claims(token: &str) -> Result<Claims, JWTError> {
-    let key = std::env::var("JWT_REFRESH_KEY").unwrap();
-    let key = jsonwebtoken::DecodingKey::from_base64_secret(&key).unwrap();
-
-    let claims = jsonwebtoken::decode::<Claims>(
-        token,
-        &key,
-        &jsonwebtoken::Validation::new(jsonwebtoken::Algorithm::default()),
-    );
-    match claims {
-        Ok(d) => {
-            let claims = d.claims;
-            return Ok(claims);
+impl From<jsonwebtoken::errors::Error> for JWTError {
+    fn from(e: jsonwebtoken::errors::Error) -> Self {
+        use jsonwebtoken::errors::ErrorKind;
+        match e.kind() {
+            ErrorKind::ExpiredSignature => return JWTError::Expired,
+            ErrorKind::InvalidToken | ErrorKind::Base64(_) | ErrorKind::Json(_) | ErrorKind::Utf8(_) => {
+                return JWTError::Malformed;
+            }
+            _ => return JWTError::Other,
         }
-        Err(e) => {
-            error!("JWT decode error: {:?}", e);
-            match e.kind() {
-            jsonwebtoken::errors::ErrorKind::ExpiredSignature => return Err(JWTError::Expired),
-            _ => return Err(JWTError::Other),
-        }},
     }
 }
 
-pub fn get_access_claims(token: &str) -> Result<Claims, JWTError> {
-    let key = std::env::var("JWT_ACCESS_KEY").unwrap();
-    let key = jsonwebtoken::DecodingKey::from_base64_secret(&key).unwrap();
+/// Checks the version, signature (picked by the header `kid`), issuer, audience, expiry and type.
+pub fn decode_claims(token: &str, keys: &KeySet, token_type: TokenType) -> Result<Claims, JWTError> {
+    // Only peeks at the version so old tokens get a clear error instead of a serde one.
+    // Nothing from this unverified read is trusted.
+    let version = jsonwebtoken::dangerous::insecure_decode::<ClaimsVersion>(token)?
+        .claims
+        .version;
+    if version != Some(CLAIMS_VERSION) {
+        warn!(version = ?version, expected = CLAIMS_VERSION, "rejecting token with unsupported claims version");
+        return Err(JWTError::UnsupportedVersion(version));
+    }
 
-    let claims = jsonwebtoken::decode::<Claims>(
-        token,
-        &key,
-        &jsonwebtoken::Validation::new(jsonwebtoken::Algorithm::default()),
-    );
-    match claims {
-        Ok(d) => {
-            let claims = d.claims;
-            return Ok(claims);
-        }
-        Err(e) => match e.kind() {
-            jsonwebtoken::errors::ErrorKind::ExpiredSignature => return Err(JWTError::Expired),
-            _ => return Err(JWTError::Other),
-        },
+    let header = jsonwebtoken::decode_header(token)?;
+    let key = keys
+        .decoding_key(header.kid.as_deref())
+        .ok_or(JWTError::Other)?;
+    let mut validation = jsonwebtoken::Validation::new(keys.algorithm);
+    validation.set_issuer(&[ISSUER]);
+    validation.set_audience(&[AUDIENCE]);
+    validation.set_required_spec_claims(&["exp", "iss", "aud"]);
+    let claims = jsonwebtoken::decode::<Claims>(token, key, &validation)?.claims;
+    if claims.token_type != token_type {
+        return Err(JWTError::WrongTokenType);
     }
+    return Ok(claims);
 }
 
-pub fn get_refresh_token(user_id: i32) -> String {
-    // 3 days expiry for refresh token
-    let expiration = Utc::now().checked_add_days(chrono::Days::new(3)).unwrap();
-    let claims = Claims::new_v1(user_id, expiration);
-    let refresh_token_key = std::env::var("JWT_REFRESH_KEY").unwrap();
-    let new_token = jsonwebtoken::encode(
-        &jsonwebtoken::Header::default(),
-        &claims,
-        &(jsonwebtoken::EncodingKey::from_base64_secret(&refresh_token_key).unwrap()),
-    )
-    .unwrap();
-    return new_token;
+/// Signs `claims` with the active key and names it in the `kid` header.
+pub fn encode_claims(claims: &Claims, keys: &KeySet) -> String {
+    let mut header = jsonwebtoken::Header::new(keys.algorithm);
+    header.kid = Some(keys.kid.clone());
+    return jsonwebtoken::encode(&header, claims, keys.encoding_key()).unwrap();
 }
 
-pub fn get_access_token(user_id: i32) -> String {
-    // 15 mins expiry for access token
-    let expiration = Utc::now()
-        .checked_add_signed(chrono::Duration::minutes(15))
-        .unwrap();
-    let claims = Claims::new_v1(user_id, expiration);
-    let refresh_token_key = std::env::var("JWT_ACCESS_KEY").unwrap();
-    let new_token = jsonwebtoken::encode(
-        &jsonwebtoken::Header::default(),
-        &claims,
-        &(jsonwebtoken::EncodingKey::from_base64_secret(&refresh_token_key).unwrap()),
-    )
-    .unwrap();
-    return new_token;
+pub fn get_refresh_claims(token: &str) -> Result<Claims, JWTError> {
+    let claims = decode_claims(token, &JwtKeys::get().refresh, TokenType::Refresh);
+    if let Err(e) = &claims {
+        error!(reason = ?e, "refresh token rejected");
+    }
+    return claims;
+}
+
+pub fn get_access_claims(token: &str) -> Result<Claims, JWTError> {
+    return decode_claims(token, &JwtKeys::get().access, TokenType::Access);
+}
+
+pub fn get_refresh_token(tokens: &TokenSettings, user_id: i32) -> String {
+    let expiration = Utc::now().checked_add_signed(tokens.refresh_ttl()).unwrap();
+    let claims = Claims::new_v2(user_id, TokenType::Refresh, expiration);
+    return encode_claims(&claims, &JwtKeys::get().refresh);
+}
+
+pub fn get_access_token(tokens: &TokenSettings, user_id: i32) -> String {
+    let expiration = Utc::now().checked_add_signed(tokens.access_ttl()).unwrap();
+    let claims = Claims::new_v2(user_id, TokenType::Access, expiration);
+    return encode_claims(&claims, &JwtKeys::get().access);
 }
 
-#[db_func]
 pub async fn get_access_token_from_refresh(
+    store: &Store,
+    tokens: &TokenSettings,
     refresh_token: &str,
-) -> Result<(String, String), AnyErr> {
+) -> Result<(String, String), AuthError> {
     let claims = get_refresh_claims(refresh_token)?;
     let user_id = claims.user_id;
-    let access_token = get_access_token(user_id);
-    let new_refresh_token = refresh_refresh_token(pool, refresh_token).await;
-    match new_refresh_token {
-        Ok(refresh_token) => Ok((access_token,refresh_token.into())),
-        Err(err) => {match err {
-            RefreshRefreshTokenErr::ExpiredToken => {
-                error!("Refresh token expired");
-                Err(err.into())
-            },
-            RefreshRefreshTokenErr::InvalidToken => {
-                error!("Refresh token invalid");
-                Err(err.into())
-            },
-            RefreshRefreshTokenErr::Sqlx(error) => {
-                error!("SQLX error: {}", error.to_string());
-                Err(AnyErr(()))
-            }
-        }
-    },
-    }
-    // return Ok((access_token, new_refresh_token));
+    let access_token = get_access_token(tokens, user_id);
+    let new_refresh_token = refresh_refresh_token(store, tokens, refresh_token).await?;
+    return Ok((access_token, new_refresh_token));
 }
 
-#[db_func]
-pub async fn get_new_refresh_token(user_id: i32) -> Result<String, ()> {
-    let token = get_refresh_token(user_id);
-    let r = add_new_token_to_new_family(pool, user_id, &token).await;
-    if r.is_err() {
-        return Err(());
-    }
+pub async fn get_new_refresh_token(store: &Store, tokens: &TokenSettings, user_id: i32) -> Result<String, sqlx::Error> {
+    let token = get_refresh_token(tokens, user_id);
+    store.add_token_family(user_id, &token).await?;
     return Ok(token);
 }
 
 #[db_err]
 pub enum RefreshRefreshTokenErr {
-    ExpiredToken,
+    #[response(status = 401, code = AuthTokenInvalid, message = "Invalid token")]
+    Jwt(JWTError),
+    #[response(status = 401, code = AuthTokenInvalid, message = "Invalid token")]
     InvalidToken,
+    #[response(status = 401, code = AuthTokenRevoked, message = "Token revoked")]
+    RevokedToken,
 }
 
-#[db_func]
-pub async fn refresh_refresh_token(token: &str) -> Result<String, RefreshRefreshTokenErr> {
-    let claims = get_refresh_claims(token);
-
-    match claims {
-        Ok(claims) => {
-            let user_id = claims.user_id;
-            let new_token = get_refresh_token(user_id);
-            add_token(pool, &new_token, &token).await?;
-            Ok(new_token)
-        }
-        Err(e) => match e {
-            JWTError::Expired => Err(RefreshRefreshTokenErr::ExpiredToken),
-            JWTError::Other => Err(RefreshRefreshTokenErr::InvalidToken),
-        },
-    }
+pub async fn refresh_refresh_token(store: &Store, tokens: &TokenSettings, token: &str) -> Result<String, RefreshRefreshTokenErr> {
+    let claims = get_refresh_claims(token).map_err(RefreshRefreshTokenErr::Jwt)?;
+    let new_token = get_refresh_token(tokens, claims.user_id);
+    store.rotate_refresh_token(token, &new_token).await?;
+    return Ok(new_token);
 }
 
-#[db_func]
+/// Rotates `old_token` out of its family. Runs on the caller's transaction.
+#[db_func(conn)]
 pub async fn add_token(token: &str, old_token: &str) -> Result<(), RefreshRefreshTokenErr> {
-    let mut txn = pool.begin().await?;
-    let family_id = query_as!(IdOnly,"SELECT tfr.token_family_id as id FROM token_family_rel tfr JOIN token t on t.id = tfr.token_id WHERE t.token = $1",old_token).fetch_optional(&mut *txn).await?;
-    if family_id.is_none() {
+    let family = query!("SELECT tfr.token_family_id, tfr.status FROM token_family_rel tfr JOIN token t on t.id = tfr.token_id WHERE t.token = $1",old_token).fetch_optional(&mut *conn).await?;
+    let Some(family) = family else {
         return Err(RefreshRefreshTokenErr::InvalidToken);
+    };
+    // A token that was already rotated out must not be usable a second time.
+    if family.status != "ACTIVE" {
+        return Err(RefreshRefreshTokenErr::RevokedToken);
     }
-    let family_id = family_id.unwrap().id;
+    let family_id = family.token_family_id;
     query!(
         "UPDATE token_family_rel SET status = 'expired' WHERE token_family_id = $1",
         family_id
     )
-    .execute(&mut *txn)
+    .execute(&mut *conn)
     .await?;
     let res = query_as!(
         IdOnly,
         "INSERT INTO token (token) VALUES ($1) returning id",
         token
     )
-    .fetch_one(&mut *txn)
+    .fetch_one(&mut *conn)
     .await?;
     query!(
         "INSERT INTO token_family_rel (token_family_id,status,token_id) VALUES ($1,$2,$3)",
@@ -230,35 +229,33 @@ pub async fn add_token(token: &str, old_token: &str) -> Result<(), RefreshRefres
         "ACTIVE",
         res.id
     )
-    .execute(&mut *txn)
+    .execute(&mut *conn)
     .await?;
-    txn.commit().await.unwrap();
     return Ok(());
 }
 
-#[db_func]
+#[db_func(conn)]
 pub async fn add_new_token_to_new_family(user_id: i32, token: &str) -> Result<IdOnly, sqlx::Error> {
-    let mut txn = pool.begin().await.unwrap();
     let family_id = query_as!(
         IdOnly,
         "INSERT INTO token_family (user_id) VALUES ($1) returning id",
         user_id
     )
-    .fetch_one(&mut *txn)
+    .fetch_one(&mut *conn)
     .await?
     .id;
     query!(
         "UPDATE token_family_rel SET status = 'expired' WHERE token_family_id = $1",
         family_id
     )
-    .execute(&mut *txn)
+    .execute(&mut *conn)
     .await?;
     let res = query_as!(
         IdOnly,
         "INSERT INTO token (token) VALUES ($1) returning id",
         token
     )
-    .fetch_one(&mut *txn)
+    .fetch_one(&mut *conn)
     .await?;
     query!(
         "INSERT INTO token_family_rel (token_family_id,status,token_id) VALUES ($1,$2,$3)",
@@ -266,8 +263,7 @@ pub async fn add_new_token_to_new_family(user_id: i32, token: &str) -> Result<Id
         "ACTIVE",
         res.id
     )
-    .execute(&mut *txn)
+    .execute(&mut *conn)
     .await?;
-    txn.commit().await.unwrap();
     return Ok(res);
 }
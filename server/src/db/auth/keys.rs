@@ -0,0 +1,244 @@
+use std::{collections::HashMap, env, fs, sync::OnceLock};
+
+use base64::{Engine, engine::general_purpose::URL_SAFE_NO_PAD};
+use jsonwebtoken::{
+    Algorithm, DecodingKey, EncodingKey,
+    jwk::{
+        AlgorithmParameters, CommonParameters, EllipticCurve, Jwk, JwkSet, KeyAlgorithm,
+        OctetKeyPairParameters, OctetKeyPairType, PublicKeyUse, RSAKeyParameters, RSAKeyType,
+    },
+};
+use rsa::{pkcs1::DecodeRsaPublicKey, pkcs8::DecodePublicKey, traits::PublicKeyParts};
+
+/// Signing key plus every key still accepted for verification, indexed by `kid`.
+pub struct KeySet {
+    pub algorithm: Algorithm,
+    pub kid: String,
+    encoding_key: EncodingKey,
+    decoding_keys: HashMap<String, DecodingKey>,
+    public_jwks: Vec<Jwk>,
+}
+
+impl KeySet {
+    pub fn encoding_key(&self) -> &EncodingKey {
+        return &self.encoding_key;
+    }
+
+    /// Tokens signed before `kid` headers were introduced fall back to the active key.
+    pub fn decoding_key(&self, kid: Option<&str>) -> Option<&DecodingKey> {
+        let kid = kid.unwrap_or(&self.kid);
+        return self.decoding_keys.get(kid);
+    }
+
+    /// A single HS256 key, for callers that don't configure keys through the environment.
+    pub fn hs256(kid: &str, secret: &[u8]) -> Self {
+        return KeySet {
+            algorithm: Algorithm::HS256,
+            kid: kid.into(),
+            encoding_key: EncodingKey::from_secret(secret),
+            decoding_keys: HashMap::from([(kid.into(), DecodingKey::from_secret(secret))]),
+            public_jwks: vec![],
+        };
+    }
+
+    /// An RS256 or EdDSA set signing as `kid` with the private key PEM at `signing_key`.
+    /// `verify_keys` are `(kid, public key PEM path)` pairs and should include `kid` itself.
+    pub fn from_pem_files(
+        algorithm: Algorithm,
+        kid: &str,
+        signing_key: &str,
+        verify_keys: &[(&str, &str)],
+    ) -> Result<Self, String> {
+        let pem = read_pem(signing_key)?;
+        let encoding_key = match algorithm {
+            Algorithm::RS256 => EncodingKey::from_rsa_pem(&pem),
+            Algorithm::EdDSA => EncodingKey::from_ed_pem(&pem),
+            other => return Err(format!("Unsupported JWT algorithm {other:?}")),
+        }
+        .map_err(|e| format!("Invalid private key in {signing_key}: {e}"))?;
+        let mut key_set = KeySet {
+            algorithm,
+            kid: kid.into(),
+            encoding_key,
+            decoding_keys: HashMap::new(),
+            public_jwks: vec![],
+        };
+        for (kid, path) in verify_keys {
+            key_set.add_verify_key(kid, path)?;
+        }
+        return Ok(key_set);
+    }
+
+    /// JWKs of the asymmetric verification keys; empty for HS256.
+    pub fn public_jwks(&self) -> &[Jwk] {
+        return &self.public_jwks;
+    }
+
+    /// Loads `JWT_<PREFIX>_*` variables.
+    ///
+    /// HS256 keeps reading the base64 secret from `JWT_<PREFIX>_KEY`. RS256 and EdDSA read a
+    /// private key PEM path from `JWT_<PREFIX>_SIGNING_KEY`. In both cases
+    /// `JWT_<PREFIX>_VERIFY_KEYS` is an optional `kid=value,...` list of retired keys that are
+    /// still accepted, where `value` is a secret for HS256 and a public key PEM path otherwise.
+    /// Asymmetric key sets must list the public key of the active `JWT_<PREFIX>_KID` there too.
+    fn from_env(prefix: &str, algorithm: Algorithm) -> Result<Self, String> {
+        let kid = env::var(format!("JWT_{prefix}_KID"))
+            .unwrap_or_else(|_| format!("{}-1", prefix.to_lowercase()));
+
+        let mut key_set = match algorithm {
+            Algorithm::HS256 => {
+                let secret = required_var(&format!("JWT_{prefix}_KEY"))?;
+                let encoding_key = EncodingKey::from_base64_secret(&secret)
+                    .map_err(|e| format!("JWT_{prefix}_KEY is not valid base64: {e}"))?;
+                let decoding_key = DecodingKey::from_base64_secret(&secret)
+                    .map_err(|e| format!("JWT_{prefix}_KEY is not valid base64: {e}"))?;
+                KeySet {
+                    algorithm,
+                    kid: kid.clone(),
+                    encoding_key,
+                    decoding_keys: HashMap::from([(kid, decoding_key)]),
+                    public_jwks: vec![],
+                }
+            }
+            Algorithm::RS256 | Algorithm::EdDSA => {
+                let path = required_var(&format!("JWT_{prefix}_SIGNING_KEY"))?;
+                KeySet::from_pem_files(algorithm, &kid, &path, &[])?
+            }
+            other => return Err(format!("Unsupported JWT algorithm {other:?}")),
+        };
+
+        let verify_keys = env::var(format!("JWT_{prefix}_VERIFY_KEYS")).unwrap_or_default();
+        for entry in verify_keys.split(',').map(str::trim).filter(|v| !v.is_empty()) {
+            let Some((kid, value)) = entry.split_once('=') else {
+                return Err(format!(
+                    "JWT_{prefix}_VERIFY_KEYS entries must look like kid=value, got {entry}"
+                ));
+            };
+            key_set.add_verify_key(kid.trim(), value.trim())?;
+        }
+
+        if !key_set.decoding_keys.contains_key(&key_set.kid) {
+            return Err(format!(
+                "No verification key for active kid {} in JWT_{prefix}_VERIFY_KEYS",
+                key_set.kid
+            ));
+        }
+        return Ok(key_set);
+    }
+
+    fn add_verify_key(&mut self, kid: &str, value: &str) -> Result<(), String> {
+        let decoding_key = match self.algorithm {
+            Algorithm::HS256 => DecodingKey::from_base64_secret(value)
+                .map_err(|e| format!("Verification secret for {kid} is not valid base64: {e}"))?,
+            _ => {
+                let jwk = public_jwk(kid, self.algorithm, &read_pem(value)?)?;
+                let key = DecodingKey::from_jwk(&jwk)
+                    .map_err(|e| format!("Invalid public key in {value}: {e}"))?;
+                self.public_jwks.push(jwk);
+                key
+            }
+        };
+        self.decoding_keys.insert(kid.into(), decoding_key);
+        return Ok(());
+    }
+}
+
+pub struct JwtKeys {
+    pub access: KeySet,
+    pub refresh: KeySet,
+}
+
+static JWT_KEYS: OnceLock<JwtKeys> = OnceLock::new();
+
+/// An Ed25519 SubjectPublicKeyInfo is this DER header (id-Ed25519, 1.3.101.112) followed by the
+/// 32 byte raw key.
+const ED25519_SPKI_PREFIX: [u8; 12] = [0x30, 0x2a, 0x30, 0x05, 0x06, 0x03, 0x2b, 0x65, 0x70, 0x03, 0x21, 0x00];
+
+impl JwtKeys {
+    /// Reads `JWT_ALGORITHM` (HS256, RS256 or EdDSA; HS256 by default) and both key sets.
+    pub fn init() -> Result<(), String> {
+        let algorithm = match env::var("JWT_ALGORITHM").as_deref() {
+            Err(_) | Ok("HS256") => Algorithm::HS256,
+            Ok("RS256") => Algorithm::RS256,
+            Ok("EdDSA") => Algorithm::EdDSA,
+            Ok(other) => return Err(format!("Unsupported JWT_ALGORITHM {other}")),
+        };
+        let keys = JwtKeys {
+            access: KeySet::from_env("ACCESS", algorithm)?,
+            refresh: KeySet::from_env("REFRESH", algorithm)?,
+        };
+        JWT_KEYS
+            .set(keys)
+            .ok()
+            .expect("JWT keys already initialized");
+        return Ok(());
+    }
+
+    /// Installs `keys` unless some were already loaded, e.g. by an earlier test in the same process.
+    pub fn init_with(keys: impl FnOnce() -> JwtKeys) {
+        JWT_KEYS.get_or_init(keys);
+    }
+
+    pub fn get() -> &'static JwtKeys {
+        return JWT_KEYS.get().expect("JWT keys not initialized");
+    }
+
+    /// Public access token keys. Refresh tokens are only ever verified by this server.
+    pub fn jwks() -> JwkSet {
+        return JwkSet {
+            keys: Self::get().access.public_jwks().to_vec(),
+        };
+    }
+}
+
+fn required_var(name: &str) -> Result<String, String> {
+    return env::var(name).map_err(|_| format!("{name} not set"));
+}
+
+fn read_pem(path: &str) -> Result<Vec<u8>, String> {
+    return fs::read(path).map_err(|e| format!("Unable to read key file {path}: {e}"));
+}
+
+fn public_jwk(kid: &str, algorithm: Algorithm, pem: &[u8]) -> Result<Jwk, String> {
+    let pem_str = String::from_utf8_lossy(pem);
+    let (key_algorithm, parameters) = match algorithm {
+        Algorithm::RS256 => {
+            let key = rsa::RsaPublicKey::from_public_key_pem(&pem_str)
+                .or_else(|_| rsa::RsaPublicKey::from_pkcs1_pem(&pem_str))
+                .map_err(|e| format!("Invalid RSA public key for {kid}: {e}"))?;
+            (
+                KeyAlgorithm::RS256,
+                AlgorithmParameters::RSA(RSAKeyParameters {
+                    key_type: RSAKeyType::RSA,
+                    n: URL_SAFE_NO_PAD.encode(key.n().to_bytes_be()),
+                    e: URL_SAFE_NO_PAD.encode(key.e().to_bytes_be()),
+                }),
+            )
+        }
+        _ => {
+            let der = pem::parse(pem)
+                .map_err(|e| format!("Invalid Ed25519 public key for {kid}: {e}"))?
+                .into_contents();
+            let Some(raw_key) = der.strip_prefix(&ED25519_SPKI_PREFIX).filter(|key| key.len() == 32) else {
+                return Err(format!("Invalid Ed25519 public key for {kid}"));
+            };
+            (
+                KeyAlgorithm::EdDSA,
+                AlgorithmParameters::OctetKeyPair(OctetKeyPairParameters {
+                    key_type: OctetKeyPairType::OctetKeyPair,
+                    curve: EllipticCurve::Ed25519,
+                    x: URL_SAFE_NO_PAD.encode(raw_key),
+                }),
+            )
+        }
+    };
+    return Ok(Jwk {
+        common: CommonParameters {
+            public_key_use: Some(PublicKeyUse::Signature),
+            key_algorithm: Some(key_algorithm),
+            key_id: Some(kid.into()),
+            ..Default::default()
+        },
+        algorithm: parameters,
+    });
+}
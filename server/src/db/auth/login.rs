@@ -3,24 +3,33 @@ use shared::{db::signup::User};
 use sqlx::{query_as};
 use shared::AnyErr;
 
+use crate::db::store::Store;
+
 #[db_err]
 #[derive(Debug)]
 pub enum LoginError{
+    #[response(status = 401, code = InvalidCredentials, message = "Wrong username or password")]
     WrongPassword,
+    #[response(status = 401, code = AccountDisabled, message = "This account has been disabled")]
+    AccountDisabled,
 }
 
-
 #[db_func]
-pub async fn check_password(username:&str,password:&str) -> Result<User,LoginError>{
+pub async fn find_user(username:&str) -> Result<Option<User>,sqlx::Error>{
     let user = query_as!(User,
-        r#"SELECT id,username,hash_password,created_at,updated_at from users where username = $1"#
-        ,username).fetch_optional(pool).await;
-    if let Err(e) = user {
-        return Err(e.into());
-    }
-    let user = user.unwrap();
+        r#"SELECT id,username,hash_password,created_at,updated_at,disabled_at,display_name,bio,status_text,avatar_updated_at,last_seen_at,hide_last_seen,contacts_only_dms from users where username = $1"#
+        ,username).fetch_optional(pool).await?;
+    return Ok(user);
+}
+
+pub async fn check_password(store: &Store, username:&str,password:&str) -> Result<User,LoginError>{
+    let user = store.find_user(username).await?;
     if let Some(user) = user {
         let pass = bcrypt::verify(password, &user.hash_password).unwrap();
+        // Checked after the password so the response doesn't reveal which accounts exist.
+        if pass && user.disabled_at.is_some() {
+            return Err(LoginError::AccountDisabled);
+        }
         if pass {
             return Ok(user);
         }else{
@@ -29,4 +38,3 @@ pub async fn check_password(username:&str,password:&str) -> Result<User,LoginErr
     }
     return Err(LoginError::WrongPassword);
 }
-
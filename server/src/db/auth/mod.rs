@@ -1,3 +1,6 @@
 pub mod signup;
 pub mod login;
-pub mod jwt;
\ No newline at end of file
+pub mod jwt;
+pub mod keys;
+pub mod cleanup;
+pub mod error;
\ No newline at end of file
@@ -5,40 +5,43 @@ use shared::db::signup::IdOnly;
 use shared::db::signup::User;
 use shared::AnyErr;
 
-#[db_func]
+use crate::db::store::Store;
+
+#[db_func(conn)]
 async fn get_user_from_username(username:&str) -> Result<User,sqlx::Error> {
-    let res = sqlx::query_as!(User,r#"SELECT id,username,hash_password,created_at,updated_at from users where username = $1"#,username).fetch_one(pool).await;
+    let res = sqlx::query_as!(User,r#"SELECT id,username,hash_password,created_at,updated_at,disabled_at,display_name,bio,status_text,avatar_updated_at,last_seen_at,hide_last_seen,contacts_only_dms from users where username = $1"#,username).fetch_one(&mut *conn).await;
     return res;
 }
 
-#[db_func]
-async fn create_account(username:&str,password:&str) -> Result<IdOnly,sqlx::Error> {
-    let hashed_password = bcrypt::hash(password,bcrypt::DEFAULT_COST).unwrap();
-    let res = sqlx::query_as!(IdOnly,"INSERT INTO users (username,hash_password) VALUES ($1,$2) returning id",username,hashed_password).fetch_one(pool).await;
+#[db_func(conn)]
+async fn create_account(username:&str,hashed_password:&str) -> Result<IdOnly,sqlx::Error> {
+    let res = sqlx::query_as!(IdOnly,"INSERT INTO users (username,hash_password) VALUES ($1,$2) returning id",username,hashed_password).fetch_one(&mut *conn).await;
     return res;
 }
 
 
 #[db_err]
 pub enum SignupError{
+    #[response(status = 400, code = UserAlreadyExists, message = "User already exists")]
     UserAlreadyExists
 }
-impl Into<String> for SignupError {
-    fn into(self) -> String {
-        match self {
-            SignupError::UserAlreadyExists => "User already exists".into(),
-            SignupError::Sqlx(error) => error.to_string(),
-        }
-    }
-}
 
 #[db_func]
-pub async fn signup(username:&str, password:&str)->Result<IdOnly,SignupError>{
-    let already_exists = get_user_from_username(pool, username).await.is_ok();
+pub async fn create_user(username:&str, hashed_password:&str)->Result<IdOnly,SignupError>{
+    let mut txn = pool.begin().await?;
+    let already_exists = get_user_from_username(&mut txn, username).await.is_ok();
     if already_exists {
         Err(SignupError::UserAlreadyExists)
     }else {
-        let user = create_account(pool, username, password).await;
-        user.map_err(|err|{err.into()})
+        let user = create_account(&mut txn, username, hashed_password).await?;
+        txn.commit().await?;
+        Ok(user)
     }
-}
\ No newline at end of file
+}
+
+/// Only creates the account: signup hands out no tokens, so the client's login that follows is a
+/// separate request with its own transaction.
+pub async fn signup(store: &Store, bcrypt_cost: u32, username:&str, password:&str)->Result<IdOnly,SignupError>{
+    let hashed_password = bcrypt::hash(password,bcrypt_cost).unwrap();
+    return store.create_user(username, &hashed_password).await;
+}
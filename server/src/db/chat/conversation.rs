@@ -1,11 +1,51 @@
+use chrono::{DateTime, Utc};
 use macros::{db_err, db_func};
-use shared::{db::signup::{IdOnly, User}, routes::chat::conversation::{ConversationMember, CreateConversationResponse}};
+use shared::{
+    db::signup::IdOnly,
+    routes::{
+        chat::conversation::{ConversationListItem, ConversationMember, CreateConversationResponse},
+        users::presence::Presence,
+    },
+};
 use sqlx::{query, query_as};
 use shared::AnyErr;
 
+use crate::db::{store::Store, users::block::Block};
+
 #[db_err]
 pub enum CreateConversationError {
+    #[response(status = 400, code = ConversationInvalidMembers, message = "One or more user IDs are invalid")]
     InvalidUsers,
+    #[response(status = 400, code = UserBlocked, message = "You blocked one of these users")]
+    BlockedUser,
+    #[response(status = 400, code = ContactsOnly, message = "One of these users only accepts conversations from contacts")]
+    ContactsOnly,
+}
+
+#[db_err]
+pub enum ListConversationsError {}
+
+#[db_err]
+pub enum MuteConversationError {
+    #[response(status = 404, code = NotFound, message = "Conversation not found")]
+    NotFound,
+}
+
+/// A `users` row as far as a member list needs it.
+pub struct MemberRow {
+    pub user_id: i32,
+    pub username: String,
+    pub display_name: Option<String>,
+    pub avatar_updated_at: Option<DateTime<Utc>>,
+    pub last_seen_at: Option<DateTime<Utc>>,
+    pub hide_last_seen: bool,
+}
+
+impl From<MemberRow> for ConversationMember {
+    fn from(row: MemberRow) -> Self {
+        let presence = Presence::offline(row.last_seen_at, row.hide_last_seen);
+        return ConversationMember::new(row.user_id, row.username, row.display_name, row.avatar_updated_at, presence);
+    }
 }
 
 struct ConversationIdAndName {
@@ -13,10 +53,19 @@ struct ConversationIdAndName {
     title: Option<String>,
 }
 
-#[db_func]
+/// Runs on the caller's transaction so the route can add more work before committing.
+#[db_func(conn)]
 pub async fn create_conversation(name: Option<String>, member_user_ids: Vec<i32>)-> Result<CreateConversationResponse, CreateConversationError> {
-    let mut txn = pool.begin().await.unwrap();
-
+    // Checked up front so unknown ids are a validation error rather than a foreign key violation.
+    let users_in_conversation = sqlx::query_as!(MemberRow,r#"SELECT id as user_id,username,display_name,avatar_updated_at,last_seen_at,hide_last_seen from users where id = ANY($1)"#, &member_user_ids)
+        .fetch_all(&mut *conn)
+        .await?
+        .into_iter()
+        .map(ConversationMember::from)
+        .collect::<Vec<_>>();
+    if users_in_conversation.len() != member_user_ids.len() {
+        return Err(CreateConversationError::InvalidUsers);
+    }
 
     let check_conversation_exists = query_as!(ConversationIdAndName,
         "SELECT cm.conversation_id as id, c.title as title FROM conversation_member cm JOIN conversation c on c.id = cm.conversation_id
@@ -24,44 +73,138 @@ pub async fn create_conversation(name: Option<String>, member_user_ids: Vec<i32>
         HAVING COUNT(cm.user_id) = $1
         AND COUNT(*) FILTER (WHERE cm.user_id = ANY($2)) = $1
         ", member_user_ids.len() as i64, member_user_ids.as_slice())
-        .fetch_optional(&mut *txn)
+        .fetch_optional(&mut *conn)
         .await
         ?;
 
+    if let Some(existing) = check_conversation_exists {
+        return Ok(
+            CreateConversationResponse { conversation_id: existing.id.to_string(), title: existing.title, members: users_in_conversation }
+        );
+    }
+
+    let create_conversation = query_as!(IdOnly,"INSERT INTO conversation (title,conv_type) VALUES ($1, 'group') RETURNING id", name)
+        .fetch_one(&mut *conn)
+        .await?;
+
+    let conversation_id = create_conversation.id;
 
-    if check_conversation_exists.is_none() {
-        let create_conversation = query_as!(IdOnly,"INSERT INTO conversation (title,conv_type) VALUES ($1, 'group') RETURNING id", name)
-            .fetch_one(&mut *txn)
+    // TODO: Optimize this with bulk insert
+    for user_id in member_user_ids.iter() {
+        query!("INSERT INTO conversation_member (conversation_id, user_id, role) VALUES ($1, $2, 'member')", conversation_id, user_id)
+            .execute(&mut *conn)
             .await?;
+    }
 
-        let conversation_id = create_conversation.id;
+    return Ok(CreateConversationResponse { conversation_id: conversation_id.to_string(), title: None, members: users_in_conversation });
+}
 
-        // TODO: Optimize this with bulk insert
-        for user_id in member_user_ids.iter() {
-            query!("INSERT INTO conversation_member (conversation_id, user_id, role) VALUES ($1, $2, 'member')", conversation_id, user_id)
-                .execute(&mut *txn)
-                .await?;
-        }
+/// Refuses conversations between users where either side blocked the other. Someone the caller
+/// blocked gets `BlockedUser`; someone who blocked the caller looks like an unknown user, so the
+/// caller can't tell they were blocked.
+pub async fn check_blocks(store: &Store, user_id: i32, participant_ids: &[i32]) -> Result<(), CreateConversationError> {
+    let blocks = store.find_blocks(user_id, participant_ids).await?;
+    if blocks.iter().any(|Block { blocker_id, .. }| *blocker_id == user_id) {
+        return Err(CreateConversationError::BlockedUser);
+    }
+    if !blocks.is_empty() {
+        return Err(CreateConversationError::InvalidUsers);
+    }
+    return Ok(());
+}
 
-        let users_in_conversation = sqlx::query_as!(ConversationMember,r#"SELECT id as user_id,username from users where id = ANY($1)"#, &member_user_ids).fetch_all(&mut *txn).await?;
-        if users_in_conversation.len() != member_user_ids.len() {
-            return Err(CreateConversationError::InvalidUsers);
-        }
+/// Refuses a direct conversation with a user who only accepts them from contacts, unless the
+/// caller is one. Group conversations aren't limited by the setting.
+pub async fn check_contacts_only(store: &Store, user_id: i32, participant_ids: &[i32]) -> Result<(), CreateConversationError> {
+    let mut others = participant_ids.iter().copied().filter(|id| *id != user_id).collect::<Vec<_>>();
+    others.sort_unstable();
+    others.dedup();
+    let [other_user_id] = others[..] else {
+        return Ok(());
+    };
+    if store.needs_contact(user_id, other_user_id).await? {
+        return Err(CreateConversationError::ContactsOnly);
+    }
+    return Ok(());
+}
 
-        txn.commit().await.unwrap();
-        
-        return Ok(CreateConversationResponse { conversation_id: conversation_id.to_string(), title: None, members: users_in_conversation });
-    }else{
-        let check_conversation_exists = check_conversation_exists.unwrap();
-        let users_in_conversation = sqlx::query_as!(ConversationMember,r#"SELECT id as user_id,username from users where id = ANY($1)"#, &member_user_ids).fetch_all(&mut *txn).await?;
-        if users_in_conversation.len() != member_user_ids.len() {
-            return Err(CreateConversationError::InvalidUsers);
-        }
+/// Returns false when `user_id` isn't a member of the conversation.
+#[db_func]
+pub async fn set_muted(conversation_id: i32, user_id: i32, muted: bool) -> Result<bool, sqlx::Error> {
+    let res = query!("UPDATE conversation_member SET muted = $3, updated_at = NOW() WHERE conversation_id = $1 AND user_id = $2",
+        conversation_id, user_id, muted)
+        .execute(pool)
+        .await?;
+    return Ok(res.rows_affected() > 0);
+}
 
-        return Ok(
-            CreateConversationResponse { conversation_id: check_conversation_exists.id.to_string(), title: check_conversation_exists.title, members: users_in_conversation }
-        )
+pub async fn mute(store: &Store, conversation_id: i32, user_id: i32, muted: bool) -> Result<(), MuteConversationError> {
+    if !store.set_conversation_muted(conversation_id, user_id, muted).await? {
+        return Err(MuteConversationError::NotFound);
+    }
+    return Ok(());
+}
 
-    };
+/// A conversation and everyone in it, as the admin CLI and the conversation list show them.
+#[derive(Debug, Clone)]
+pub struct ConversationSummary {
+    pub id: i32,
+    pub title: Option<String>,
+    pub members: Vec<ConversationMember>,
+    /// Whether the member the conversations were listed for muted this one. Always false when
+    /// listing every conversation.
+    pub muted: bool,
+}
 
-}
\ No newline at end of file
+/// One row per member, ordered by conversation.
+pub struct ConversationMemberRow {
+    pub conversation_id: i32,
+    pub title: Option<String>,
+    pub user_id: i32,
+    pub username: String,
+    pub display_name: Option<String>,
+    pub avatar_updated_at: Option<DateTime<Utc>>,
+    pub last_seen_at: Option<DateTime<Utc>>,
+    pub hide_last_seen: bool,
+    pub muted: bool,
+}
+
+impl From<ConversationSummary> for ConversationListItem {
+    fn from(value: ConversationSummary) -> Self {
+        return ConversationListItem { id: value.id, title: value.title, members: value.members, muted: value.muted };
+    }
+}
+
+impl ConversationSummary {
+    /// Folds rows ordered by `conversation_id` into one summary per conversation.
+    pub fn from_rows(rows: Vec<ConversationMemberRow>) -> Vec<Self> {
+        let mut summaries: Vec<ConversationSummary> = vec![];
+        for row in rows {
+            let presence = Presence::offline(row.last_seen_at, row.hide_last_seen);
+            let member = ConversationMember::new(row.user_id, row.username, row.display_name, row.avatar_updated_at, presence);
+            match summaries.last_mut() {
+                Some(last) if last.id == row.conversation_id => last.members.push(member),
+                _ => summaries.push(ConversationSummary { id: row.conversation_id, title: row.title, members: vec![member], muted: row.muted }),
+            }
+        }
+        return summaries;
+    }
+}
+
+/// Every conversation, or only those `member_user_id` belongs to.
+#[db_func]
+pub async fn list_conversations(member_user_id: Option<i32>) -> Result<Vec<ConversationSummary>, sqlx::Error> {
+    let rows = query_as!(ConversationMemberRow,
+        r#"SELECT c.id as conversation_id, c.title, u.id as user_id, u.username, u.display_name, u.avatar_updated_at, u.last_seen_at, u.hide_last_seen,
+            COALESCE(me.muted, FALSE) as "muted!"
+        FROM conversation c
+        JOIN conversation_member cm on cm.conversation_id = c.id
+        JOIN users u on u.id = cm.user_id
+        LEFT JOIN conversation_member me on me.conversation_id = c.id AND me.user_id = $1
+        WHERE $1::INTEGER IS NULL OR me.id IS NOT NULL
+        ORDER BY c.id, u.username"#,
+        member_user_id)
+        .fetch_all(pool)
+        .await?;
+    return Ok(ConversationSummary::from_rows(rows));
+}
@@ -0,0 +1,30 @@
+use macros::{db_err, db_func};
+use shared::db::signup::IdOnly;
+use sqlx::query_as;
+use shared::AnyErr;
+
+#[db_err]
+pub enum SendMessageError {
+    #[response(status = 404, code = NotFound, message = "Conversation not found")]
+    NotAMember,
+}
+
+/// Stores a text message from `sender_user_id`, who must already be a member of the conversation.
+#[db_func(conn)]
+pub async fn send_text_message(conversation_id: i32, sender_user_id: i32, text: &str) -> Result<IdOnly, SendMessageError> {
+    let member = query_as!(IdOnly, "SELECT id FROM conversation_member WHERE conversation_id = $1 AND user_id = $2", conversation_id, sender_user_id)
+        .fetch_optional(&mut *conn)
+        .await?;
+    let Some(member) = member else {
+        return Err(SendMessageError::NotAMember);
+    };
+    let content = query_as!(IdOnly, "INSERT INTO text_message_content (text) VALUES ($1) RETURNING id", text)
+        .fetch_one(&mut *conn)
+        .await?;
+    let message = query_as!(IdOnly,
+        "INSERT INTO message (conversation_id, sender_member_id, message_type, message_content_id) VALUES ($1, $2, 'text', $3) RETURNING id",
+        conversation_id, member.id, content.id)
+        .fetch_one(&mut *conn)
+        .await?;
+    return Ok(message);
+}
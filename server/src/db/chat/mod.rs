@@ -1 +1,2 @@
-pub mod conversation;
\ No newline at end of file
+pub mod conversation;
+pub mod message;
\ No newline at end of file
@@ -1,3 +1,4 @@
 pub mod auth;
 pub mod users;
-pub mod chat;
\ No newline at end of file
+pub mod chat;
+pub mod store;
\ No newline at end of file
@@ -0,0 +1,560 @@
+use std::sync::Mutex;
+
+use chrono::{DateTime, Duration, Utc};
+use shared::{db::signup::{IdOnly, User}, routes::{chat::conversation::{ConversationMember, CreateConversationResponse}, users::{account::ExportedMessage, contacts::ContactStatus, presence::Presence}}};
+use sqlx::migrate::MigrateError;
+
+use crate::db::{
+    auth::{cleanup::CleanupStats, jwt::RefreshRefreshTokenErr, signup::SignupError},
+    chat::conversation::{ConversationSummary, CreateConversationError},
+    store::{MigrationStatus, PoolStats, Storage},
+    users::{block::Block, contacts::{ContactChange, ContactError, ContactRow}, profile::{Avatar, ProfileChanges}, search::SearchUsersError},
+};
+
+/// Rows plus the last id handed out, like a `SERIAL` column.
+struct Table<T> {
+    rows: Vec<T>,
+    last_id: i32,
+}
+
+impl<T> Table<T> {
+    fn new() -> Self {
+        return Table { rows: vec![], last_id: 0 };
+    }
+
+    fn insert(&mut self, row: impl FnOnce(i32) -> T) -> i32 {
+        self.last_id += 1;
+        self.rows.push(row(self.last_id));
+        return self.last_id;
+    }
+}
+
+struct TokenFamily {
+    id: i32,
+    user_id: i32,
+    created_at: DateTime<Utc>,
+}
+
+struct Token {
+    token: String,
+    family_id: i32,
+    active: bool,
+    created_at: DateTime<Utc>,
+}
+
+struct Conversation {
+    id: i32,
+    title: Option<String>,
+    member_user_ids: Vec<i32>,
+    /// Members who muted the conversation.
+    muted_user_ids: Vec<i32>,
+}
+
+struct Message {
+    id: i32,
+    conversation_id: i32,
+    /// `None` once the sender's account is deleted.
+    sender_user_id: Option<i32>,
+    text: String,
+    created_at: DateTime<Utc>,
+}
+
+struct State {
+    users: Table<User>,
+    token_families: Table<TokenFamily>,
+    tokens: Table<Token>,
+    conversations: Table<Conversation>,
+    messages: Table<Message>,
+    /// Keyed by user id.
+    avatars: Vec<(i32, Avatar)>,
+    blocks: Vec<Block>,
+    contacts: Vec<ContactRow>,
+}
+
+/// Keeps everything in process memory. Meant for tests and quick local runs; nothing survives a
+/// restart. Every operation holds the lock for its whole duration, which makes it atomic.
+pub struct MemoryStorage {
+    state: Mutex<State>,
+}
+
+impl MemoryStorage {
+    pub fn new() -> Self {
+        return MemoryStorage {
+            state: Mutex::new(State {
+                users: Table::new(),
+                token_families: Table::new(),
+                tokens: Table::new(),
+                conversations: Table::new(),
+                messages: Table::new(),
+                avatars: vec![],
+                blocks: vec![],
+                contacts: vec![],
+            }),
+        };
+    }
+}
+
+fn member(user: &User) -> ConversationMember {
+    let presence = Presence::offline(user.last_seen_at, user.hide_last_seen);
+    return ConversationMember::new(user.id, user.username.clone(), user.display_name.clone(), user.avatar_updated_at, presence);
+}
+
+impl State {
+    fn revoke_token_families(&mut self, user_id: i32) -> u64 {
+        let State { token_families, tokens, .. } = self;
+        let mut revoked = 0;
+        for token in tokens.rows.iter_mut().filter(|t| t.active) {
+            if token_families.rows.iter().any(|f| f.id == token.family_id && f.user_id == user_id) {
+                token.active = false;
+                revoked += 1;
+            }
+        }
+        return revoked;
+    }
+
+    fn remove_contact(&mut self, a: i32, b: i32) {
+        self.contacts.retain(|c| !((c.requester_id == a && c.addressee_id == b) || (c.requester_id == b && c.addressee_id == a)));
+    }
+
+    fn blocked_between(&self, a: i32, b: i32) -> bool {
+        return self.blocks.iter().any(|block| {
+            (block.blocker_id == a && block.blocked_id == b) || (block.blocker_id == b && block.blocked_id == a)
+        });
+    }
+}
+
+impl Default for MemoryStorage {
+    fn default() -> Self {
+        return Self::new();
+    }
+}
+
+#[rocket::async_trait]
+impl Storage for MemoryStorage {
+    async fn find_user(&self, username: &str) -> Result<Option<User>, sqlx::Error> {
+        let state = self.state.lock().unwrap();
+        return Ok(state.users.rows.iter().find(|u| u.username == username).cloned());
+    }
+
+    async fn find_user_by_id(&self, user_id: i32) -> Result<Option<User>, sqlx::Error> {
+        let state = self.state.lock().unwrap();
+        return Ok(state.users.rows.iter().find(|u| u.id == user_id).cloned());
+    }
+
+    async fn create_user(&self, username: &str, hash_password: &str) -> Result<IdOnly, SignupError> {
+        let mut state = self.state.lock().unwrap();
+        if state.users.rows.iter().any(|u| u.username == username) {
+            return Err(SignupError::UserAlreadyExists);
+        }
+        let now = Utc::now();
+        let id = state.users.insert(|id| User {
+            id,
+            username: username.into(),
+            hash_password: hash_password.into(),
+            created_at: now,
+            updated_at: now,
+            disabled_at: None,
+            display_name: None,
+            bio: None,
+            status_text: None,
+            avatar_updated_at: None,
+            last_seen_at: None,
+            hide_last_seen: false,
+            contacts_only_dms: false,
+        });
+        return Ok(IdOnly { id });
+    }
+
+    async fn search_users(&self, viewer_id: i32, name: &str, limit: i64, after: Option<&str>) -> Result<Vec<User>, SearchUsersError> {
+        let state = self.state.lock().unwrap();
+        let name = name.to_lowercase();
+        let key = |username: &str| (!username.to_lowercase().starts_with(&name), username.to_string());
+        let after = after.map(key);
+        let mut users = state
+            .users
+            .rows
+            .iter()
+            .filter(|u| u.username.to_lowercase().contains(&name) && u.id != viewer_id && !state.blocked_between(viewer_id, u.id))
+            .filter(|u| after.as_ref().is_none_or(|after| key(&u.username) > *after))
+            .cloned()
+            .collect::<Vec<_>>();
+        users.sort_by_key(|u| key(&u.username));
+        users.truncate(limit as usize);
+        return Ok(users);
+    }
+
+    async fn add_token_family(&self, user_id: i32, token: &str) -> Result<(), sqlx::Error> {
+        let mut state = self.state.lock().unwrap();
+        let now = Utc::now();
+        let family_id = state.token_families.insert(|id| TokenFamily { id, user_id, created_at: now });
+        state.tokens.insert(|_| Token { token: token.into(), family_id, active: true, created_at: now });
+        return Ok(());
+    }
+
+    async fn rotate_refresh_token(&self, old_token: &str, new_token: &str) -> Result<(), RefreshRefreshTokenErr> {
+        let mut state = self.state.lock().unwrap();
+        let Some(old) = state.tokens.rows.iter().find(|t| t.token == old_token) else {
+            return Err(RefreshRefreshTokenErr::InvalidToken);
+        };
+        if !old.active {
+            return Err(RefreshRefreshTokenErr::RevokedToken);
+        }
+        let family_id = old.family_id;
+        for token in state.tokens.rows.iter_mut().filter(|t| t.family_id == family_id) {
+            token.active = false;
+        }
+        state.tokens.insert(|_| Token { token: new_token.into(), family_id, active: true, created_at: Utc::now() });
+        return Ok(());
+    }
+
+    async fn cleanup_tokens(&self, refresh_ttl: Duration, retention: Duration) -> Result<CleanupStats, sqlx::Error> {
+        let retention_cutoff = Utc::now() - retention;
+        let expiry_cutoff = retention_cutoff - refresh_ttl;
+        let mut state = self.state.lock().unwrap();
+
+        let tokens_before = state.tokens.rows.len();
+        state.tokens.rows.retain(|t| {
+            let expired = t.created_at < expiry_cutoff;
+            let rotated_out = t.created_at < retention_cutoff && !t.active;
+            return !(expired || rotated_out);
+        });
+        let tokens_removed = tokens_before - state.tokens.rows.len();
+
+        let families_before = state.token_families.rows.len();
+        let State { token_families, tokens, .. } = &mut *state;
+        token_families.rows.retain(|f| {
+            f.created_at >= retention_cutoff || tokens.rows.iter().any(|t| t.family_id == f.id)
+        });
+        let families_removed = families_before - state.token_families.rows.len();
+
+        return Ok(CleanupStats {
+            tokens_removed: tokens_removed as u64,
+            families_removed: families_removed as u64,
+        });
+    }
+
+    async fn create_conversation(
+        &self,
+        name: Option<String>,
+        member_user_ids: Vec<i32>,
+        first_message: Option<(i32, String)>,
+    ) -> Result<CreateConversationResponse, CreateConversationError> {
+        let mut state = self.state.lock().unwrap();
+
+        let members = state
+            .users
+            .rows
+            .iter()
+            .filter(|u| member_user_ids.contains(&u.id))
+            .map(member)
+            .collect::<Vec<_>>();
+        if members.len() != member_user_ids.len() {
+            return Err(CreateConversationError::InvalidUsers);
+        }
+        if let Some((sender_user_id, _)) = &first_message
+            && !member_user_ids.contains(sender_user_id)
+        {
+            return Err(CreateConversationError::InvalidUsers);
+        }
+
+        // Same rule as the SQL backends: reuse a conversation whose members are exactly these users.
+        let existing = state.conversations.rows.iter().find(|c| {
+            c.member_user_ids.len() == member_user_ids.len()
+                && c.member_user_ids.iter().all(|id| member_user_ids.contains(id))
+        });
+        let (conversation_id, title) = match existing {
+            Some(c) => (c.id, c.title.clone()),
+            None => {
+                let id = state.conversations.insert(|id| Conversation { id, title: name, member_user_ids, muted_user_ids: vec![] });
+                (id, None)
+            }
+        };
+
+        if let Some((sender_user_id, text)) = first_message {
+            state.messages.insert(|id| Message {
+                id,
+                conversation_id,
+                sender_user_id: Some(sender_user_id),
+                text,
+                created_at: Utc::now(),
+            });
+        }
+
+        return Ok(CreateConversationResponse { conversation_id: conversation_id.to_string(), title, members });
+    }
+
+    async fn migration_status(&self) -> Result<MigrationStatus, sqlx::Error> {
+        return Ok(MigrationStatus { latest_applied: None, pending: 0 });
+    }
+
+    async fn migrate_up(&self) -> Result<(), MigrateError> {
+        return Ok(());
+    }
+
+    async fn migrate_down(&self, _target: Option<i64>) -> Result<(), MigrateError> {
+        return Err(MigrateError::Source("the memory backend has no migrations to revert".into()));
+    }
+
+    fn pool_stats(&self) -> Option<PoolStats> {
+        return None;
+    }
+
+    async fn set_user_disabled(&self, user_id: i32, disabled: bool) -> Result<bool, sqlx::Error> {
+        let mut state = self.state.lock().unwrap();
+        let Some(user) = state.users.rows.iter_mut().find(|u| u.id == user_id) else {
+            return Ok(false);
+        };
+        let now = Utc::now();
+        user.disabled_at = if disabled { Some(user.disabled_at.unwrap_or(now)) } else { None };
+        user.updated_at = now;
+        if disabled {
+            state.revoke_token_families(user_id);
+        }
+        return Ok(true);
+    }
+
+    async fn set_password(&self, user_id: i32, hash_password: &str) -> Result<bool, sqlx::Error> {
+        let mut state = self.state.lock().unwrap();
+        let Some(user) = state.users.rows.iter_mut().find(|u| u.id == user_id) else {
+            return Ok(false);
+        };
+        user.hash_password = hash_password.into();
+        user.updated_at = Utc::now();
+        state.revoke_token_families(user_id);
+        return Ok(true);
+    }
+
+    async fn revoke_token_families(&self, user_id: i32) -> Result<u64, sqlx::Error> {
+        return Ok(self.state.lock().unwrap().revoke_token_families(user_id));
+    }
+
+    async fn delete_user(&self, user_id: i32) -> Result<bool, sqlx::Error> {
+        let mut state = self.state.lock().unwrap();
+        let users_before = state.users.rows.len();
+        state.users.rows.retain(|u| u.id != user_id);
+        if state.users.rows.len() == users_before {
+            return Ok(false);
+        }
+        // What the SQL backends' ON DELETE CASCADE and SET NULL do.
+        let State { token_families, tokens, conversations, messages, avatars, blocks, contacts, .. } = &mut *state;
+        avatars.retain(|(id, _)| *id != user_id);
+        blocks.retain(|b| b.blocker_id != user_id && b.blocked_id != user_id);
+        contacts.retain(|c| c.requester_id != user_id && c.addressee_id != user_id);
+        let families = token_families.rows.iter().filter(|f| f.user_id == user_id).map(|f| f.id).collect::<Vec<_>>();
+        token_families.rows.retain(|f| f.user_id != user_id);
+        tokens.rows.retain(|t| !families.contains(&t.family_id));
+        for conversation in conversations.rows.iter_mut() {
+            conversation.member_user_ids.retain(|id| *id != user_id);
+            conversation.muted_user_ids.retain(|id| *id != user_id);
+        }
+        for message in messages.rows.iter_mut().filter(|m| m.sender_user_id == Some(user_id)) {
+            message.sender_user_id = None;
+        }
+        return Ok(true);
+    }
+
+    async fn list_conversations(&self, member_user_id: Option<i32>) -> Result<Vec<ConversationSummary>, sqlx::Error> {
+        let state = self.state.lock().unwrap();
+        return Ok(state
+            .conversations
+            .rows
+            .iter()
+            .filter(|c| !c.member_user_ids.is_empty())
+            .filter(|c| member_user_id.is_none_or(|id| c.member_user_ids.contains(&id)))
+            .map(|c| {
+                let mut members = state
+                    .users
+                    .rows
+                    .iter()
+                    .filter(|u| c.member_user_ids.contains(&u.id))
+                    .map(member)
+                    .collect::<Vec<_>>();
+                members.sort_by(|a, b| a.username.cmp(&b.username));
+                let muted = member_user_id.is_some_and(|id| c.muted_user_ids.contains(&id));
+                return ConversationSummary { id: c.id, title: c.title.clone(), members, muted };
+            })
+            .collect());
+    }
+
+    async fn list_sent_messages(&self, user_id: i32) -> Result<Vec<ExportedMessage>, sqlx::Error> {
+        let state = self.state.lock().unwrap();
+        return Ok(state
+            .messages
+            .rows
+            .iter()
+            .filter(|m| m.sender_user_id == Some(user_id))
+            .map(|m| ExportedMessage {
+                id: m.id,
+                conversation_id: m.conversation_id,
+                text: m.text.clone(),
+                sent_at: m.created_at,
+            })
+            .collect());
+    }
+
+    async fn update_profile(&self, user_id: i32, changes: &ProfileChanges) -> Result<Option<User>, sqlx::Error> {
+        let mut state = self.state.lock().unwrap();
+        let Some(user) = state.users.rows.iter_mut().find(|u| u.id == user_id) else {
+            return Ok(None);
+        };
+        if let Some(display_name) = &changes.display_name {
+            user.display_name = display_name.clone();
+        }
+        if let Some(bio) = &changes.bio {
+            user.bio = bio.clone();
+        }
+        if let Some(status_text) = &changes.status_text {
+            user.status_text = status_text.clone();
+        }
+        if let Some(hide_last_seen) = changes.hide_last_seen {
+            user.hide_last_seen = hide_last_seen;
+        }
+        if let Some(contacts_only_dms) = changes.contacts_only_dms {
+            user.contacts_only_dms = contacts_only_dms;
+        }
+        user.updated_at = Utc::now();
+        return Ok(Some(user.clone()));
+    }
+
+    async fn set_avatar(&self, user_id: i32, avatar: Option<&Avatar>) -> Result<Option<User>, sqlx::Error> {
+        let mut state = self.state.lock().unwrap();
+        let State { users, avatars, .. } = &mut *state;
+        let Some(user) = users.rows.iter_mut().find(|u| u.id == user_id) else {
+            return Ok(None);
+        };
+        let now = Utc::now();
+        user.avatar_updated_at = avatar.map(|_| now);
+        user.updated_at = now;
+        avatars.retain(|(id, _)| *id != user_id);
+        if let Some(avatar) = avatar {
+            avatars.push((user_id, avatar.clone()));
+        }
+        return Ok(Some(user.clone()));
+    }
+
+    async fn get_avatar(&self, user_id: i32) -> Result<Option<Avatar>, sqlx::Error> {
+        let state = self.state.lock().unwrap();
+        let avatar = state.avatars.iter().find(|(id, _)| *id == user_id).map(|(_, avatar)| avatar.clone());
+        return Ok(avatar);
+    }
+
+    async fn touch_last_seen(&self, user_id: i32) -> Result<(), sqlx::Error> {
+        let mut state = self.state.lock().unwrap();
+        if let Some(user) = state.users.rows.iter_mut().find(|u| u.id == user_id) {
+            user.last_seen_at = Some(Utc::now());
+        }
+        return Ok(());
+    }
+
+    async fn block_user(&self, blocker_id: i32, blocked_id: i32) -> Result<bool, sqlx::Error> {
+        let mut state = self.state.lock().unwrap();
+        if !state.users.rows.iter().any(|u| u.id == blocked_id) {
+            return Ok(false);
+        }
+        let block = Block { blocker_id, blocked_id };
+        if !state.blocks.contains(&block) {
+            state.blocks.push(block);
+        }
+        return Ok(true);
+    }
+
+    async fn unblock_user(&self, blocker_id: i32, blocked_id: i32) -> Result<(), sqlx::Error> {
+        let mut state = self.state.lock().unwrap();
+        state.blocks.retain(|b| *b != Block { blocker_id, blocked_id });
+        return Ok(());
+    }
+
+    async fn list_blocked(&self, blocker_id: i32) -> Result<Vec<User>, sqlx::Error> {
+        let state = self.state.lock().unwrap();
+        let mut users = state
+            .users
+            .rows
+            .iter()
+            .filter(|u| state.blocks.contains(&Block { blocker_id, blocked_id: u.id }))
+            .cloned()
+            .collect::<Vec<_>>();
+        users.sort_by(|a, b| a.username.cmp(&b.username));
+        return Ok(users);
+    }
+
+    async fn find_blocks(&self, user_id: i32, other_user_ids: &[i32]) -> Result<Vec<Block>, sqlx::Error> {
+        let state = self.state.lock().unwrap();
+        return Ok(state
+            .blocks
+            .iter()
+            .filter(|b| {
+                (b.blocker_id == user_id && other_user_ids.contains(&b.blocked_id))
+                    || (b.blocked_id == user_id && other_user_ids.contains(&b.blocker_id))
+            })
+            .copied()
+            .collect());
+    }
+
+    async fn set_conversation_muted(&self, conversation_id: i32, user_id: i32, muted: bool) -> Result<bool, sqlx::Error> {
+        let mut state = self.state.lock().unwrap();
+        let Some(conversation) = state
+            .conversations
+            .rows
+            .iter_mut()
+            .find(|c| c.id == conversation_id && c.member_user_ids.contains(&user_id))
+        else {
+            return Ok(false);
+        };
+        conversation.muted_user_ids.retain(|id| *id != user_id);
+        if muted {
+            conversation.muted_user_ids.push(user_id);
+        }
+        return Ok(true);
+    }
+
+    async fn needs_contact(&self, user_id: i32, other_user_id: i32) -> Result<bool, sqlx::Error> {
+        let state = self.state.lock().unwrap();
+        if !state.users.rows.iter().any(|u| u.id == other_user_id && u.contacts_only_dms) {
+            return Ok(false);
+        }
+        let accepted = state.contacts.iter().any(|c| {
+            ((c.requester_id == user_id && c.addressee_id == other_user_id)
+                || (c.requester_id == other_user_id && c.addressee_id == user_id))
+                && c.status() == ContactStatus::Accepted
+        });
+        return Ok(!accepted);
+    }
+
+    async fn change_contact(&self, user_id: i32, other_user_id: i32, change: ContactChange) -> Result<ContactRow, ContactError> {
+        let mut state = self.state.lock().unwrap();
+        let current = state.contacts.iter().find(|c| {
+            (c.requester_id == user_id && c.addressee_id == other_user_id)
+                || (c.requester_id == other_user_id && c.addressee_id == user_id)
+        });
+        let Some((requester_id, addressee_id, status)) = change.apply(user_id, other_user_id, current)? else {
+            return Ok(current.cloned().unwrap());
+        };
+        state.remove_contact(requester_id, addressee_id);
+        let row = ContactRow { requester_id, addressee_id, status: status.as_str().into(), updated_at: Utc::now() };
+        state.contacts.push(row.clone());
+        return Ok(row);
+    }
+
+    async fn remove_contact(&self, user_id: i32, other_user_id: i32) -> Result<(), sqlx::Error> {
+        self.state.lock().unwrap().remove_contact(user_id, other_user_id);
+        return Ok(());
+    }
+
+    async fn list_contacts(&self, user_id: i32) -> Result<Vec<(ContactRow, User)>, sqlx::Error> {
+        let state = self.state.lock().unwrap();
+        let mut contacts = state
+            .contacts
+            .iter()
+            .filter_map(|c| {
+                let other_id = if c.requester_id == user_id { c.addressee_id } else { c.requester_id };
+                if c.requester_id != user_id && c.addressee_id != user_id {
+                    return None;
+                }
+                let other = state.users.rows.iter().find(|u| u.id == other_id)?;
+                return Some((c.clone(), other.clone()));
+            })
+            .collect::<Vec<_>>();
+        contacts.sort_by(|a, b| a.1.username.cmp(&b.1.username));
+        return Ok(contacts);
+    }
+}
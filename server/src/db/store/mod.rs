@@ -0,0 +1,160 @@
+use std::sync::Arc;
+
+use shared::{db::signup::{IdOnly, User}, routes::{chat::conversation::CreateConversationResponse, users::account::ExportedMessage}};
+use sqlx::migrate::{MigrateError, Migrator};
+
+use crate::config::Settings;
+use crate::db::{
+    auth::{cleanup::CleanupStats, jwt::RefreshRefreshTokenErr, signup::SignupError},
+    chat::conversation::{ConversationSummary, CreateConversationError},
+    users::{block::Block, contacts::{ContactChange, ContactError, ContactRow}, profile::{Avatar, ProfileChanges}, search::SearchUsersError},
+};
+
+pub mod memory;
+pub mod postgres;
+pub mod sqlite;
+
+/// Where the database stands against the migrations this build ships.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MigrationStatus {
+    /// Newest migration recorded as applied, `None` for an empty or in-process database.
+    pub latest_applied: Option<i64>,
+    /// Shipped migrations that haven't been applied (or failed half way).
+    pub pending: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PoolStats {
+    pub connections: u32,
+    pub idle: usize,
+}
+
+impl MigrationStatus {
+    /// `applied` are the versions recorded as successful in `_sqlx_migrations`.
+    pub fn new(migrator: &Migrator, applied: &[i64]) -> Self {
+        return MigrationStatus {
+            latest_applied: applied.iter().max().copied(),
+            pending: migrator
+                .iter()
+                .filter(|m| m.migration_type.is_up_migration() && !applied.contains(&m.version))
+                .count(),
+        };
+    }
+}
+
+/// The version to undo back to when only the newest applied migration should be reverted.
+pub fn previous_migration(migrator: &Migrator, latest_applied: Option<i64>) -> i64 {
+    let Some(latest) = latest_applied else {
+        return 0;
+    };
+    return migrator
+        .iter()
+        .map(|m| m.version)
+        .filter(|version| *version < latest)
+        .max()
+        .unwrap_or(0);
+}
+
+/// Everything the routes need from a database.
+///
+/// Each backend keeps multi-step operations (signup, token rotation, conversation creation) atomic
+/// on its own; password hashing and JWT handling stay above this layer.
+#[rocket::async_trait]
+pub trait Storage: Send + Sync {
+    async fn find_user(&self, username: &str) -> Result<Option<User>, sqlx::Error>;
+    async fn find_user_by_id(&self, user_id: i32) -> Result<Option<User>, sqlx::Error>;
+    async fn create_user(&self, username: &str, hash_password: &str) -> Result<IdOnly, SignupError>;
+    /// Leaves out users `viewer_id` blocked or was blocked by.
+    async fn search_users(&self, viewer_id: i32, name: &str, limit: i64, after: Option<&str>) -> Result<Vec<User>, SearchUsersError>;
+
+    /// Starts a new refresh token family for a fresh login.
+    async fn add_token_family(&self, user_id: i32, token: &str) -> Result<(), sqlx::Error>;
+    /// Replaces `old_token` with `new_token` in its family, failing if it was already rotated out.
+    async fn rotate_refresh_token(&self, old_token: &str, new_token: &str) -> Result<(), RefreshRefreshTokenErr>;
+    /// See `db::auth::cleanup::cleanup_tokens` for what counts as unusable.
+    async fn cleanup_tokens(&self, refresh_ttl: chrono::Duration, retention: chrono::Duration) -> Result<CleanupStats, sqlx::Error>;
+
+    /// Returns the existing conversation with exactly these members if there is one.
+    /// `first_message` is `(sender_user_id, text)` and is stored in the same transaction.
+    async fn create_conversation(
+        &self,
+        name: Option<String>,
+        member_user_ids: Vec<i32>,
+        first_message: Option<(i32, String)>,
+    ) -> Result<CreateConversationResponse, CreateConversationError>;
+
+    /// Also serves as the readiness check, so it must go to the database every time.
+    async fn migration_status(&self) -> Result<MigrationStatus, sqlx::Error>;
+
+    /// Applies every pending migration.
+    async fn migrate_up(&self) -> Result<(), MigrateError>;
+    /// Reverts every applied migration newer than `target`, or only the newest one without it.
+    async fn migrate_down(&self, target: Option<i64>) -> Result<(), MigrateError>;
+
+    /// Connection pool usage, `None` for backends without a pool.
+    fn pool_stats(&self) -> Option<PoolStats>;
+
+    /// Disabling also revokes the user's token families. Returns false for an unknown user.
+    async fn set_user_disabled(&self, user_id: i32, disabled: bool) -> Result<bool, sqlx::Error>;
+    /// Also revokes the user's token families. Returns false for an unknown user.
+    async fn set_password(&self, user_id: i32, hash_password: &str) -> Result<bool, sqlx::Error>;
+    /// Returns how many families were still live.
+    async fn revoke_token_families(&self, user_id: i32) -> Result<u64, sqlx::Error>;
+    /// Drops the user's tokens and memberships but keeps the messages they sent, with no sender.
+    /// Returns false for an unknown user.
+    async fn delete_user(&self, user_id: i32) -> Result<bool, sqlx::Error>;
+    /// Every conversation, or only those `member_user_id` belongs to, ordered by id. `muted` is
+    /// `member_user_id`'s own setting.
+    async fn list_conversations(&self, member_user_id: Option<i32>) -> Result<Vec<ConversationSummary>, sqlx::Error>;
+    /// Text messages `user_id` sent, oldest first.
+    async fn list_sent_messages(&self, user_id: i32) -> Result<Vec<ExportedMessage>, sqlx::Error>;
+    /// Returns the updated user, or `None` for an unknown user.
+    async fn update_profile(&self, user_id: i32, changes: &ProfileChanges) -> Result<Option<User>, sqlx::Error>;
+    /// Replaces the avatar, or removes it for `None`. Returns `None` for an unknown user.
+    async fn set_avatar(&self, user_id: i32, avatar: Option<&Avatar>) -> Result<Option<User>, sqlx::Error>;
+    async fn get_avatar(&self, user_id: i32) -> Result<Option<Avatar>, sqlx::Error>;
+    /// Sets `last_seen_at` to now. Unknown users are ignored.
+    async fn touch_last_seen(&self, user_id: i32) -> Result<(), sqlx::Error>;
+    /// Returns false for an unknown `blocked_id`. Blocking someone twice changes nothing.
+    async fn block_user(&self, blocker_id: i32, blocked_id: i32) -> Result<bool, sqlx::Error>;
+    async fn unblock_user(&self, blocker_id: i32, blocked_id: i32) -> Result<(), sqlx::Error>;
+    /// Everyone `blocker_id` blocked, ordered by username.
+    async fn list_blocked(&self, blocker_id: i32) -> Result<Vec<User>, sqlx::Error>;
+    /// Blocks in either direction between `user_id` and any of `other_user_ids`.
+    async fn find_blocks(&self, user_id: i32, other_user_ids: &[i32]) -> Result<Vec<Block>, sqlx::Error>;
+    /// Only affects `user_id`'s membership. Returns false when they aren't a member.
+    async fn set_conversation_muted(&self, conversation_id: i32, user_id: i32, muted: bool) -> Result<bool, sqlx::Error>;
+    /// Whether `other_user_id` only takes conversations from contacts and `user_id` isn't one.
+    async fn needs_contact(&self, user_id: i32, other_user_id: i32) -> Result<bool, sqlx::Error>;
+    /// Applies `change` to the pair's row atomically, so two users asking each other at once still
+    /// end up with one row.
+    async fn change_contact(&self, user_id: i32, other_user_id: i32, change: ContactChange) -> Result<ContactRow, ContactError>;
+    async fn remove_contact(&self, user_id: i32, other_user_id: i32) -> Result<(), sqlx::Error>;
+    /// Every contact row involving `user_id` with the other user of each, ordered by username.
+    async fn list_contacts(&self, user_id: i32) -> Result<Vec<(ContactRow, User)>, sqlx::Error>;
+}
+
+pub type Store = Arc<dyn Storage>;
+
+/// Picks the backend from the `database_url` scheme without touching its migrations.
+/// `memory://` keeps everything in process and starts empty on every run.
+pub async fn open(settings: &Settings) -> Store {
+    let db_url = settings.database_url.as_str();
+    if db_url.starts_with("memory:") {
+        return Arc::new(memory::MemoryStorage::new());
+    }
+    if db_url.starts_with("sqlite:") {
+        return Arc::new(sqlite::SqliteStorage::connect(db_url, &settings.database).await);
+    }
+    if db_url.starts_with("postgres:") || db_url.starts_with("postgresql:") {
+        return Arc::new(postgres::PostgresStorage::connect(db_url, &settings.database).await);
+    }
+    panic!("Unsupported DATABASE_URL scheme, expected postgres://, sqlite:// or memory://");
+}
+
+/// [`open`] plus every pending migration.
+pub async fn connect(settings: &Settings) -> Store {
+    let store = open(settings).await;
+    store.migrate_up().await.expect("Failed to run migrations");
+    return store;
+}
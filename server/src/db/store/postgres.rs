@@ -0,0 +1,208 @@
+use std::{str::FromStr, time::Duration};
+
+use shared::{db::signup::{IdOnly, User}, routes::{chat::conversation::CreateConversationResponse, users::account::ExportedMessage}};
+use sqlx::{PgPool, migrate::{MigrateError, Migrator}, postgres::{PgConnectOptions, PgPoolOptions}};
+
+use crate::config::DatabaseSettings;
+use crate::db::{
+    auth::{cleanup::{self, CleanupStats}, jwt::{self, RefreshRefreshTokenErr}, login, signup::{self, SignupError}},
+    chat::{conversation::{self, ConversationSummary, CreateConversationError}, message::{self, SendMessageError}},
+    store::{MigrationStatus, PoolStats, Storage, previous_migration},
+    users::{account, admin, block::{self, Block}, contacts::{self, ContactChange, ContactError, ContactRow}, profile::{self, Avatar, ProfileChanges}, search::{self, SearchUsersError}},
+};
+
+pub static MIGRATOR: Migrator = sqlx::migrate!("./migrations");
+
+pub struct PostgresStorage {
+    pool: PgPool,
+}
+
+impl PostgresStorage {
+    /// Wraps a pool whose migrations have already run, e.g. one handed out by `#[sqlx::test]`.
+    pub fn new(pool: PgPool) -> Self {
+        return PostgresStorage { pool };
+    }
+
+    pub async fn connect(db_url: &str, database: &DatabaseSettings) -> Self {
+        let connect_options = PgConnectOptions::from_str(db_url)
+            .unwrap();
+        let pool = PgPoolOptions::new()
+            .max_connections(database.max_connections)
+            .min_connections(database.min_connections)
+            .acquire_timeout(Duration::from_secs(database.acquire_timeout_secs))
+            .connect_with(connect_options)
+            .await
+            .expect("Unable to connect to database");
+        return Self::new(pool);
+    }
+}
+
+#[rocket::async_trait]
+impl Storage for PostgresStorage {
+    async fn find_user(&self, username: &str) -> Result<Option<User>, sqlx::Error> {
+        return login::find_user(&self.pool, username).await;
+    }
+
+    async fn find_user_by_id(&self, user_id: i32) -> Result<Option<User>, sqlx::Error> {
+        return account::find_user_by_id(&self.pool, user_id).await;
+    }
+
+    async fn create_user(&self, username: &str, hash_password: &str) -> Result<IdOnly, SignupError> {
+        return signup::create_user(&self.pool, username, hash_password).await;
+    }
+
+    async fn search_users(&self, viewer_id: i32, name: &str, limit: i64, after: Option<&str>) -> Result<Vec<User>, SearchUsersError> {
+        return search::search_users(&self.pool, viewer_id, name, limit, after).await;
+    }
+
+    async fn add_token_family(&self, user_id: i32, token: &str) -> Result<(), sqlx::Error> {
+        let mut txn = self.pool.begin().await?;
+        jwt::add_new_token_to_new_family(&mut txn, user_id, token).await?;
+        txn.commit().await?;
+        return Ok(());
+    }
+
+    async fn rotate_refresh_token(&self, old_token: &str, new_token: &str) -> Result<(), RefreshRefreshTokenErr> {
+        let mut txn = self.pool.begin().await?;
+        jwt::add_token(&mut txn, new_token, old_token).await?;
+        txn.commit().await?;
+        return Ok(());
+    }
+
+    async fn cleanup_tokens(&self, refresh_ttl: chrono::Duration, retention: chrono::Duration) -> Result<CleanupStats, sqlx::Error> {
+        return cleanup::cleanup_tokens(&self.pool, refresh_ttl, retention).await;
+    }
+
+    async fn create_conversation(
+        &self,
+        name: Option<String>,
+        member_user_ids: Vec<i32>,
+        first_message: Option<(i32, String)>,
+    ) -> Result<CreateConversationResponse, CreateConversationError> {
+        let mut txn = self.pool.begin().await?;
+        let response = conversation::create_conversation(&mut txn, name, member_user_ids).await?;
+        if let Some((sender_user_id, text)) = first_message {
+            let conversation_id = response.conversation_id.parse().unwrap();
+            message::send_text_message(&mut txn, conversation_id, sender_user_id, &text)
+                .await
+                .map_err(|e| match e {
+                    SendMessageError::Sqlx(e) => CreateConversationError::Sqlx(e),
+                    SendMessageError::NotAMember => CreateConversationError::InvalidUsers,
+                })?;
+        }
+        txn.commit().await?;
+        return Ok(response);
+    }
+
+    async fn migration_status(&self) -> Result<MigrationStatus, sqlx::Error> {
+        let applied: Vec<i64> = sqlx::query_scalar("SELECT version FROM _sqlx_migrations WHERE success")
+            .fetch_all(&self.pool)
+            .await?;
+        return Ok(MigrationStatus::new(&MIGRATOR, &applied));
+    }
+
+    async fn migrate_up(&self) -> Result<(), MigrateError> {
+        return MIGRATOR.run(&self.pool).await;
+    }
+
+    async fn migrate_down(&self, target: Option<i64>) -> Result<(), MigrateError> {
+        let target = match target {
+            Some(target) => target,
+            None => previous_migration(&MIGRATOR, self.migration_status().await?.latest_applied),
+        };
+        return MIGRATOR.undo(&self.pool, target).await;
+    }
+
+    fn pool_stats(&self) -> Option<PoolStats> {
+        return Some(PoolStats { connections: self.pool.size(), idle: self.pool.num_idle() });
+    }
+
+    async fn set_user_disabled(&self, user_id: i32, disabled: bool) -> Result<bool, sqlx::Error> {
+        let mut txn = self.pool.begin().await?;
+        let found = admin::set_disabled(&mut txn, user_id, disabled).await?;
+        if found && disabled {
+            admin::revoke_token_families(&mut txn, user_id).await?;
+        }
+        txn.commit().await?;
+        return Ok(found);
+    }
+
+    async fn set_password(&self, user_id: i32, hash_password: &str) -> Result<bool, sqlx::Error> {
+        let mut txn = self.pool.begin().await?;
+        let found = admin::set_password(&mut txn, user_id, hash_password).await?;
+        if found {
+            admin::revoke_token_families(&mut txn, user_id).await?;
+        }
+        txn.commit().await?;
+        return Ok(found);
+    }
+
+    async fn revoke_token_families(&self, user_id: i32) -> Result<u64, sqlx::Error> {
+        let mut conn = self.pool.acquire().await?;
+        return admin::revoke_token_families(&mut conn, user_id).await;
+    }
+
+    async fn delete_user(&self, user_id: i32) -> Result<bool, sqlx::Error> {
+        return admin::delete_user(&self.pool, user_id).await;
+    }
+
+    async fn list_conversations(&self, member_user_id: Option<i32>) -> Result<Vec<ConversationSummary>, sqlx::Error> {
+        return conversation::list_conversations(&self.pool, member_user_id).await;
+    }
+
+    async fn list_sent_messages(&self, user_id: i32) -> Result<Vec<ExportedMessage>, sqlx::Error> {
+        return account::list_sent_messages(&self.pool, user_id).await;
+    }
+
+    async fn update_profile(&self, user_id: i32, changes: &ProfileChanges) -> Result<Option<User>, sqlx::Error> {
+        return profile::update_profile(&self.pool, user_id, changes).await;
+    }
+
+    async fn set_avatar(&self, user_id: i32, avatar: Option<&Avatar>) -> Result<Option<User>, sqlx::Error> {
+        return profile::set_avatar(&self.pool, user_id, avatar).await;
+    }
+
+    async fn get_avatar(&self, user_id: i32) -> Result<Option<Avatar>, sqlx::Error> {
+        return profile::get_avatar(&self.pool, user_id).await;
+    }
+
+    async fn touch_last_seen(&self, user_id: i32) -> Result<(), sqlx::Error> {
+        return profile::touch_last_seen(&self.pool, user_id).await;
+    }
+
+    async fn block_user(&self, blocker_id: i32, blocked_id: i32) -> Result<bool, sqlx::Error> {
+        return block::block_user(&self.pool, blocker_id, blocked_id).await;
+    }
+
+    async fn unblock_user(&self, blocker_id: i32, blocked_id: i32) -> Result<(), sqlx::Error> {
+        return block::unblock_user(&self.pool, blocker_id, blocked_id).await;
+    }
+
+    async fn list_blocked(&self, blocker_id: i32) -> Result<Vec<User>, sqlx::Error> {
+        return block::list_blocked(&self.pool, blocker_id).await;
+    }
+
+    async fn find_blocks(&self, user_id: i32, other_user_ids: &[i32]) -> Result<Vec<Block>, sqlx::Error> {
+        return block::find_blocks(&self.pool, user_id, other_user_ids).await;
+    }
+
+    async fn set_conversation_muted(&self, conversation_id: i32, user_id: i32, muted: bool) -> Result<bool, sqlx::Error> {
+        return conversation::set_muted(&self.pool, conversation_id, user_id, muted).await;
+    }
+
+    async fn needs_contact(&self, user_id: i32, other_user_id: i32) -> Result<bool, sqlx::Error> {
+        return contacts::needs_contact(&self.pool, user_id, other_user_id).await;
+    }
+
+    async fn change_contact(&self, user_id: i32, other_user_id: i32, change: ContactChange) -> Result<ContactRow, ContactError> {
+        return contacts::change_contact(&self.pool, user_id, other_user_id, change).await;
+    }
+
+    async fn remove_contact(&self, user_id: i32, other_user_id: i32) -> Result<(), sqlx::Error> {
+        return contacts::remove_contact(&self.pool, user_id, other_user_id).await;
+    }
+
+    async fn list_contacts(&self, user_id: i32) -> Result<Vec<(ContactRow, User)>, sqlx::Error> {
+        return contacts::list_contacts(&self.pool, user_id).await;
+    }
+}
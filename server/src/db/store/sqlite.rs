@@ -0,0 +1,661 @@
+use std::{str::FromStr, time::Duration as StdDuration};
+
+use chrono::{DateTime, Duration, Utc};
+use shared::{db::signup::{IdOnly, User}, routes::{chat::conversation::{ConversationMember, CreateConversationResponse}, users::{account::ExportedMessage, contacts::ContactStatus, presence::Presence}}};
+use sqlx::{
+    Row, SqliteConnection, SqlitePool,
+    migrate::{MigrateError, Migrator},
+    sqlite::{SqliteConnectOptions, SqlitePoolOptions, SqliteRow},
+};
+
+use crate::config::DatabaseSettings;
+use crate::db::{
+    auth::{cleanup::CleanupStats, jwt::RefreshRefreshTokenErr, signup::SignupError},
+    chat::conversation::{ConversationMemberRow, ConversationSummary, CreateConversationError},
+    store::{MigrationStatus, PoolStats, Storage, previous_migration},
+    users::{block::Block, contacts::{ContactChange, ContactError, ContactRow}, profile::{Avatar, ProfileChanges}, search::{SearchUsersError, escape_like}},
+};
+
+/// Same schema as Postgres, with `ANY($1)` replaced by `json_each` over a JSON array of ids.
+pub static MIGRATOR: Migrator = sqlx::migrate!("./migrations_sqlite");
+
+pub struct SqliteStorage {
+    pool: SqlitePool,
+}
+
+impl SqliteStorage {
+    /// `sqlite://path/to/db.sqlite` creates the file if needed, `sqlite::memory:` keeps everything
+    /// on a single connection so the database lives as long as the pool.
+    pub async fn connect(db_url: &str, database: &DatabaseSettings) -> Self {
+        let connect_options = SqliteConnectOptions::from_str(db_url)
+            .unwrap()
+            .create_if_missing(true)
+            .foreign_keys(true);
+        let in_memory = db_url.contains(":memory:");
+        let max_connections = if in_memory { 1 } else { database.max_connections };
+        let min_connections = if in_memory { 1 } else { database.min_connections };
+        let pool = SqlitePoolOptions::new()
+            .max_connections(max_connections)
+            .min_connections(min_connections)
+            .acquire_timeout(StdDuration::from_secs(database.acquire_timeout_secs))
+            .connect_with(connect_options)
+            .await
+            .expect("Unable to connect to database");
+        return SqliteStorage { pool };
+    }
+}
+
+fn user_from_row(row: SqliteRow) -> User {
+    return User {
+        id: row.get("id"),
+        username: row.get("username"),
+        hash_password: row.get("hash_password"),
+        created_at: row.get("created_at"),
+        updated_at: row.get("updated_at"),
+        disabled_at: row.get("disabled_at"),
+        display_name: row.get("display_name"),
+        bio: row.get("bio"),
+        status_text: row.get("status_text"),
+        avatar_updated_at: row.get("avatar_updated_at"),
+        last_seen_at: row.get("last_seen_at"),
+        hide_last_seen: row.get("hide_last_seen"),
+        contacts_only_dms: row.get("contacts_only_dms"),
+    };
+}
+
+fn contact_from_row(row: &SqliteRow) -> ContactRow {
+    return ContactRow {
+        requester_id: row.get("requester_id"),
+        addressee_id: row.get("addressee_id"),
+        status: row.get("status"),
+        updated_at: row.get("contact_updated_at"),
+    };
+}
+
+/// Matches the format of the `strftime` column defaults so timestamps compare as text.
+fn timestamp(time: DateTime<Utc>) -> String {
+    return time.format("%Y-%m-%dT%H:%M:%S%.3fZ").to_string();
+}
+
+fn id_list(ids: &[i32]) -> String {
+    return serde_json::to_string(ids).unwrap();
+}
+
+async fn insert_token(conn: &mut SqliteConnection, family_id: i32, token: &str) -> Result<(), sqlx::Error> {
+    let token_id: i32 = sqlx::query_scalar("INSERT INTO token (token) VALUES (?1) RETURNING id")
+        .bind(token)
+        .fetch_one(&mut *conn)
+        .await?;
+    sqlx::query("INSERT INTO token_family_rel (token_family_id,status,token_id) VALUES (?1,'ACTIVE',?2)")
+        .bind(family_id)
+        .bind(token_id)
+        .execute(&mut *conn)
+        .await?;
+    return Ok(());
+}
+
+/// Same as the Postgres query in `db::users::admin::revoke_token_families`.
+async fn revoke_families(conn: &mut SqliteConnection, user_id: i32) -> Result<u64, sqlx::Error> {
+    let res = sqlx::query(
+        "UPDATE token_family_rel SET status = 'REVOKED', updated_at = strftime('%Y-%m-%dT%H:%M:%fZ', 'now')
+        WHERE status = 'ACTIVE'
+        AND token_family_id IN (SELECT id FROM token_family WHERE user_id = ?1)",
+    )
+    .bind(user_id)
+    .execute(&mut *conn)
+    .await?;
+    return Ok(res.rows_affected());
+}
+
+#[rocket::async_trait]
+impl Storage for SqliteStorage {
+    async fn find_user(&self, username: &str) -> Result<Option<User>, sqlx::Error> {
+        let user = sqlx::query("SELECT id,username,hash_password,created_at,updated_at,disabled_at,display_name,bio,status_text,avatar_updated_at,last_seen_at,hide_last_seen,contacts_only_dms from users where username = ?1")
+            .bind(username)
+            .fetch_optional(&self.pool)
+            .await?;
+        return Ok(user.map(user_from_row));
+    }
+
+    async fn find_user_by_id(&self, user_id: i32) -> Result<Option<User>, sqlx::Error> {
+        let user = sqlx::query("SELECT id,username,hash_password,created_at,updated_at,disabled_at,display_name,bio,status_text,avatar_updated_at,last_seen_at,hide_last_seen,contacts_only_dms from users where id = ?1")
+            .bind(user_id)
+            .fetch_optional(&self.pool)
+            .await?;
+        return Ok(user.map(user_from_row));
+    }
+
+    async fn create_user(&self, username: &str, hash_password: &str) -> Result<IdOnly, SignupError> {
+        let id = sqlx::query_scalar("INSERT INTO users (username,hash_password) VALUES (?1,?2) returning id")
+            .bind(username)
+            .bind(hash_password)
+            .fetch_one(&self.pool)
+            .await;
+        match id {
+            Ok(id) => return Ok(IdOnly { id }),
+            Err(sqlx::Error::Database(e)) if e.is_unique_violation() => return Err(SignupError::UserAlreadyExists),
+            Err(e) => return Err(e.into()),
+        }
+    }
+
+    async fn search_users(&self, viewer_id: i32, name: &str, limit: i64, after: Option<&str>) -> Result<Vec<User>, SearchUsersError> {
+        let escaped = escape_like(name);
+        // LIKE already ignores case here, though only for ASCII letters.
+        let users = sqlx::query(
+            r"SELECT id,username,hash_password,created_at,updated_at,disabled_at,display_name,bio,status_text,avatar_updated_at,last_seen_at,hide_last_seen,contacts_only_dms from users
+            where username LIKE ?1 ESCAPE '\'
+            AND id <> ?4
+            AND NOT EXISTS (SELECT 1 FROM user_block b WHERE (b.blocker_id = ?4 AND b.blocked_id = users.id) OR (b.blocker_id = users.id AND b.blocked_id = ?4))
+            AND (?5 IS NULL OR (CASE WHEN username LIKE ?2 ESCAPE '\' THEN 0 ELSE 1 END, username)
+                > (CASE WHEN ?5 LIKE ?2 ESCAPE '\' THEN 0 ELSE 1 END, ?5))
+            ORDER BY CASE WHEN username LIKE ?2 ESCAPE '\' THEN 0 ELSE 1 END, username
+            LIMIT ?3",
+        )
+        .bind(format!("%{escaped}%"))
+        .bind(format!("{escaped}%"))
+        .bind(limit)
+        .bind(viewer_id)
+        .bind(after)
+        .fetch_all(&self.pool)
+        .await?;
+        return Ok(users.into_iter().map(user_from_row).collect());
+    }
+
+    async fn add_token_family(&self, user_id: i32, token: &str) -> Result<(), sqlx::Error> {
+        let mut txn = self.pool.begin().await?;
+        let family_id: i32 = sqlx::query_scalar("INSERT INTO token_family (user_id) VALUES (?1) returning id")
+            .bind(user_id)
+            .fetch_one(&mut *txn)
+            .await?;
+        insert_token(&mut txn, family_id, token).await?;
+        txn.commit().await?;
+        return Ok(());
+    }
+
+    async fn rotate_refresh_token(&self, old_token: &str, new_token: &str) -> Result<(), RefreshRefreshTokenErr> {
+        let mut txn = self.pool.begin().await?;
+        let family = sqlx::query("SELECT tfr.token_family_id, tfr.status FROM token_family_rel tfr JOIN token t on t.id = tfr.token_id WHERE t.token = ?1")
+            .bind(old_token)
+            .fetch_optional(&mut *txn)
+            .await?;
+        let Some(family) = family else {
+            return Err(RefreshRefreshTokenErr::InvalidToken);
+        };
+        if family.get::<String, _>("status") != "ACTIVE" {
+            return Err(RefreshRefreshTokenErr::RevokedToken);
+        }
+        let family_id: i32 = family.get("token_family_id");
+        sqlx::query("UPDATE token_family_rel SET status = 'expired' WHERE token_family_id = ?1")
+            .bind(family_id)
+            .execute(&mut *txn)
+            .await?;
+        insert_token(&mut txn, family_id, new_token).await?;
+        txn.commit().await?;
+        return Ok(());
+    }
+
+    async fn cleanup_tokens(&self, refresh_ttl: Duration, retention: Duration) -> Result<CleanupStats, sqlx::Error> {
+        let retention_cutoff = Utc::now() - retention;
+        let expiry_cutoff = retention_cutoff - refresh_ttl;
+
+        let mut txn = self.pool.begin().await?;
+        let tokens_removed = sqlx::query(
+            "DELETE FROM token
+            WHERE created_at < ?1
+            OR (
+                created_at < ?2
+                AND NOT EXISTS (SELECT 1 FROM token_family_rel tfr WHERE tfr.token_id = token.id AND tfr.status = 'ACTIVE')
+            )",
+        )
+        .bind(timestamp(expiry_cutoff))
+        .bind(timestamp(retention_cutoff))
+        .execute(&mut *txn)
+        .await?
+        .rows_affected();
+
+        let families_removed = sqlx::query(
+            "DELETE FROM token_family
+            WHERE created_at < ?1
+            AND NOT EXISTS (SELECT 1 FROM token_family_rel tfr WHERE tfr.token_family_id = token_family.id)",
+        )
+        .bind(timestamp(retention_cutoff))
+        .execute(&mut *txn)
+        .await?
+        .rows_affected();
+        txn.commit().await?;
+
+        return Ok(CleanupStats {
+            tokens_removed,
+            families_removed,
+        });
+    }
+
+    async fn create_conversation(
+        &self,
+        name: Option<String>,
+        member_user_ids: Vec<i32>,
+        first_message: Option<(i32, String)>,
+    ) -> Result<CreateConversationResponse, CreateConversationError> {
+        let ids = id_list(&member_user_ids);
+        let mut txn = self.pool.begin().await?;
+
+        let users_in_conversation = sqlx::query("SELECT id,username,display_name,avatar_updated_at,last_seen_at,hide_last_seen from users where id IN (SELECT value FROM json_each(?1))")
+            .bind(&ids)
+            .fetch_all(&mut *txn)
+            .await?
+            .into_iter()
+            .map(|row| {
+                let presence = Presence::offline(row.get("last_seen_at"), row.get("hide_last_seen"));
+                ConversationMember::new(row.get("id"), row.get("username"), row.get("display_name"), row.get("avatar_updated_at"), presence)
+            })
+            .collect::<Vec<_>>();
+        if users_in_conversation.len() != member_user_ids.len() {
+            return Err(CreateConversationError::InvalidUsers);
+        }
+
+        let existing = sqlx::query(
+            "SELECT cm.conversation_id as id, c.title as title FROM conversation_member cm JOIN conversation c on c.id = cm.conversation_id
+            GROUP BY cm.conversation_id, c.title
+            HAVING COUNT(cm.user_id) = ?1
+            AND SUM(cm.user_id IN (SELECT value FROM json_each(?2))) = ?1",
+        )
+        .bind(member_user_ids.len() as i64)
+        .bind(&ids)
+        .fetch_optional(&mut *txn)
+        .await?;
+
+        let (conversation_id, title): (i32, Option<String>) = match existing {
+            Some(row) => (row.get("id"), row.get("title")),
+            None => {
+                let conversation_id: i32 = sqlx::query_scalar("INSERT INTO conversation (title,conv_type) VALUES (?1, 'group') RETURNING id")
+                    .bind(&name)
+                    .fetch_one(&mut *txn)
+                    .await?;
+                for user_id in member_user_ids.iter() {
+                    sqlx::query("INSERT INTO conversation_member (conversation_id, user_id, role) VALUES (?1, ?2, 'member')")
+                        .bind(conversation_id)
+                        .bind(user_id)
+                        .execute(&mut *txn)
+                        .await?;
+                }
+                (conversation_id, None)
+            }
+        };
+
+        if let Some((sender_user_id, text)) = first_message {
+            let member_id: Option<i32> = sqlx::query_scalar("SELECT id FROM conversation_member WHERE conversation_id = ?1 AND user_id = ?2")
+                .bind(conversation_id)
+                .bind(sender_user_id)
+                .fetch_optional(&mut *txn)
+                .await?;
+            let Some(member_id) = member_id else {
+                return Err(CreateConversationError::InvalidUsers);
+            };
+            let content_id: i32 = sqlx::query_scalar("INSERT INTO text_message_content (text) VALUES (?1) RETURNING id")
+                .bind(&text)
+                .fetch_one(&mut *txn)
+                .await?;
+            sqlx::query("INSERT INTO message (conversation_id, sender_member_id, message_type, message_content_id) VALUES (?1, ?2, 'text', ?3)")
+                .bind(conversation_id)
+                .bind(member_id)
+                .bind(content_id)
+                .execute(&mut *txn)
+                .await?;
+        }
+        txn.commit().await?;
+
+        return Ok(CreateConversationResponse { conversation_id: conversation_id.to_string(), title, members: users_in_conversation });
+    }
+
+    async fn migration_status(&self) -> Result<MigrationStatus, sqlx::Error> {
+        let applied: Vec<i64> = sqlx::query_scalar("SELECT version FROM _sqlx_migrations WHERE success")
+            .fetch_all(&self.pool)
+            .await?;
+        return Ok(MigrationStatus::new(&MIGRATOR, &applied));
+    }
+
+    async fn migrate_up(&self) -> Result<(), MigrateError> {
+        return MIGRATOR.run(&self.pool).await;
+    }
+
+    async fn migrate_down(&self, target: Option<i64>) -> Result<(), MigrateError> {
+        let target = match target {
+            Some(target) => target,
+            None => previous_migration(&MIGRATOR, self.migration_status().await?.latest_applied),
+        };
+        return MIGRATOR.undo(&self.pool, target).await;
+    }
+
+    fn pool_stats(&self) -> Option<PoolStats> {
+        return Some(PoolStats { connections: self.pool.size(), idle: self.pool.num_idle() });
+    }
+
+    async fn set_user_disabled(&self, user_id: i32, disabled: bool) -> Result<bool, sqlx::Error> {
+        let mut txn = self.pool.begin().await?;
+        let found = sqlx::query(
+            "UPDATE users SET disabled_at = CASE WHEN ?2 THEN COALESCE(disabled_at, strftime('%Y-%m-%dT%H:%M:%fZ', 'now')) END,
+            updated_at = strftime('%Y-%m-%dT%H:%M:%fZ', 'now')
+            WHERE id = ?1",
+        )
+        .bind(user_id)
+        .bind(disabled)
+        .execute(&mut *txn)
+        .await?
+        .rows_affected()
+            > 0;
+        if found && disabled {
+            revoke_families(&mut txn, user_id).await?;
+        }
+        txn.commit().await?;
+        return Ok(found);
+    }
+
+    async fn set_password(&self, user_id: i32, hash_password: &str) -> Result<bool, sqlx::Error> {
+        let mut txn = self.pool.begin().await?;
+        let found = sqlx::query("UPDATE users SET hash_password = ?2, updated_at = strftime('%Y-%m-%dT%H:%M:%fZ', 'now') WHERE id = ?1")
+            .bind(user_id)
+            .bind(hash_password)
+            .execute(&mut *txn)
+            .await?
+            .rows_affected()
+            > 0;
+        if found {
+            revoke_families(&mut txn, user_id).await?;
+        }
+        txn.commit().await?;
+        return Ok(found);
+    }
+
+    async fn revoke_token_families(&self, user_id: i32) -> Result<u64, sqlx::Error> {
+        let mut conn = self.pool.acquire().await?;
+        return revoke_families(&mut conn, user_id).await;
+    }
+
+    async fn delete_user(&self, user_id: i32) -> Result<bool, sqlx::Error> {
+        let res = sqlx::query("DELETE FROM users WHERE id = ?1")
+            .bind(user_id)
+            .execute(&self.pool)
+            .await?;
+        return Ok(res.rows_affected() > 0);
+    }
+
+    async fn list_conversations(&self, member_user_id: Option<i32>) -> Result<Vec<ConversationSummary>, sqlx::Error> {
+        let rows = sqlx::query(
+            "SELECT c.id as conversation_id, c.title, u.id as user_id, u.username, u.display_name, u.avatar_updated_at, u.last_seen_at, u.hide_last_seen,
+                COALESCE(me.muted, FALSE) as muted
+            FROM conversation c
+            JOIN conversation_member cm on cm.conversation_id = c.id
+            JOIN users u on u.id = cm.user_id
+            LEFT JOIN conversation_member me on me.conversation_id = c.id AND me.user_id = ?1
+            WHERE ?1 IS NULL OR me.id IS NOT NULL
+            ORDER BY c.id, u.username",
+        )
+        .bind(member_user_id)
+        .fetch_all(&self.pool)
+        .await?
+        .into_iter()
+        .map(|row| ConversationMemberRow {
+            conversation_id: row.get("conversation_id"),
+            title: row.get("title"),
+            user_id: row.get("user_id"),
+            username: row.get("username"),
+            display_name: row.get("display_name"),
+            avatar_updated_at: row.get("avatar_updated_at"),
+            last_seen_at: row.get("last_seen_at"),
+            hide_last_seen: row.get("hide_last_seen"),
+            muted: row.get("muted"),
+        })
+        .collect();
+        return Ok(ConversationSummary::from_rows(rows));
+    }
+
+    async fn list_sent_messages(&self, user_id: i32) -> Result<Vec<ExportedMessage>, sqlx::Error> {
+        let messages = sqlx::query(
+            "SELECT m.id, m.conversation_id, t.text, m.created_at as sent_at
+            FROM message m
+            JOIN conversation_member cm on cm.id = m.sender_member_id
+            JOIN text_message_content t on t.id = m.message_content_id
+            WHERE cm.user_id = ?1
+            ORDER BY m.id",
+        )
+        .bind(user_id)
+        .fetch_all(&self.pool)
+        .await?
+        .into_iter()
+        .map(|row| ExportedMessage {
+            id: row.get("id"),
+            conversation_id: row.get("conversation_id"),
+            text: row.get("text"),
+            sent_at: row.get("sent_at"),
+        })
+        .collect();
+        return Ok(messages);
+    }
+
+    async fn update_profile(&self, user_id: i32, changes: &ProfileChanges) -> Result<Option<User>, sqlx::Error> {
+        let user = sqlx::query(
+            "UPDATE users SET
+                display_name = CASE WHEN ?2 THEN ?3 ELSE display_name END,
+                bio = CASE WHEN ?4 THEN ?5 ELSE bio END,
+                status_text = CASE WHEN ?6 THEN ?7 ELSE status_text END,
+                hide_last_seen = COALESCE(?8, hide_last_seen),
+                contacts_only_dms = COALESCE(?9, contacts_only_dms),
+                updated_at = strftime('%Y-%m-%dT%H:%M:%fZ', 'now')
+            WHERE id = ?1
+            RETURNING id,username,hash_password,created_at,updated_at,disabled_at,display_name,bio,status_text,avatar_updated_at,last_seen_at,hide_last_seen,contacts_only_dms",
+        )
+        .bind(user_id)
+        .bind(changes.display_name.is_some())
+        .bind(changes.display_name.clone().flatten())
+        .bind(changes.bio.is_some())
+        .bind(changes.bio.clone().flatten())
+        .bind(changes.status_text.is_some())
+        .bind(changes.status_text.clone().flatten())
+        .bind(changes.hide_last_seen)
+        .bind(changes.contacts_only_dms)
+        .fetch_optional(&self.pool)
+        .await?;
+        return Ok(user.map(user_from_row));
+    }
+
+    async fn set_avatar(&self, user_id: i32, avatar: Option<&Avatar>) -> Result<Option<User>, sqlx::Error> {
+        let mut txn = self.pool.begin().await?;
+        let user = sqlx::query(
+            "UPDATE users SET
+                avatar_updated_at = CASE WHEN ?2 THEN strftime('%Y-%m-%dT%H:%M:%fZ', 'now') END,
+                updated_at = strftime('%Y-%m-%dT%H:%M:%fZ', 'now')
+            WHERE id = ?1
+            RETURNING id,username,hash_password,created_at,updated_at,disabled_at,display_name,bio,status_text,avatar_updated_at,last_seen_at,hide_last_seen,contacts_only_dms",
+        )
+        .bind(user_id)
+        .bind(avatar.is_some())
+        .fetch_optional(&mut *txn)
+        .await?;
+        let Some(user) = user else {
+            return Ok(None);
+        };
+        match avatar {
+            Some(avatar) => {
+                sqlx::query(
+                    "INSERT INTO user_avatar (user_id, content_type, data) VALUES (?1, ?2, ?3)
+                    ON CONFLICT (user_id) DO UPDATE SET content_type = excluded.content_type, data = excluded.data",
+                )
+                .bind(user_id)
+                .bind(&avatar.content_type)
+                .bind(&avatar.data)
+                .execute(&mut *txn)
+                .await?;
+            }
+            None => {
+                sqlx::query("DELETE FROM user_avatar WHERE user_id = ?1")
+                    .bind(user_id)
+                    .execute(&mut *txn)
+                    .await?;
+            }
+        }
+        txn.commit().await?;
+        return Ok(Some(user_from_row(user)));
+    }
+
+    async fn get_avatar(&self, user_id: i32) -> Result<Option<Avatar>, sqlx::Error> {
+        let avatar = sqlx::query("SELECT content_type, data FROM user_avatar WHERE user_id = ?1")
+            .bind(user_id)
+            .fetch_optional(&self.pool)
+            .await?
+            .map(|row| Avatar { content_type: row.get("content_type"), data: row.get("data") });
+        return Ok(avatar);
+    }
+
+    async fn touch_last_seen(&self, user_id: i32) -> Result<(), sqlx::Error> {
+        sqlx::query("UPDATE users SET last_seen_at = strftime('%Y-%m-%dT%H:%M:%fZ', 'now') WHERE id = ?1")
+            .bind(user_id)
+            .execute(&self.pool)
+            .await?;
+        return Ok(());
+    }
+
+    async fn block_user(&self, blocker_id: i32, blocked_id: i32) -> Result<bool, sqlx::Error> {
+        let exists: bool = sqlx::query_scalar("SELECT EXISTS(SELECT 1 FROM users WHERE id = ?1)")
+            .bind(blocked_id)
+            .fetch_one(&self.pool)
+            .await?;
+        if !exists {
+            return Ok(false);
+        }
+        sqlx::query("INSERT INTO user_block (blocker_id, blocked_id) VALUES (?1, ?2) ON CONFLICT DO NOTHING")
+            .bind(blocker_id)
+            .bind(blocked_id)
+            .execute(&self.pool)
+            .await?;
+        return Ok(true);
+    }
+
+    async fn unblock_user(&self, blocker_id: i32, blocked_id: i32) -> Result<(), sqlx::Error> {
+        sqlx::query("DELETE FROM user_block WHERE blocker_id = ?1 AND blocked_id = ?2")
+            .bind(blocker_id)
+            .bind(blocked_id)
+            .execute(&self.pool)
+            .await?;
+        return Ok(());
+    }
+
+    async fn list_blocked(&self, blocker_id: i32) -> Result<Vec<User>, sqlx::Error> {
+        let users = sqlx::query(
+            "SELECT u.id,u.username,u.hash_password,u.created_at,u.updated_at,u.disabled_at,u.display_name,u.bio,u.status_text,u.avatar_updated_at,u.last_seen_at,u.hide_last_seen,u.contacts_only_dms
+            FROM users u
+            JOIN user_block b on b.blocked_id = u.id
+            WHERE b.blocker_id = ?1
+            ORDER BY u.username",
+        )
+        .bind(blocker_id)
+        .fetch_all(&self.pool)
+        .await?;
+        return Ok(users.into_iter().map(user_from_row).collect());
+    }
+
+    async fn find_blocks(&self, user_id: i32, other_user_ids: &[i32]) -> Result<Vec<Block>, sqlx::Error> {
+        let blocks = sqlx::query(
+            "SELECT blocker_id, blocked_id FROM user_block
+            WHERE (blocker_id = ?1 AND blocked_id IN (SELECT value FROM json_each(?2)))
+            OR (blocked_id = ?1 AND blocker_id IN (SELECT value FROM json_each(?2)))",
+        )
+        .bind(user_id)
+        .bind(id_list(other_user_ids))
+        .fetch_all(&self.pool)
+        .await?
+        .into_iter()
+        .map(|row| Block { blocker_id: row.get("blocker_id"), blocked_id: row.get("blocked_id") })
+        .collect();
+        return Ok(blocks);
+    }
+
+    async fn set_conversation_muted(&self, conversation_id: i32, user_id: i32, muted: bool) -> Result<bool, sqlx::Error> {
+        let res = sqlx::query(
+            "UPDATE conversation_member SET muted = ?3, updated_at = strftime('%Y-%m-%dT%H:%M:%fZ', 'now')
+            WHERE conversation_id = ?1 AND user_id = ?2",
+        )
+        .bind(conversation_id)
+        .bind(user_id)
+        .bind(muted)
+        .execute(&self.pool)
+        .await?;
+        return Ok(res.rows_affected() > 0);
+    }
+
+    async fn needs_contact(&self, user_id: i32, other_user_id: i32) -> Result<bool, sqlx::Error> {
+        let row = sqlx::query(
+            "SELECT EXISTS (
+                SELECT 1 FROM users u
+                WHERE u.id = ?2 AND u.contacts_only_dms AND NOT EXISTS (
+                    SELECT 1 FROM contact c
+                    WHERE c.status = ?3
+                    AND ((c.requester_id = ?1 AND c.addressee_id = ?2) OR (c.requester_id = ?2 AND c.addressee_id = ?1))
+                )
+            ) as needs_contact",
+        )
+        .bind(user_id)
+        .bind(other_user_id)
+        .bind(ContactStatus::Accepted.as_str())
+        .fetch_one(&self.pool)
+        .await?;
+        return Ok(row.get("needs_contact"));
+    }
+
+    async fn change_contact(&self, user_id: i32, other_user_id: i32, change: ContactChange) -> Result<ContactRow, ContactError> {
+        // IMMEDIATE takes the write lock up front, so nothing changes between the read and the write.
+        let mut txn = self.pool.begin_with("BEGIN IMMEDIATE").await?;
+        let current = sqlx::query(
+            "SELECT requester_id, addressee_id, status, updated_at as contact_updated_at FROM contact
+            WHERE (requester_id = ?1 AND addressee_id = ?2) OR (requester_id = ?2 AND addressee_id = ?1)",
+        )
+        .bind(user_id)
+        .bind(other_user_id)
+        .fetch_optional(&mut *txn)
+        .await?
+        .as_ref()
+        .map(contact_from_row);
+        let Some((requester_id, addressee_id, status)) = change.apply(user_id, other_user_id, current.as_ref())? else {
+            return Ok(current.unwrap());
+        };
+        sqlx::query("DELETE FROM contact WHERE (requester_id = ?1 AND addressee_id = ?2) OR (requester_id = ?2 AND addressee_id = ?1)")
+            .bind(requester_id)
+            .bind(addressee_id)
+            .execute(&mut *txn)
+            .await?;
+        let row = sqlx::query(
+            "INSERT INTO contact (requester_id, addressee_id, status) VALUES (?1, ?2, ?3)
+            RETURNING requester_id, addressee_id, status, updated_at as contact_updated_at",
+        )
+        .bind(requester_id)
+        .bind(addressee_id)
+        .bind(status.as_str())
+        .fetch_one(&mut *txn)
+        .await?;
+        txn.commit().await?;
+        return Ok(contact_from_row(&row));
+    }
+
+    async fn remove_contact(&self, user_id: i32, other_user_id: i32) -> Result<(), sqlx::Error> {
+        sqlx::query("DELETE FROM contact WHERE (requester_id = ?1 AND addressee_id = ?2) OR (requester_id = ?2 AND addressee_id = ?1)")
+            .bind(user_id)
+            .bind(other_user_id)
+            .execute(&self.pool)
+            .await?;
+        return Ok(());
+    }
+
+    async fn list_contacts(&self, user_id: i32) -> Result<Vec<(ContactRow, User)>, sqlx::Error> {
+        let rows = sqlx::query(
+            "SELECT c.requester_id, c.addressee_id, c.status, c.updated_at as contact_updated_at,
+                u.id,u.username,u.hash_password,u.created_at,u.updated_at,u.disabled_at,u.display_name,u.bio,u.status_text,u.avatar_updated_at,u.last_seen_at,u.hide_last_seen,u.contacts_only_dms
+            FROM contact c
+            JOIN users u on u.id = CASE WHEN c.requester_id = ?1 THEN c.addressee_id ELSE c.requester_id END
+            WHERE c.requester_id = ?1 OR c.addressee_id = ?1
+            ORDER BY u.username",
+        )
+        .bind(user_id)
+        .fetch_all(&self.pool)
+        .await?;
+        return Ok(rows.into_iter().map(|row| (contact_from_row(&row), user_from_row(row))).collect());
+    }
+}
@@ -0,0 +1,98 @@
+use chrono::Utc;
+use macros::{db_err, db_func};
+use shared::{
+    AnyErr,
+    db::signup::User,
+    routes::users::{account::{AccountExport, ExportedConversation, ExportedMessage, ExportedProfile}, contacts::ContactStatus},
+};
+use sqlx::query_as;
+
+use crate::db::{chat::conversation::ConversationSummary, store::Store};
+
+#[db_err]
+pub enum AccountError {
+    #[response(status = 404, code = NotFound, message = "Account not found")]
+    NotFound,
+    /// A 400 rather than a 401, which clients take to mean the session is gone.
+    #[response(status = 400, code = ValidationFailed, message = "Wrong password")]
+    WrongPassword,
+}
+
+impl From<ConversationSummary> for ExportedConversation {
+    fn from(value: ConversationSummary) -> Self {
+        return ExportedConversation { id: value.id, title: value.title, members: value.members, muted: value.muted };
+    }
+}
+
+#[db_func]
+pub async fn find_user_by_id(user_id: i32) -> Result<Option<User>, sqlx::Error> {
+    let user = query_as!(User,
+        r#"SELECT id,username,hash_password,created_at,updated_at,disabled_at,display_name,bio,status_text,avatar_updated_at,last_seen_at,hide_last_seen,contacts_only_dms from users where id = $1"#,
+        user_id)
+        .fetch_optional(pool)
+        .await?;
+    return Ok(user);
+}
+
+/// Text messages `user_id` sent, oldest first.
+#[db_func]
+pub async fn list_sent_messages(user_id: i32) -> Result<Vec<ExportedMessage>, sqlx::Error> {
+    let messages = query_as!(ExportedMessage,
+        "SELECT m.id, m.conversation_id, t.text, m.created_at as sent_at
+        FROM message m
+        JOIN conversation_member cm on cm.id = m.sender_member_id
+        JOIN text_message_content t on t.id = m.message_content_id
+        WHERE cm.user_id = $1
+        ORDER BY m.id",
+        user_id)
+        .fetch_all(pool)
+        .await?;
+    return Ok(messages);
+}
+
+pub async fn export_account(store: &Store, user_id: i32) -> Result<AccountExport, AccountError> {
+    let Some(user) = store.find_user_by_id(user_id).await? else {
+        return Err(AccountError::NotFound);
+    };
+    let conversations = store.list_conversations(Some(user_id)).await?;
+    let messages = store.list_sent_messages(user_id).await?;
+    let blocked_users = store.list_blocked(user_id).await?.into_iter().map(|u| u.username).collect();
+    let contacts = store
+        .list_contacts(user_id)
+        .await?
+        .into_iter()
+        .filter(|(row, _)| row.status() == ContactStatus::Accepted)
+        .map(|(_, user)| user.username)
+        .collect();
+    return Ok(AccountExport {
+        exported_at: Utc::now(),
+        profile: ExportedProfile {
+            id: user.id,
+            username: user.username,
+            display_name: user.display_name,
+            bio: user.bio,
+            status_text: user.status_text,
+            created_at: user.created_at,
+            updated_at: user.updated_at,
+        },
+        conversations: conversations.into_iter().map(ExportedConversation::from).collect(),
+        messages,
+        blocked_users,
+        contacts,
+    });
+}
+
+/// Deletes the caller's account once `password` matches. Their messages stay behind without a
+/// sender, see `Storage::delete_user`.
+pub async fn delete_account(store: &Store, user_id: i32, password: &str) -> Result<(), AccountError> {
+    let Some(user) = store.find_user_by_id(user_id).await? else {
+        return Err(AccountError::NotFound);
+    };
+    if !bcrypt::verify(password, &user.hash_password).unwrap() {
+        return Err(AccountError::WrongPassword);
+    }
+    if !store.delete_user(user_id).await? {
+        return Err(AccountError::NotFound);
+    }
+    return Ok(());
+}
@@ -0,0 +1,52 @@
+use macros::db_func;
+use sqlx::query;
+
+/// Marks every active refresh token of `user_id` as revoked, so each family fails its next
+/// refresh. Returns how many families were still live.
+#[db_func(conn)]
+pub async fn revoke_token_families(user_id: i32) -> Result<u64, sqlx::Error> {
+    let res = query!(
+        "UPDATE token_family_rel SET status = 'REVOKED', updated_at = NOW()
+        WHERE status = 'ACTIVE'
+        AND token_family_id IN (SELECT id FROM token_family WHERE user_id = $1)",
+        user_id
+    )
+    .execute(&mut *conn)
+    .await?;
+    return Ok(res.rows_affected());
+}
+
+/// Keeps the first `disabled_at` when disabling twice. Returns false for an unknown user.
+#[db_func(conn)]
+pub async fn set_disabled(user_id: i32, disabled: bool) -> Result<bool, sqlx::Error> {
+    let res = query!(
+        "UPDATE users SET disabled_at = CASE WHEN $2 THEN COALESCE(disabled_at, NOW()) END, updated_at = NOW() WHERE id = $1",
+        user_id,
+        disabled
+    )
+    .execute(&mut *conn)
+    .await?;
+    return Ok(res.rows_affected() > 0);
+}
+
+#[db_func(conn)]
+pub async fn set_password(user_id: i32, hash_password: &str) -> Result<bool, sqlx::Error> {
+    let res = query!(
+        "UPDATE users SET hash_password = $2, updated_at = NOW() WHERE id = $1",
+        user_id,
+        hash_password
+    )
+    .execute(&mut *conn)
+    .await?;
+    return Ok(res.rows_affected() > 0);
+}
+
+/// Token families and memberships go with it through `ON DELETE CASCADE`; the user's messages
+/// stay, with `sender_member_id` set to NULL.
+#[db_func]
+pub async fn delete_user(user_id: i32) -> Result<bool, sqlx::Error> {
+    let res = query!("DELETE FROM users WHERE id = $1", user_id)
+        .execute(pool)
+        .await?;
+    return Ok(res.rows_affected() > 0);
+}
@@ -0,0 +1,81 @@
+use macros::{db_err, db_func};
+use shared::{AnyErr, db::signup::User};
+use sqlx::{query, query_as, query_scalar};
+
+use crate::db::store::Store;
+
+#[db_err]
+pub enum BlockError {
+    #[response(status = 404, code = NotFound, message = "User not found")]
+    NotFound,
+    #[response(status = 400, code = ValidationFailed, message = "You can't block yourself")]
+    BlockingSelf,
+}
+
+/// One `user_block` row: `blocker_id` doesn't want to hear from `blocked_id`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Block {
+    pub blocker_id: i32,
+    pub blocked_id: i32,
+}
+
+/// Returns false for an unknown `blocked_id`. Blocking someone twice changes nothing.
+#[db_func]
+pub async fn block_user(blocker_id: i32, blocked_id: i32) -> Result<bool, sqlx::Error> {
+    let exists = query_scalar!(r#"SELECT EXISTS(SELECT 1 FROM users WHERE id = $1) as "exists!""#, blocked_id)
+        .fetch_one(pool)
+        .await?;
+    if !exists {
+        return Ok(false);
+    }
+    query!("INSERT INTO user_block (blocker_id, blocked_id) VALUES ($1, $2) ON CONFLICT DO NOTHING", blocker_id, blocked_id)
+        .execute(pool)
+        .await?;
+    return Ok(true);
+}
+
+#[db_func]
+pub async fn unblock_user(blocker_id: i32, blocked_id: i32) -> Result<(), sqlx::Error> {
+    query!("DELETE FROM user_block WHERE blocker_id = $1 AND blocked_id = $2", blocker_id, blocked_id)
+        .execute(pool)
+        .await?;
+    return Ok(());
+}
+
+#[db_func]
+pub async fn list_blocked(blocker_id: i32) -> Result<Vec<User>, sqlx::Error> {
+    let users = query_as!(User,
+        r#"SELECT u.id,u.username,u.hash_password,u.created_at,u.updated_at,u.disabled_at,u.display_name,u.bio,u.status_text,u.avatar_updated_at,u.last_seen_at,u.hide_last_seen,u.contacts_only_dms
+        FROM users u
+        JOIN user_block b on b.blocked_id = u.id
+        WHERE b.blocker_id = $1
+        ORDER BY u.username"#,
+        blocker_id)
+        .fetch_all(pool)
+        .await?;
+    return Ok(users);
+}
+
+/// Blocks in either direction between `user_id` and any of `other_user_ids`.
+#[db_func]
+pub async fn find_blocks(user_id: i32, other_user_ids: &[i32]) -> Result<Vec<Block>, sqlx::Error> {
+    let blocks = query_as!(Block,
+        "SELECT blocker_id, blocked_id FROM user_block
+        WHERE (blocker_id = $1 AND blocked_id = ANY($2)) OR (blocked_id = $1 AND blocker_id = ANY($2))",
+        user_id,
+        other_user_ids)
+        .fetch_all(pool)
+        .await?;
+    return Ok(blocks);
+}
+
+pub async fn block(store: &Store, blocker_id: i32, blocked_id: i32) -> Result<(), BlockError> {
+    if blocker_id == blocked_id {
+        return Err(BlockError::BlockingSelf);
+    }
+    if !store.block_user(blocker_id, blocked_id).await? {
+        return Err(BlockError::NotFound);
+    }
+    store.remove_contact(blocker_id, blocked_id).await?;
+    return Ok(());
+}
@@ -0,0 +1,242 @@
+use chrono::{DateTime, Utc};
+use macros::{db_err, db_func};
+use shared::{
+    AnyErr,
+    db::signup::User,
+    routes::users::{contacts::{Contact, ContactStatus}, search::SearchUser},
+};
+use sqlx::{query, query_as};
+
+use crate::db::{store::Store, users::block::Block};
+
+#[db_err]
+pub enum ContactError {
+    #[response(status = 404, code = NotFound, message = "User not found")]
+    NotFound,
+    #[response(status = 400, code = ValidationFailed, message = "You can't add yourself as a contact")]
+    Yourself,
+    #[response(status = 400, code = UserBlocked, message = "You blocked this user")]
+    BlockedUser,
+    #[response(status = 404, code = NotFound, message = "No contact request from this user")]
+    NoRequest,
+}
+
+/// A `contact` row. There is at most one per pair of users, whichever direction it points.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ContactRow {
+    pub requester_id: i32,
+    pub addressee_id: i32,
+    pub status: String,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl ContactRow {
+    pub fn status(&self) -> ContactStatus {
+        return ContactStatus::parse(&self.status).unwrap();
+    }
+
+    /// `other` as `user_id` sees them. Requesters aren't told about declines.
+    pub fn view(&self, user_id: i32, other: User) -> Contact {
+        let incoming = self.requester_id != user_id;
+        let status = match self.status() {
+            ContactStatus::Declined if !incoming => ContactStatus::Pending,
+            status => status,
+        };
+        return Contact { user: SearchUser::from(other), status, incoming, updated_at: self.updated_at };
+    }
+}
+
+/// A request or an answer to one, applied to the pair's row by `Storage::change_contact`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ContactChange {
+    /// Sends a request, or accepts the one the other user already sent. Asking again while a
+    /// request is open changes nothing.
+    Request,
+    /// Answers a request the other user sent. A declined request can still be accepted later.
+    Respond { accept: bool },
+}
+
+impl ContactChange {
+    /// The `(requester_id, addressee_id, status)` to replace `current` with, or `None` to keep it.
+    pub fn apply(self, user_id: i32, other_user_id: i32, current: Option<&ContactRow>) -> Result<Option<(i32, i32, ContactStatus)>, ContactError> {
+        match (self, current) {
+            (ContactChange::Request, Some(row)) if row.status() == ContactStatus::Accepted || row.requester_id == user_id => {
+                return Ok(None);
+            }
+            (ContactChange::Request, Some(_)) => return Ok(Some((other_user_id, user_id, ContactStatus::Accepted))),
+            (ContactChange::Request, None) => return Ok(Some((user_id, other_user_id, ContactStatus::Pending))),
+            (ContactChange::Respond { accept }, Some(row)) if row.requester_id == other_user_id && row.status() != ContactStatus::Accepted => {
+                let status = if accept { ContactStatus::Accepted } else { ContactStatus::Declined };
+                return Ok(Some((other_user_id, user_id, status)));
+            }
+            (ContactChange::Respond { .. }, _) => return Err(ContactError::NoRequest),
+        }
+    }
+}
+
+/// The row between `user_id` and `other_user_id`, in either direction.
+#[db_func(conn)]
+pub async fn find_contact(user_id: i32, other_user_id: i32) -> Result<Option<ContactRow>, sqlx::Error> {
+    let row = query_as!(ContactRow,
+        "SELECT requester_id, addressee_id, status, updated_at FROM contact
+        WHERE (requester_id = $1 AND addressee_id = $2) OR (requester_id = $2 AND addressee_id = $1)",
+        user_id,
+        other_user_id)
+        .fetch_optional(&mut *conn)
+        .await?;
+    return Ok(row);
+}
+
+/// Replaces whatever row the pair had, so the request can change direction.
+#[db_func(conn)]
+pub async fn save_contact(requester_id: i32, addressee_id: i32, status: ContactStatus) -> Result<ContactRow, sqlx::Error> {
+    query!("DELETE FROM contact WHERE (requester_id = $1 AND addressee_id = $2) OR (requester_id = $2 AND addressee_id = $1)",
+        requester_id, addressee_id)
+        .execute(&mut *conn)
+        .await?;
+    let row = query_as!(ContactRow,
+        "INSERT INTO contact (requester_id, addressee_id, status) VALUES ($1, $2, $3)
+        RETURNING requester_id, addressee_id, status, updated_at",
+        requester_id,
+        addressee_id,
+        status.as_str())
+        .fetch_one(&mut *conn)
+        .await?;
+    return Ok(row);
+}
+
+/// Reads and rewrites the pair's row in one transaction. The advisory lock also covers the first
+/// request, when there is no row to lock yet.
+#[db_func]
+pub async fn change_contact(user_id: i32, other_user_id: i32, change: ContactChange) -> Result<ContactRow, ContactError> {
+    let mut txn = pool.begin().await?;
+    query!("SELECT 1 as locked FROM (SELECT pg_advisory_xact_lock(LEAST($1::int4, $2::int4), GREATEST($1::int4, $2::int4))) lock",
+        user_id, other_user_id)
+        .fetch_one(&mut *txn)
+        .await?;
+    let current = find_contact(&mut txn, user_id, other_user_id).await?;
+    let row = match change.apply(user_id, other_user_id, current.as_ref())? {
+        Some((requester_id, addressee_id, status)) => save_contact(&mut txn, requester_id, addressee_id, status).await?,
+        None => current.unwrap(),
+    };
+    txn.commit().await?;
+    return Ok(row);
+}
+
+#[db_func]
+pub async fn remove_contact(user_id: i32, other_user_id: i32) -> Result<(), sqlx::Error> {
+    query!("DELETE FROM contact WHERE (requester_id = $1 AND addressee_id = $2) OR (requester_id = $2 AND addressee_id = $1)",
+        user_id, other_user_id)
+        .execute(pool)
+        .await?;
+    return Ok(());
+}
+
+struct ContactWithUser {
+    requester_id: i32,
+    addressee_id: i32,
+    status: String,
+    contact_updated_at: DateTime<Utc>,
+    id: i32,
+    username: String,
+    hash_password: String,
+    created_at: DateTime<Utc>,
+    updated_at: DateTime<Utc>,
+    disabled_at: Option<DateTime<Utc>>,
+    display_name: Option<String>,
+    bio: Option<String>,
+    status_text: Option<String>,
+    avatar_updated_at: Option<DateTime<Utc>>,
+    last_seen_at: Option<DateTime<Utc>>,
+    hide_last_seen: bool,
+    contacts_only_dms: bool,
+}
+
+/// True when `other_user_id` only takes conversations from contacts and `user_id` isn't an
+/// accepted one.
+#[db_func]
+pub async fn needs_contact(user_id: i32, other_user_id: i32) -> Result<bool, sqlx::Error> {
+    let row = query!(
+        r#"SELECT EXISTS (
+            SELECT 1 FROM users u
+            WHERE u.id = $2 AND u.contacts_only_dms AND NOT EXISTS (
+                SELECT 1 FROM contact c
+                WHERE c.status = $3
+                AND ((c.requester_id = $1 AND c.addressee_id = $2) OR (c.requester_id = $2 AND c.addressee_id = $1))
+            )
+        ) as "needs_contact!""#,
+        user_id,
+        other_user_id,
+        ContactStatus::Accepted.as_str())
+        .fetch_one(pool)
+        .await?;
+    return Ok(row.needs_contact);
+}
+
+/// Every row involving `user_id` with the other user of each, ordered by their username.
+#[db_func]
+pub async fn list_contacts(user_id: i32) -> Result<Vec<(ContactRow, User)>, sqlx::Error> {
+    let rows = query_as!(ContactWithUser,
+        r#"SELECT c.requester_id, c.addressee_id, c.status, c.updated_at as contact_updated_at,
+            u.id,u.username,u.hash_password,u.created_at,u.updated_at,u.disabled_at,u.display_name,u.bio,u.status_text,u.avatar_updated_at,u.last_seen_at,u.hide_last_seen,u.contacts_only_dms
+        FROM contact c
+        JOIN users u on u.id = CASE WHEN c.requester_id = $1 THEN c.addressee_id ELSE c.requester_id END
+        WHERE c.requester_id = $1 OR c.addressee_id = $1
+        ORDER BY u.username"#,
+        user_id)
+        .fetch_all(pool)
+        .await?;
+    return Ok(rows
+        .into_iter()
+        .map(|row| {
+            let contact = ContactRow {
+                requester_id: row.requester_id,
+                addressee_id: row.addressee_id,
+                status: row.status,
+                updated_at: row.contact_updated_at,
+            };
+            let user = User {
+                id: row.id,
+                username: row.username,
+                hash_password: row.hash_password,
+                created_at: row.created_at,
+                updated_at: row.updated_at,
+                disabled_at: row.disabled_at,
+                display_name: row.display_name,
+                bio: row.bio,
+                status_text: row.status_text,
+                avatar_updated_at: row.avatar_updated_at,
+                last_seen_at: row.last_seen_at,
+                hide_last_seen: row.hide_last_seen,
+                contacts_only_dms: row.contacts_only_dms,
+            };
+            (contact, user)
+        })
+        .collect());
+}
+
+/// The other user, as long as neither side blocked the other. Someone who blocked the caller
+/// looks like an unknown user.
+async fn reachable_user(store: &Store, user_id: i32, other_user_id: i32) -> Result<User, ContactError> {
+    if user_id == other_user_id {
+        return Err(ContactError::Yourself);
+    }
+    let Some(other) = store.find_user_by_id(other_user_id).await? else {
+        return Err(ContactError::NotFound);
+    };
+    let blocks = store.find_blocks(user_id, &[other_user_id]).await?;
+    if blocks.iter().any(|Block { blocker_id, .. }| *blocker_id == user_id) {
+        return Err(ContactError::BlockedUser);
+    }
+    if !blocks.is_empty() {
+        return Err(ContactError::NotFound);
+    }
+    return Ok(other);
+}
+
+/// Applies `change` from `user_id`'s side and returns the pair as they now see it.
+pub async fn change(store: &Store, user_id: i32, other_user_id: i32, change: ContactChange) -> Result<Contact, ContactError> {
+    let other = reachable_user(store, user_id, other_user_id).await?;
+    let row = store.change_contact(user_id, other_user_id, change).await?;
+    return Ok(row.view(user_id, other));
+}
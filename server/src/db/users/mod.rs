@@ -1 +1,6 @@
-pub mod search;
\ No newline at end of file
+pub mod search;
+pub mod admin;
+pub mod account;
+pub mod profile;
+pub mod block;
+pub mod contacts;
\ No newline at end of file
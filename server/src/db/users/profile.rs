@@ -0,0 +1,208 @@
+use base64::{Engine, engine::general_purpose::STANDARD};
+use macros::{db_err, db_func};
+use shared::{AnyErr, FieldError, db::signup::User, routes::users::profile::{SetAvatarRequest, UpdateProfileRequest}};
+use sqlx::{query, query_as};
+
+use crate::db::store::Store;
+
+/// Decoded size. Base64 adds a third on the wire, which still fits Rocket's 1 MiB JSON limit.
+pub const MAX_AVATAR_BYTES: usize = 512 * 1024;
+
+#[db_err]
+pub enum ProfileError {
+    #[response(status = 404, code = NotFound, message = "User not found")]
+    NotFound,
+}
+
+/// `None` leaves a field alone, `Some(None)` clears it.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ProfileChanges {
+    pub display_name: Option<Option<String>>,
+    pub bio: Option<Option<String>>,
+    pub status_text: Option<Option<String>>,
+    pub hide_last_seen: Option<bool>,
+    pub contacts_only_dms: Option<bool>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Avatar {
+    pub content_type: String,
+    pub data: Vec<u8>,
+}
+
+/// Only the bio may span lines.
+struct FieldRule {
+    name: &'static str,
+    max_chars: usize,
+    multiline: bool,
+}
+
+const DISPLAY_NAME: FieldRule = FieldRule { name: "display_name", max_chars: 64, multiline: false };
+const BIO: FieldRule = FieldRule { name: "bio", max_chars: 500, multiline: true };
+const STATUS_TEXT: FieldRule = FieldRule { name: "status_text", max_chars: 100, multiline: false };
+
+fn profile_field(rule: FieldRule, value: Option<String>, errors: &mut Vec<FieldError>) -> Option<Option<String>> {
+    let value = value?;
+    let value = value.trim();
+    if value.chars().count() > rule.max_chars {
+        errors.push(FieldError::new(rule.name, &format!("must be at most {} characters", rule.max_chars)));
+    }
+    if value.chars().any(|c| c.is_control() && !(rule.multiline && c == '\n')) {
+        let message = if rule.multiline { "must not contain control characters" } else { "must be a single line" };
+        errors.push(FieldError::new(rule.name, message));
+    }
+    if value.is_empty() {
+        return Some(None);
+    }
+    return Some(Some(value.to_string()));
+}
+
+/// Trims every field and turns empty strings into "clear this field".
+pub fn validate_changes(request: UpdateProfileRequest) -> Result<ProfileChanges, Vec<FieldError>> {
+    let mut errors = vec![];
+    let changes = ProfileChanges {
+        display_name: profile_field(DISPLAY_NAME, request.display_name, &mut errors),
+        bio: profile_field(BIO, request.bio, &mut errors),
+        status_text: profile_field(STATUS_TEXT, request.status_text, &mut errors),
+        hide_last_seen: request.hide_last_seen,
+        contacts_only_dms: request.contacts_only_dms,
+    };
+    if !errors.is_empty() {
+        return Err(errors);
+    }
+    return Ok(changes);
+}
+
+/// The image type from its first bytes, whatever the client claims it is.
+fn sniff_image_type(data: &[u8]) -> Option<&'static str> {
+    if data.starts_with(b"\x89PNG\r\n\x1a\n") {
+        return Some("image/png");
+    }
+    if data.starts_with(b"\xff\xd8\xff") {
+        return Some("image/jpeg");
+    }
+    if data.starts_with(b"GIF87a") || data.starts_with(b"GIF89a") {
+        return Some("image/gif");
+    }
+    if data.len() >= 12 && &data[0..4] == b"RIFF" && &data[8..12] == b"WEBP" {
+        return Some("image/webp");
+    }
+    return None;
+}
+
+/// `Ok(None)` removes the avatar.
+pub fn decode_avatar(request: SetAvatarRequest) -> Result<Option<Avatar>, FieldError> {
+    let Some(image) = request.image else {
+        return Ok(None);
+    };
+    let Ok(data) = STANDARD.decode(image.trim()) else {
+        return Err(FieldError::new("image", "must be base64"));
+    };
+    if data.len() > MAX_AVATAR_BYTES {
+        return Err(FieldError::new("image", &format!("must be at most {} KiB", MAX_AVATAR_BYTES / 1024)));
+    }
+    let Some(content_type) = sniff_image_type(&data) else {
+        return Err(FieldError::new("image", "must be a PNG, JPEG, GIF or WebP image"));
+    };
+    return Ok(Some(Avatar { content_type: content_type.into(), data }));
+}
+
+#[db_func]
+pub async fn update_profile(user_id: i32, changes: &ProfileChanges) -> Result<Option<User>, sqlx::Error> {
+    let user = query_as!(User,
+        r#"UPDATE users SET
+            display_name = CASE WHEN $2 THEN $3 ELSE display_name END,
+            bio = CASE WHEN $4 THEN $5 ELSE bio END,
+            status_text = CASE WHEN $6 THEN $7 ELSE status_text END,
+            hide_last_seen = COALESCE($8, hide_last_seen),
+            contacts_only_dms = COALESCE($9, contacts_only_dms),
+            updated_at = NOW()
+        WHERE id = $1
+        RETURNING id,username,hash_password,created_at,updated_at,disabled_at,display_name,bio,status_text,avatar_updated_at,last_seen_at,hide_last_seen,contacts_only_dms"#,
+        user_id,
+        changes.display_name.is_some(),
+        changes.display_name.clone().flatten(),
+        changes.bio.is_some(),
+        changes.bio.clone().flatten(),
+        changes.status_text.is_some(),
+        changes.status_text.clone().flatten(),
+        changes.hide_last_seen,
+        changes.contacts_only_dms)
+        .fetch_optional(pool)
+        .await?;
+    return Ok(user);
+}
+
+/// Replaces or, with `None`, removes the avatar and moves `avatar_updated_at` along with it.
+#[db_func]
+pub async fn set_avatar(user_id: i32, avatar: Option<&Avatar>) -> Result<Option<User>, sqlx::Error> {
+    let mut txn = pool.begin().await?;
+    let user = query_as!(User,
+        r#"UPDATE users SET avatar_updated_at = CASE WHEN $2 THEN NOW() END, updated_at = NOW()
+        WHERE id = $1
+        RETURNING id,username,hash_password,created_at,updated_at,disabled_at,display_name,bio,status_text,avatar_updated_at,last_seen_at,hide_last_seen,contacts_only_dms"#,
+        user_id,
+        avatar.is_some())
+        .fetch_optional(&mut *txn)
+        .await?;
+    if user.is_none() {
+        return Ok(None);
+    }
+    match avatar {
+        Some(avatar) => {
+            query!(
+                "INSERT INTO user_avatar (user_id, content_type, data) VALUES ($1, $2, $3)
+                ON CONFLICT (user_id) DO UPDATE SET content_type = EXCLUDED.content_type, data = EXCLUDED.data",
+                user_id,
+                avatar.content_type,
+                avatar.data)
+                .execute(&mut *txn)
+                .await?;
+        }
+        None => {
+            query!("DELETE FROM user_avatar WHERE user_id = $1", user_id)
+                .execute(&mut *txn)
+                .await?;
+        }
+    }
+    txn.commit().await?;
+    return Ok(user);
+}
+
+/// Not bumping `updated_at`: being seen doesn't change the account.
+#[db_func]
+pub async fn touch_last_seen(user_id: i32) -> Result<(), sqlx::Error> {
+    query!("UPDATE users SET last_seen_at = NOW() WHERE id = $1", user_id)
+        .execute(pool)
+        .await?;
+    return Ok(());
+}
+
+#[db_func]
+pub async fn get_avatar(user_id: i32) -> Result<Option<Avatar>, sqlx::Error> {
+    let avatar = query_as!(Avatar, "SELECT content_type, data FROM user_avatar WHERE user_id = $1", user_id)
+        .fetch_optional(pool)
+        .await?;
+    return Ok(avatar);
+}
+
+pub async fn get_profile(store: &Store, user_id: i32) -> Result<User, ProfileError> {
+    match store.find_user_by_id(user_id).await? {
+        Some(user) => return Ok(user),
+        None => return Err(ProfileError::NotFound),
+    }
+}
+
+pub async fn save_changes(store: &Store, user_id: i32, changes: &ProfileChanges) -> Result<User, ProfileError> {
+    match store.update_profile(user_id, changes).await? {
+        Some(user) => return Ok(user),
+        None => return Err(ProfileError::NotFound),
+    }
+}
+
+pub async fn save_avatar(store: &Store, user_id: i32, avatar: Option<&Avatar>) -> Result<User, ProfileError> {
+    match store.set_avatar(user_id, avatar).await? {
+        Some(user) => return Ok(user),
+        None => return Err(ProfileError::NotFound),
+    }
+}
@@ -1,15 +1,54 @@
-use macros::db_func;
-use sqlx::{Execute, query_as};
-use shared::db::signup::User;
+use macros::{db_err, db_func};
+use sqlx::query_as;
+use shared::{AnyErr, db::signup::User};
+
+use crate::db::store::Store;
+
+#[db_err]
+pub enum SearchUsersError {}
+
+/// Escapes `\`, `%` and `_` so they match themselves in a `LIKE` pattern.
+pub fn escape_like(name: &str) -> String {
+    let mut escaped = String::with_capacity(name.len());
+    for c in name.chars() {
+        if matches!(c, '\\' | '%' | '_') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    return escaped;
+}
+
+/// Usernames containing `name` in any case, the ones starting with it first, then by username.
+/// `after` is the last username of the previous page. Leaves out `viewer_id` and users they
+/// blocked or were blocked by.
 #[db_func]
-pub async fn search_users(name: &str,limit:i64,page:i64)->Result<Vec<User>,sqlx::Error>{
-    let offset = limit * (page-1);
-    let name = format!("%{name}%");
-    println!("Searching users with name pattern: {}", name);
-    println!("Limit: {}, Offset: {}", limit, offset);
-    let sql = query_as!(User,r#"SELECT id,username,hash_password,created_at,updated_at  from users where username LIKE $1 LIMIT $2 OFFSET $3"#,name,limit,offset).sql();
-    println!("search sql:{}",sql);
-    let res = query_as!(User,r#"SELECT id,username,hash_password,created_at,updated_at from users where username LIKE $1 LIMIT $2 OFFSET $3"#,name,limit,offset)    
-    .fetch_all(pool).await;
-    return res;
-}
\ No newline at end of file
+pub async fn search_users(viewer_id: i32, name: &str, limit: i64, after: Option<&str>) -> Result<Vec<User>, SearchUsersError> {
+    let escaped = escape_like(name);
+    let res = query_as!(User, r#"SELECT id,username,hash_password,created_at,updated_at,disabled_at,display_name,bio,status_text,avatar_updated_at,last_seen_at,hide_last_seen,contacts_only_dms from users
+        where username ILIKE $1 ESCAPE '\'
+        AND id <> $4
+        AND NOT EXISTS (SELECT 1 FROM user_block b WHERE (b.blocker_id = $4 AND b.blocked_id = users.id) OR (b.blocker_id = users.id AND b.blocked_id = $4))
+        AND ($5::text IS NULL OR (CASE WHEN username ILIKE $2 ESCAPE '\' THEN 0 ELSE 1 END, username COLLATE "C")
+            > (CASE WHEN $5 ILIKE $2 ESCAPE '\' THEN 0 ELSE 1 END, $5 COLLATE "C"))
+        ORDER BY CASE WHEN username ILIKE $2 ESCAPE '\' THEN 0 ELSE 1 END, username COLLATE "C"
+        LIMIT $3"#,
+        format!("%{escaped}%"),
+        format!("{escaped}%"),
+        limit,
+        viewer_id,
+        after)
+    .fetch_all(pool).await?;
+    return Ok(res);
+}
+
+/// One page of `search_users`, with the cursor for the next one when there is more.
+pub async fn search(store: &Store, viewer_id: i32, name: &str, limit: i64, after: Option<&str>) -> Result<(Vec<User>, Option<String>), SearchUsersError> {
+    let mut users = store.search_users(viewer_id, name, limit + 1, after).await?;
+    let mut next_cursor = None;
+    if users.len() as i64 > limit {
+        users.truncate(limit as usize);
+        next_cursor = users.last().map(|u| u.username.clone());
+    }
+    return Ok((users, next_cursor));
+}
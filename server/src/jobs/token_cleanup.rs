@@ -0,0 +1,76 @@
+use std::sync::atomic::{AtomicI64, AtomicU64, Ordering};
+
+use rocket::{fairing::AdHoc, tokio};
+use tracing::{error, info};
+use crate::{config::{Settings, TokenSettings}, db::{auth::cleanup::CleanupStats, store::Store}};
+
+/// Running totals since startup, read by whoever reports metrics.
+pub struct TokenCleanupMetrics {
+    pub runs: AtomicU64,
+    pub failures: AtomicU64,
+    pub tokens_removed: AtomicU64,
+    pub families_removed: AtomicU64,
+    pub last_run_unix: AtomicI64,
+}
+
+pub static TOKEN_CLEANUP_METRICS: TokenCleanupMetrics = TokenCleanupMetrics {
+    runs: AtomicU64::new(0),
+    failures: AtomicU64::new(0),
+    tokens_removed: AtomicU64::new(0),
+    families_removed: AtomicU64::new(0),
+    last_run_unix: AtomicI64::new(0),
+};
+
+/// Runs one cleanup pass and records it in [`TOKEN_CLEANUP_METRICS`].
+pub async fn run_once(store: &Store, tokens: &TokenSettings) -> Result<CleanupStats, sqlx::Error> {
+    let metrics = &TOKEN_CLEANUP_METRICS;
+    metrics.runs.fetch_add(1, Ordering::Relaxed);
+    metrics
+        .last_run_unix
+        .store(chrono::Utc::now().timestamp(), Ordering::Relaxed);
+    match store.cleanup_tokens(tokens.refresh_ttl(), tokens.retention()).await {
+        Ok(stats) => {
+            metrics
+                .tokens_removed
+                .fetch_add(stats.tokens_removed, Ordering::Relaxed);
+            metrics
+                .families_removed
+                .fetch_add(stats.families_removed, Ordering::Relaxed);
+            info!(
+                tokens_removed = stats.tokens_removed,
+                families_removed = stats.families_removed,
+                "token cleanup finished"
+            );
+            return Ok(stats);
+        }
+        Err(e) => {
+            metrics.failures.fetch_add(1, Ordering::Relaxed);
+            error!(error = %e, "token cleanup failed");
+            return Err(e);
+        }
+    }
+}
+
+/// Spawns the periodic cleanup once Rocket has lifted off.
+pub fn fairing() -> AdHoc {
+    return AdHoc::on_liftoff("Token cleanup", |rocket| {
+        Box::pin(async move {
+            let store = rocket
+                .state::<Store>()
+                .expect("Store must be managed before the token cleanup job")
+                .clone();
+            let tokens = rocket
+                .state::<Settings>()
+                .expect("Settings must be managed before the token cleanup job")
+                .tokens
+                .clone();
+            tokio::spawn(async move {
+                let mut interval = tokio::time::interval(tokens.cleanup_interval());
+                loop {
+                    interval.tick().await;
+                    let _ = run_once(&store, &tokens).await;
+                }
+            });
+        })
+    });
+}
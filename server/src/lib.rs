@@ -0,0 +1,56 @@
+#[macro_use]
+extern crate rocket;
+
+use rocket::{Build, Rocket};
+
+use crate::config::Settings;
+use crate::cors::Cors;
+use crate::db::store::Store;
+use crate::metrics::{Metrics, RequestMetrics};
+use crate::presence::PresenceTracker;
+use crate::rate_limit::RateLimiter;
+use crate::request_id::RequestLog;
+use crate::routes::catchers::{too_many_requests, unauthorized};
+use crate::routes::health::{live, ready, version};
+use crate::routes::metrics::prometheus_metrics;
+use crate::routes::presence::{heartbeat, stream as presence_stream};
+use crate::routes::{auth::{jwks::jwks, login::login, refresh::refresh, signup::signup}, chat::conversation::{create_conversation, list_conversations, mute_conversation}, users::{account::{delete_account, export_account}, block::{block_user, list_blocked, unblock_user}, contacts::{list_contacts, remove_contact, request_contact, respond_contact}, profile::{avatar, get_me, get_user, set_avatar, update_me}, search::search_users}};
+
+#[macro_use]
+pub mod routes;
+pub mod db;
+pub mod jobs;
+pub mod config;
+pub mod cors;
+pub mod rate_limit;
+pub mod metrics;
+pub mod request_id;
+pub mod telemetry;
+pub mod admin;
+pub mod presence;
+
+#[get("/")]
+fn index() -> &'static str {
+    return "Hello World";
+}
+
+/// Every route and catcher mounted on `store`, configured by `settings`. Background jobs are
+/// attached by the caller.
+pub fn rocket(store: Store, settings: Settings) -> Rocket<Build> {
+    let rocket = rocket::build()
+    .attach(RequestLog)
+    .attach(Cors::new(&settings.cors))
+    .attach(RequestMetrics)
+    .manage(Metrics::default())
+    .manage(RateLimiter::new(settings.rate_limits.clone()))
+    .manage(PresenceTracker::new(&settings.presence))
+    .manage(settings)
+    .manage(store)
+    .register("/", catchers![unauthorized, too_many_requests])
+    .mount("/", routes![index, version, prometheus_metrics])
+    .mount("/health", routes![live, ready])
+    .mount("/users", routes![avatar])
+    .mount("/presence", routes![presence_stream])
+    .mount("/.well-known", routes![jwks]);
+    return mount_api!(rocket, [signup, login, refresh, search_users, get_me, update_me, set_avatar, get_user, export_account, delete_account, block_user, unblock_user, list_blocked, list_contacts, request_contact, respond_contact, remove_contact, create_conversation, list_conversations, mute_conversation, heartbeat]);
+}
@@ -1,38 +1,30 @@
-#[macro_use]
-extern crate rocket;
-
-use std::{env, str::FromStr};
-
 use dotenvy::dotenv;
-use sqlx::{PgPool, postgres::PgConnectOptions};
-
-use crate::routes::{auth::{login::login, refresh::refresh, signup::signup}, chat::conversation::create_conversation, users::search::search_users};
 
-mod routes;
-mod db;
+use server::config::Settings;
+use server::db::auth::keys::JwtKeys;
+use server::db::store;
+use server::jobs::token_cleanup;
+use server::telemetry;
 
-#[get("/")]
-fn index() -> &'static str {
-    return "Hello World";
+/// Exits before anything starts when the configuration is missing or invalid.
+fn load_settings() -> Settings {
+    return Settings::from_figment(&Settings::figment()).unwrap_or_else(|e| {
+        eprintln!("Invalid configuration: {e}");
+        std::process::exit(1);
+    });
 }
-#[launch]
-async fn rocket() -> _ {
-    let _ = dotenv();
-    let db_url = env::var("DATABASE_URL").expect("DB_URL not set");
-    let connect_options = PgConnectOptions::from_str(&db_url)
-        .unwrap();
-    let pool = PgPool::connect_with(connect_options)
-        .await
-        .expect("Unable to connect to database");
-    sqlx::migrate!("./migrations")
-        .run(&pool)
-        .await
-        .expect("Failed to run migrations");
-    rocket::build()
-    .manage(pool)
-    .mount("/", routes![index])
-    .mount("/auth", routes![signup,login,refresh])
-    .mount("/users",routes![search_users])
-    .mount("/chat/conversation", routes![create_conversation])
 
+#[rocket::main]
+async fn main() -> Result<(), Box<rocket::Error>> {
+    let _ = dotenv();
+    let settings = load_settings();
+    telemetry::init(&settings.logging);
+    JwtKeys::init().unwrap_or_else(|e| panic!("Unable to load JWT keys: {e}"));
+    let store = store::connect(&settings).await;
+    server::rocket(store, settings)
+    .attach(token_cleanup::fairing())
+    .launch()
+    .await
+    .map_err(Box::new)?;
+    return Ok(());
 }
@@ -0,0 +1,194 @@
+use std::{sync::atomic::Ordering, time::Instant};
+
+use prometheus::{
+    Encoder, HistogramOpts, HistogramVec, IntCounter, IntCounterVec, IntGauge, Opts, Registry,
+    TextEncoder,
+};
+use rocket::{
+    Data, Request, Response,
+    fairing::{Fairing, Info, Kind},
+    http::Status,
+    outcome::Outcome,
+    request::{self, FromRequest},
+};
+
+use crate::{
+    config::Settings,
+    db::{auth::error::AuthError, store::Store},
+    jobs::token_cleanup::TOKEN_CLEANUP_METRICS,
+};
+
+/// Everything `/metrics` reports. Each Rocket instance has its own registry.
+pub struct Metrics {
+    registry: Registry,
+    requests: IntCounterVec,
+    request_duration: HistogramVec,
+    logins: IntCounterVec,
+    refresh_rotations: IntCounter,
+    messages_sent: IntCounter,
+    pool_connections: IntGauge,
+    pool_idle_connections: IntGauge,
+    cleanup_runs: IntCounter,
+    cleanup_failures: IntCounter,
+    cleanup_tokens_removed: IntCounter,
+    cleanup_families_removed: IntCounter,
+    cleanup_last_run: IntGauge,
+}
+
+fn counter(registry: &Registry, name: &str, help: &str) -> IntCounter {
+    let counter = IntCounter::new(name, help).unwrap();
+    registry.register(Box::new(counter.clone())).unwrap();
+    return counter;
+}
+
+fn gauge(registry: &Registry, name: &str, help: &str) -> IntGauge {
+    let gauge = IntGauge::new(name, help).unwrap();
+    registry.register(Box::new(gauge.clone())).unwrap();
+    return gauge;
+}
+
+/// Brings a counter up to a running total kept elsewhere.
+fn catch_up(counter: &IntCounter, total: u64) {
+    counter.inc_by(total.saturating_sub(counter.get()));
+}
+
+impl Default for Metrics {
+    fn default() -> Self {
+        let registry = Registry::new();
+        let requests = IntCounterVec::new(
+            Opts::new("http_requests_total", "HTTP requests by route and status"),
+            &["method", "route", "status"],
+        )
+        .unwrap();
+        registry.register(Box::new(requests.clone())).unwrap();
+        let request_duration = HistogramVec::new(
+            HistogramOpts::new("http_request_duration_seconds", "Time spent handling a request"),
+            &["method", "route"],
+        )
+        .unwrap();
+        registry.register(Box::new(request_duration.clone())).unwrap();
+        let logins = IntCounterVec::new(Opts::new("auth_logins_total", "Login attempts by result"), &["result"]).unwrap();
+        registry.register(Box::new(logins.clone())).unwrap();
+
+        return Metrics {
+            requests,
+            request_duration,
+            logins,
+            refresh_rotations: counter(&registry, "auth_refresh_rotations_total", "Refresh tokens rotated"),
+            messages_sent: counter(&registry, "messages_sent_total", "Messages stored"),
+            pool_connections: gauge(&registry, "db_pool_connections", "Open database connections"),
+            pool_idle_connections: gauge(&registry, "db_pool_idle_connections", "Idle database connections"),
+            cleanup_runs: counter(&registry, "token_cleanup_runs_total", "Token cleanup passes"),
+            cleanup_failures: counter(&registry, "token_cleanup_failures_total", "Token cleanup passes that failed"),
+            cleanup_tokens_removed: counter(&registry, "token_cleanup_tokens_removed_total", "Refresh tokens deleted by cleanup"),
+            cleanup_families_removed: counter(&registry, "token_cleanup_families_removed_total", "Token families deleted by cleanup"),
+            cleanup_last_run: gauge(&registry, "token_cleanup_last_run_timestamp_seconds", "Unix time of the last cleanup pass"),
+            registry,
+        };
+    }
+}
+
+impl Metrics {
+    pub fn record_login(&self, success: bool) {
+        let result = if success { "success" } else { "failure" };
+        self.logins.with_label_values(&[result]).inc();
+    }
+
+    pub fn record_refresh_rotation(&self) {
+        self.refresh_rotations.inc();
+    }
+
+    pub fn record_message_sent(&self) {
+        self.messages_sent.inc();
+    }
+
+    /// Prometheus text format, with the gauges read from `store` and the cleanup job at call time.
+    pub fn render(&self, store: &Store) -> String {
+        if let Some(pool) = store.pool_stats() {
+            self.pool_connections.set(pool.connections.into());
+            self.pool_idle_connections.set(pool.idle as i64);
+        }
+        let cleanup = &TOKEN_CLEANUP_METRICS;
+        catch_up(&self.cleanup_runs, cleanup.runs.load(Ordering::Relaxed));
+        catch_up(&self.cleanup_failures, cleanup.failures.load(Ordering::Relaxed));
+        catch_up(&self.cleanup_tokens_removed, cleanup.tokens_removed.load(Ordering::Relaxed));
+        catch_up(&self.cleanup_families_removed, cleanup.families_removed.load(Ordering::Relaxed));
+        self.cleanup_last_run.set(cleanup.last_run_unix.load(Ordering::Relaxed));
+
+        let mut buffer = vec![];
+        TextEncoder::new().encode(&self.registry.gather(), &mut buffer).unwrap();
+        return String::from_utf8(buffer).unwrap();
+    }
+}
+
+struct RequestStart(Instant);
+
+/// Counts and times every request by its matched route, or `unmatched`, so unknown paths can't
+/// grow the label set.
+pub struct RequestMetrics;
+
+#[async_trait]
+impl Fairing for RequestMetrics {
+    fn info(&self) -> Info {
+        return Info {
+            name: "Request metrics",
+            kind: Kind::Request | Kind::Response,
+        };
+    }
+
+    async fn on_request(&self, req: &mut Request<'_>, _data: &mut Data<'_>) {
+        req.local_cache(|| RequestStart(Instant::now()));
+    }
+
+    async fn on_response<'r>(&self, req: &'r Request<'_>, res: &mut Response<'r>) {
+        let Some(metrics) = req.rocket().state::<Metrics>() else {
+            return;
+        };
+        let started = req.local_cache(|| RequestStart(Instant::now())).0;
+        let method = req.method().as_str();
+        let route = req.route().map(|route| route.uri.as_str()).unwrap_or("unmatched");
+        let status = res.status().code.to_string();
+        metrics
+            .requests
+            .with_label_values(&[method, route, status.as_str()])
+            .inc();
+        metrics
+            .request_duration
+            .with_label_values(&[method, route])
+            .observe(started.elapsed().as_secs_f64());
+    }
+}
+
+/// Compares without stopping at the first differing byte.
+fn same_token(a: &str, b: &str) -> bool {
+    return a.len() == b.len() && a.bytes().zip(b.bytes()).fold(0, |diff, (x, y)| diff | (x ^ y)) == 0;
+}
+
+/// Lets the request through when no `metrics.bearer_token` is configured, or when it carries
+/// that token as `Authorization: Bearer`.
+pub struct MetricsAuth;
+
+#[async_trait]
+impl<'r> FromRequest<'r> for MetricsAuth {
+    type Error = AuthError;
+
+    async fn from_request(req: &'r Request<'_>) -> request::Outcome<Self, Self::Error> {
+        let expected = req
+            .rocket()
+            .state::<Settings>()
+            .and_then(|settings| settings.metrics.bearer_token.as_deref());
+        let Some(expected) = expected else {
+            return Outcome::Success(MetricsAuth);
+        };
+        let error = match req.headers().get_one("Authorization").map(|h| h.split_once(' ')) {
+            Some(Some(("Bearer", token))) if same_token(token, expected) => {
+                return Outcome::Success(MetricsAuth);
+            }
+            Some(Some(("Bearer", _))) => AuthError::InvalidToken,
+            Some(_) => AuthError::MalformedToken,
+            None => AuthError::MissingToken,
+        };
+        req.local_cache(|| Some(error));
+        return Outcome::Error((Status::Unauthorized, error));
+    }
+}
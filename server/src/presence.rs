@@ -0,0 +1,103 @@
+//! Who is connected right now, from open realtime streams and client heartbeats. Kept in process
+//! memory: only `last_seen_at` reaches the database, so with several server instances each one
+//! only knows the connections it holds itself.
+
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use shared::routes::{chat::conversation::ConversationMember, users::presence::PresenceStatus};
+
+use crate::config::PresenceSettings;
+
+#[derive(Default)]
+struct Seen {
+    /// Open `/presence/stream` connections.
+    streams: u32,
+    last_heartbeat: Option<Instant>,
+    /// Last heartbeat that said the user was active, or the last stream they opened.
+    last_active: Option<Instant>,
+}
+
+pub struct PresenceTracker {
+    away_after: Duration,
+    heartbeat_timeout: Duration,
+    users: Arc<Mutex<HashMap<i32, Seen>>>,
+}
+
+/// Counts as one open stream until dropped.
+pub struct StreamConnection {
+    user_id: i32,
+    users: Arc<Mutex<HashMap<i32, Seen>>>,
+}
+
+impl Drop for StreamConnection {
+    fn drop(&mut self) {
+        if let Some(seen) = self.users.lock().unwrap().get_mut(&self.user_id) {
+            seen.streams = seen.streams.saturating_sub(1);
+        }
+    }
+}
+
+impl PresenceTracker {
+    pub fn new(settings: &PresenceSettings) -> Self {
+        return PresenceTracker {
+            away_after: settings.away_after(),
+            heartbeat_timeout: settings.heartbeat_timeout(),
+            users: Arc::new(Mutex::new(HashMap::new())),
+        };
+    }
+
+    fn connected(&self, seen: &Seen, now: Instant) -> bool {
+        return seen.streams > 0
+            || seen.last_heartbeat.is_some_and(|at| now.duration_since(at) <= self.heartbeat_timeout);
+    }
+
+    /// Looks up `user_id`, dropping everyone who went offline on the way.
+    fn seen<'a>(&self, users: &'a mut HashMap<i32, Seen>, user_id: i32, now: Instant) -> &'a mut Seen {
+        users.retain(|id, seen| *id == user_id || self.connected(seen, now));
+        return users.entry(user_id).or_default();
+    }
+
+    pub fn heartbeat(&self, user_id: i32, active: bool) {
+        let now = Instant::now();
+        let mut users = self.users.lock().unwrap();
+        let seen = self.seen(&mut users, user_id, now);
+        seen.last_heartbeat = Some(now);
+        if active {
+            seen.last_active = Some(now);
+        }
+    }
+
+    pub fn connect(&self, user_id: i32) -> StreamConnection {
+        let now = Instant::now();
+        let mut users = self.users.lock().unwrap();
+        let seen = self.seen(&mut users, user_id, now);
+        seen.streams += 1;
+        seen.last_active = Some(now);
+        return StreamConnection { user_id, users: self.users.clone() };
+    }
+
+    pub fn status(&self, user_id: i32) -> PresenceStatus {
+        let now = Instant::now();
+        let users = self.users.lock().unwrap();
+        let Some(seen) = users.get(&user_id) else {
+            return PresenceStatus::Offline;
+        };
+        if !self.connected(seen, now) {
+            return PresenceStatus::Offline;
+        }
+        match seen.last_active {
+            Some(at) if now.duration_since(at) < self.away_after => return PresenceStatus::Online,
+            _ => return PresenceStatus::Away,
+        }
+    }
+
+    pub fn fill_members(&self, members: &mut [ConversationMember]) {
+        for member in members.iter_mut() {
+            member.presence.status = self.status(member.user_id);
+        }
+    }
+}
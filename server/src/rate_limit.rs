@@ -0,0 +1,108 @@
+use std::{
+    collections::HashMap,
+    net::IpAddr,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use rocket::{
+    Request,
+    http::Status,
+    outcome::Outcome,
+    request::{self, FromRequest},
+};
+use shared::api::{Endpoint, Login};
+
+use crate::config::RateLimitSettings;
+
+const WINDOW: Duration = Duration::from_secs(60);
+/// Past this many tracked clients, finished windows are dropped before adding another.
+const PRUNE_AT: usize = 10_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Bucket {
+    Auth,
+    Api,
+}
+
+struct Window {
+    started: Instant,
+    count: u32,
+}
+
+/// Fixed one minute windows per client IP and bucket, shared by every route through the
+/// [`RateLimit`] guard.
+pub struct RateLimiter {
+    limits: RateLimitSettings,
+    windows: Mutex<HashMap<(Bucket, Option<IpAddr>), Window>>,
+}
+
+impl RateLimiter {
+    pub fn new(limits: RateLimitSettings) -> Self {
+        return RateLimiter {
+            limits,
+            windows: Mutex::new(HashMap::new()),
+        };
+    }
+
+    /// Counts one request. Over the limit, returns how many seconds are left in the window.
+    pub fn check(&self, bucket: Bucket, ip: Option<IpAddr>) -> Result<(), u64> {
+        let limit = match bucket {
+            Bucket::Auth => self.limits.auth_per_minute,
+            Bucket::Api => self.limits.api_per_minute,
+        };
+        if limit == 0 {
+            return Ok(());
+        }
+        let now = Instant::now();
+        let mut windows = self.windows.lock().unwrap();
+        if windows.len() >= PRUNE_AT {
+            windows.retain(|_, window| now.duration_since(window.started) < WINDOW);
+        }
+        let window = windows.entry((bucket, ip)).or_insert(Window { started: now, count: 0 });
+        if now.duration_since(window.started) >= WINDOW {
+            window.started = now;
+            window.count = 0;
+        }
+        if window.count >= limit {
+            let left = WINDOW.saturating_sub(now.duration_since(window.started));
+            return Err(left.as_secs().max(1));
+        }
+        window.count += 1;
+        return Ok(());
+    }
+}
+
+/// Seconds until the client may retry, left for the 429 catcher.
+#[derive(Debug, Clone, Copy)]
+pub struct RetryAfter(pub u64);
+
+/// Request guard that counts the request against its bucket: the auth scope for signup, login
+/// and refresh, the api bucket for everything else.
+pub struct RateLimit;
+
+#[async_trait]
+impl<'r> FromRequest<'r> for RateLimit {
+    type Error = RetryAfter;
+
+    async fn from_request(req: &'r Request<'_>) -> request::Outcome<Self, Self::Error> {
+        let Some(limiter) = req.rocket().state::<RateLimiter>() else {
+            return Outcome::Success(RateLimit);
+        };
+        let bucket = if req.uri().path().starts_with(Login::SCOPE) {
+            Bucket::Auth
+        } else {
+            Bucket::Api
+        };
+        match limiter.check(bucket, req.client_ip()) {
+            Ok(()) => {
+                return Outcome::Success(RateLimit);
+            }
+            Err(seconds) => {
+                let retry_after = RetryAfter(seconds);
+                req.local_cache(|| Some(retry_after));
+                return Outcome::Error((Status::TooManyRequests, retry_after));
+            }
+        }
+    }
+}
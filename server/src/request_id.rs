@@ -0,0 +1,85 @@
+use std::{convert::Infallible, fmt, time::Instant};
+
+use rocket::{
+    Data, Request, Response,
+    fairing::{Fairing, Info, Kind},
+    outcome::Outcome,
+    request::{self, FromRequest},
+};
+use tracing::info;
+
+pub const REQUEST_ID_HEADER: &str = "X-Request-Id";
+
+/// Identifies one request in the logs. Taken from the caller's `X-Request-Id` when it looks sane,
+/// generated otherwise, and sent back in the response.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RequestId(pub String);
+
+impl fmt::Display for RequestId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return f.write_str(&self.0);
+    }
+}
+
+fn acceptable(id: &str) -> bool {
+    return !id.is_empty()
+        && id.len() <= 128
+        && id.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.' | ':'));
+}
+
+impl RequestId {
+    pub fn of<'r>(req: &'r Request<'_>) -> &'r RequestId {
+        return req.local_cache(|| {
+            let incoming = req.headers().get_one(REQUEST_ID_HEADER).filter(|id| acceptable(id));
+            match incoming {
+                Some(id) => RequestId(id.into()),
+                None => RequestId(uuid::Uuid::new_v4().to_string()),
+            }
+        });
+    }
+}
+
+#[async_trait]
+impl<'r> FromRequest<'r> for RequestId {
+    type Error = Infallible;
+
+    async fn from_request(req: &'r Request<'_>) -> request::Outcome<Self, Self::Error> {
+        return Outcome::Success(RequestId::of(req).clone());
+    }
+}
+
+struct Started(Instant);
+
+/// Assigns the request id, echoes it in the response and logs one line per request.
+pub struct RequestLog;
+
+#[async_trait]
+impl Fairing for RequestLog {
+    fn info(&self) -> Info {
+        return Info {
+            name: "Request log",
+            kind: Kind::Request | Kind::Response,
+        };
+    }
+
+    async fn on_request(&self, req: &mut Request<'_>, _data: &mut Data<'_>) {
+        req.local_cache(|| Started(Instant::now()));
+        RequestId::of(req);
+    }
+
+    async fn on_response<'r>(&self, req: &'r Request<'_>, res: &mut Response<'r>) {
+        let request_id = RequestId::of(req);
+        res.set_raw_header(REQUEST_ID_HEADER, request_id.0.clone());
+        let started = req.local_cache(|| Started(Instant::now())).0;
+        // The path only: query strings carry search terms.
+        info!(
+            request_id = %request_id,
+            method = %req.method(),
+            path = %req.uri().path(),
+            route = req.route().map(|route| route.uri.as_str()).unwrap_or("unmatched"),
+            status = res.status().code,
+            latency_ms = started.elapsed().as_secs_f64() * 1000.0,
+            "request finished"
+        );
+    }
+}
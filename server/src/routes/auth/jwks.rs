@@ -0,0 +1,10 @@
+use jsonwebtoken::jwk::JwkSet;
+use rocket::serde::json::Json;
+
+use crate::db::auth::keys::JwtKeys;
+
+/// Public keys other services can use to verify access tokens.
+#[get("/jwks.json")]
+pub fn jwks() -> Json<JwkSet> {
+    return Json(JwtKeys::jwks());
+}
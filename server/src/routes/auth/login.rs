@@ -1,29 +1,22 @@
+use macros::api_route;
 use rocket::{State, serde::json::Json};
-use shared::{Response, routes::auth::login::{LoginRequest, LoginResponse}};
-use sqlx::PgPool;
+use shared::{ApiResult, Response, api::{Login, Req, Res}, routes::auth::login::{LoginRequest, LoginResponse}};
 
-use crate::db::auth::{jwt::{get_access_token_from_refresh, get_new_refresh_token}, login::check_password};
+use crate::{config::Settings, metrics::Metrics, db::{auth::{jwt::{get_access_token_from_refresh, get_new_refresh_token}, login::check_password}, store::Store}, rate_limit::RateLimit};
 
-#[post("/login",data="<payload>")]
-pub async fn login(pool: &State<PgPool>, payload:Json<LoginRequest>)->Response<LoginResponse>{
+#[api_route(Login, data = "<payload>")]
+pub async fn login(_limit: RateLimit, store: &State<Store>, settings: &State<Settings>, metrics: &State<Metrics>, payload:Json<Req<Login>>)->ApiResult<Res<Login>>{
     let LoginRequest {email,password} = payload.0;
-    let user = check_password(pool, &email, &password).await;
-    if user.is_ok() {
-        let user = user.unwrap();
-        let refresh_token = get_new_refresh_token(pool, user.id).await;
-        if refresh_token.is_err() {
-            return Response::internal_error("COULD NOT GENERATE REFRESH TOKEN", None);
-        }
-        let refresh_token = refresh_token.unwrap();
-        let new_tokens = get_access_token_from_refresh(pool, &refresh_token).await;
-        if new_tokens.is_err(){
-            return  Response::internal_error("COULD NOT GENERATE ACCESS TOKEN", None);
-        }
-        let new_tokens = new_tokens.unwrap();
-        return Response::success("SUCCESS",LoginResponse{
-            access_token: new_tokens.0.clone(),
-            refresh_token: new_tokens.1.clone()
-        })
-    };
-    return Response::unauthorized("UNAUTHORIZED", None)
-}
\ No newline at end of file
+    let user = check_password(store, &email, &password)
+        .await
+        .inspect_err(|_| metrics.record_login(false))?;
+    let refresh_token = get_new_refresh_token(store, &settings.tokens, user.id)
+        .await
+        .map_err(|_| Response::internal_error("COULD NOT GENERATE REFRESH TOKEN", None))?;
+    let (access_token, refresh_token) = get_access_token_from_refresh(store, &settings.tokens, &refresh_token).await?;
+    metrics.record_login(true);
+    return Ok(Response::success("SUCCESS",LoginResponse{
+        access_token,
+        refresh_token
+    }));
+}
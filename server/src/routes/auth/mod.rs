@@ -1,3 +1,4 @@
 pub mod signup;
 pub mod login;
-pub mod refresh;
\ No newline at end of file
+pub mod refresh;
+pub mod jwks;
\ No newline at end of file
@@ -1,20 +1,13 @@
+use macros::api_route;
 use rocket::{State, serde::json::Json};
-use shared::{Response, routes::auth::refresh::{RefreshRequest, RefreshResponse}};
-use sqlx::PgPool;
+use shared::{ApiResult, Response, api::{Refresh, Req, Res}, routes::auth::refresh::{RefreshRequest, RefreshResponse}};
 
-use crate::db::auth::jwt::get_access_token_from_refresh;
+use crate::{config::Settings, metrics::Metrics, db::{auth::jwt::get_access_token_from_refresh, store::Store}, rate_limit::RateLimit};
 
-#[post("/refresh",data="<payload>")]
-pub async fn refresh(pool: &State<PgPool>, payload:Json<RefreshRequest>)->Response<RefreshResponse>{
+#[api_route(Refresh, data = "<payload>")]
+pub async fn refresh(_limit: RateLimit, store: &State<Store>, settings: &State<Settings>, metrics: &State<Metrics>, payload:Json<Req<Refresh>>)->ApiResult<Res<Refresh>>{
     let RefreshRequest {refresh_token} = payload.0;
-    let tokens = get_access_token_from_refresh(pool, &refresh_token).await;
-    match tokens {
-        Ok(tokens) => {
-            let (access_token,refresh_token) = tokens;
-            Response::success("Refreshed", RefreshResponse { refresh_token, access_token })
-        },
-        Err(_) => {
-            Response::unauthorized("BAD TOKEN", None)
-        },
-    }
-}
\ No newline at end of file
+    let (access_token,refresh_token) = get_access_token_from_refresh(store, &settings.tokens, &refresh_token).await?;
+    metrics.record_refresh_rotation();
+    return Ok(Response::success("Refreshed", RefreshResponse { refresh_token, access_token }));
+}
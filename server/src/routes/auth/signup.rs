@@ -1,30 +1,36 @@
+use macros::api_route;
 use rocket::{State, serde::json::Json};
 use shared::{
-    Response, routes::auth::signup::{SignupRequest, SignupResponse}
+    ApiResult, FieldError, Response, api::{Req, Res, Signup}, routes::auth::signup::SignupRequest, ErrorCode
 };
-use sqlx::PgPool;
 
-use crate::db;
+use crate::{config::Settings, db::{self, store::Store}, rate_limit::RateLimit};
 
-#[post("/signup", data = "<payload>")]
+fn validate(payload: &SignupRequest) -> Vec<FieldError> {
+    let mut errors = vec![];
+    if payload.email.trim().is_empty() {
+        errors.push(FieldError::new("email", "must not be empty"));
+    }
+    if payload.password.is_empty() {
+        errors.push(FieldError::new("password", "must not be empty"));
+    }
+    return errors;
+}
+
+#[api_route(Signup, data = "<payload>")]
 pub async fn signup(
-    pool: &State<PgPool>,
-    payload: Json<SignupRequest>,
-) -> Response<SignupResponse> {
-    let SignupRequest { email, password } = payload.0;
-    let new_user = db::auth::signup::signup(pool, &email, &password).await;
-    match new_user {
-        Ok(id) => Response::success("User created successfully",id),
-        Err(e) => {
-            match e {
-                db::auth::signup::SignupError::UserAlreadyExists => {
-                    Response::bad_request("User already exists",None)
-                },
-                db::auth::signup::SignupError::Sqlx(error) => {
-                    let e_string: String = error.to_string();
-                    Response::internal_error(&e_string, None)
-                }
-            }
-        }
+    _limit: RateLimit,
+    store: &State<Store>,
+    settings: &State<Settings>,
+    payload: Json<Req<Signup>>,
+) -> ApiResult<Res<Signup>> {
+    let errors = validate(&payload);
+    if !errors.is_empty() {
+        return Err(Response::bad_request("Invalid signup details", None)
+            .with_code(ErrorCode::ValidationFailed)
+            .with_details(errors));
     }
+    let SignupRequest { email, password } = payload.0;
+    let id = db::auth::signup::signup(store, settings.bcrypt_cost, &email, &password).await?;
+    return Ok(Response::success("User created successfully",id));
 }
@@ -0,0 +1,27 @@
+use rocket::{Request, http::Header};
+use shared::{ErrorCode, Response};
+
+use crate::{db::auth::error::AuthError, rate_limit::RetryAfter};
+
+/// Renders the reason a `Claims` guard rejected the request.
+#[catch(401)]
+pub fn unauthorized(req: &Request) -> Response<()> {
+    let error = req
+        .local_cache(|| None::<AuthError>)
+        .unwrap_or(AuthError::MissingToken);
+    return error.into();
+}
+
+#[derive(Responder)]
+pub struct RateLimited(Response<()>, Header<'static>);
+
+/// Sent when a `RateLimit` guard turns the request away.
+#[catch(429)]
+pub fn too_many_requests(req: &Request) -> RateLimited {
+    let RetryAfter(seconds) = req
+        .local_cache(|| None::<RetryAfter>)
+        .unwrap_or(RetryAfter(60));
+    let body = Response::too_many_requests("Too many requests, slow down", None)
+        .with_code(ErrorCode::RateLimited);
+    return RateLimited(body, Header::new("Retry-After", seconds.to_string()));
+}
@@ -1,38 +1,79 @@
+use macros::api_route;
 use rocket::{State, serde::json::Json};
-use serde::{Deserialize, Serialize};
-use shared::{Response, routes::chat::conversation::{CreateConversationRequest, CreateConversationResponse}};
-use sqlx::PgPool;
+use shared::{
+    ApiResult, ErrorCode, FieldError, Response,
+    api::{CreateConversation, ListConversations, MuteConversation, Req, Res},
+    routes::chat::conversation::{ConversationList, ConversationListItem, CreateConversationRequest},
+};
 
-use crate::db::{auth::jwt::Claims, chat};
+use crate::{
+    db::{auth::jwt::Claims, chat::conversation::{self, ListConversationsError}, store::Store},
+    metrics::Metrics,
+    presence::PresenceTracker,
+    rate_limit::RateLimit,
+};
 
 
-#[post("/create", data = "<payload>")]
+#[api_route(CreateConversation, data = "<payload>")]
 pub async fn create_conversation(
-    pool: &State<PgPool>,
-    payload: Json<CreateConversationRequest>,
+    _limit: RateLimit,
+    store: &State<Store>,
+    metrics: &State<Metrics>,
+    tracker: &State<PresenceTracker>,
+    payload: Json<Req<CreateConversation>>,
     claims: Claims,
-)->Response<CreateConversationResponse>{
-    let CreateConversationRequest { mut participant_ids} = payload.0;
+)->ApiResult<Res<CreateConversation>>{
+    let CreateConversationRequest { mut participant_ids, first_message } = payload.0;
+    if participant_ids.is_empty() {
+        return Err(Response::bad_request("Pick at least one participant", None)
+            .with_code(ErrorCode::ValidationFailed)
+            .with_details(vec![FieldError::new("participant_ids", "must not be empty")]));
+    }
     let Claims{user_id,..} = claims;
+    conversation::check_blocks(store, user_id, &participant_ids).await?;
+    conversation::check_contacts_only(store, user_id, &participant_ids).await?;
     participant_ids.push(user_id);
-    let new_conversation_id = chat::conversation::create_conversation(pool, None, participant_ids).await;
-    match new_conversation_id {
-        Ok(create_response) => {
-            return Response::success("Conversation Created", create_response);
-        },
-        Err(e) => {
-            match e  {
-                chat::conversation::CreateConversationError::InvalidUsers => {
-                    error!("Invalid user IDs provided while creating conversation");
-                    return Response::bad_request("One or more user IDs are invalid", None);
-                },
-                chat::conversation::CreateConversationError::Sqlx(error) => {
-                    let e_string: String = error.to_string();
-                    error!("Database error while creating conversation: {}", e_string.clone());
-                    return Response::internal_error(&e_string, None);
-                },
-            }
-        },
+    let first_message = first_message
+        .filter(|text| !text.trim().is_empty())
+        .map(|text| (user_id, text));
+    let sends_message = first_message.is_some();
+    let mut create_response = store.create_conversation(None, participant_ids, first_message).await?;
+    tracker.fill_members(&mut create_response.members);
+    if sends_message {
+        metrics.record_message_sent();
     }
+    return Ok(Response::success("Conversation Created", create_response));
+}
+
+#[api_route(ListConversations)]
+pub async fn list_conversations(
+    _limit: RateLimit,
+    store: &State<Store>,
+    tracker: &State<PresenceTracker>,
+    claims: Claims,
+) -> ApiResult<Res<ListConversations>> {
+    let conversations = store
+        .list_conversations(Some(claims.user_id))
+        .await
+        .map_err(ListConversationsError::from)?
+        .into_iter()
+        .map(|conversation| {
+            let mut conversation = ConversationListItem::from(conversation);
+            tracker.fill_members(&mut conversation.members);
+            conversation
+        })
+        .collect();
+    return Ok(Response::success("Conversations fetched", ConversationList { conversations }));
+}
 
+#[api_route(MuteConversation, data = "<payload>")]
+pub async fn mute_conversation(
+    _limit: RateLimit,
+    store: &State<Store>,
+    payload: Json<Req<MuteConversation>>,
+    claims: Claims,
+) -> ApiResult<Res<MuteConversation>> {
+    conversation::mute(store, payload.conversation_id, claims.user_id, payload.muted).await?;
+    let message = if payload.muted { "Conversation muted" } else { "Conversation unmuted" };
+    return Ok(Response::success(message, ()));
 }
@@ -0,0 +1,79 @@
+use rocket::{
+    State,
+    http::Status,
+    serde::{Serialize, json::Json},
+};
+use tracing::error;
+
+use crate::db::store::Store;
+
+#[derive(Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct Liveness {
+    pub status: &'static str,
+}
+
+#[derive(Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct Readiness {
+    pub ready: bool,
+    /// `ok` or `unreachable`.
+    pub database: &'static str,
+    pub latest_migration: Option<i64>,
+    pub pending_migrations: Option<usize>,
+}
+
+#[derive(Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct VersionInfo {
+    pub version: &'static str,
+    pub git_hash: &'static str,
+    /// Newest migration applied to the database, `None` if it can't be read.
+    pub migration_version: Option<i64>,
+}
+
+/// The process is up. Says nothing about the database.
+#[get("/live")]
+pub fn live() -> Json<Liveness> {
+    return Json(Liveness { status: "ok" });
+}
+
+/// 200 once the database answers and every migration this build ships is applied, 503 otherwise.
+#[get("/ready")]
+pub async fn ready(store: &State<Store>) -> (Status, Json<Readiness>) {
+    match store.migration_status().await {
+        Ok(migrations) => {
+            let ready = migrations.pending == 0;
+            let status = if ready { Status::Ok } else { Status::ServiceUnavailable };
+            return (status, Json(Readiness {
+                ready,
+                database: "ok",
+                latest_migration: migrations.latest_applied,
+                pending_migrations: Some(migrations.pending),
+            }));
+        }
+        Err(e) => {
+            error!(error = %e, "readiness check failed");
+            return (Status::ServiceUnavailable, Json(Readiness {
+                ready: false,
+                database: "unreachable",
+                latest_migration: None,
+                pending_migrations: None,
+            }));
+        }
+    }
+}
+
+#[get("/version")]
+pub async fn version(store: &State<Store>) -> Json<VersionInfo> {
+    let migration_version = store
+        .migration_status()
+        .await
+        .ok()
+        .and_then(|migrations| migrations.latest_applied);
+    return Json(VersionInfo {
+        version: env!("CARGO_PKG_VERSION"),
+        git_hash: env!("GIT_HASH"),
+        migration_version,
+    });
+}
@@ -0,0 +1,13 @@
+use rocket::{State, http::ContentType};
+
+use crate::{
+    db::store::Store,
+    metrics::{Metrics, MetricsAuth},
+};
+
+/// Prometheus scrape endpoint.
+#[get("/metrics")]
+pub fn prometheus_metrics(_auth: MetricsAuth, metrics: &State<Metrics>, store: &State<Store>) -> (ContentType, String) {
+    let content_type = ContentType::new("text", "plain").with_params(("version", "0.0.4"));
+    return (content_type, metrics.render(store));
+}
@@ -1,3 +1,22 @@
+/// Mounts each handler at the scope of the `shared::api` endpoint it was declared for with
+/// `#[api_route]`.
+macro_rules! mount_api {
+    ($rocket:expr, [$($handler:ident),* $(,)?]) => {{
+        let rocket = $rocket;
+        $(
+            let rocket = rocket.mount(
+                <<$handler as shared::api::ApiRoute>::Endpoint as shared::api::Endpoint>::SCOPE,
+                routes![$handler],
+            );
+        )*
+        rocket
+    }};
+}
+
 pub mod auth;
 pub mod users;
-pub mod chat;
\ No newline at end of file
+pub mod chat;
+pub mod catchers;
+pub mod health;
+pub mod metrics;
+pub mod presence;
@@ -0,0 +1,60 @@
+use macros::api_route;
+use rocket::{
+    Shutdown, State,
+    response::stream::{Event, EventStream},
+    serde::json::Json,
+    tokio::{select, time::interval},
+};
+use shared::{ApiResult, Response, api::{Heartbeat, Req, Res}, routes::users::presence::Presence};
+use tracing::warn;
+
+use crate::{
+    config::Settings,
+    db::{auth::jwt::Claims, store::Store, users::profile::ProfileError},
+    presence::PresenceTracker,
+    rate_limit::RateLimit,
+};
+
+#[api_route(Heartbeat, data = "<payload>")]
+pub async fn heartbeat(
+    _limit: RateLimit,
+    store: &State<Store>,
+    tracker: &State<PresenceTracker>,
+    payload: Json<Req<Heartbeat>>,
+    claims: Claims,
+) -> ApiResult<Res<Heartbeat>> {
+    tracker.heartbeat(claims.user_id, payload.active);
+    store.touch_last_seen(claims.user_id).await.map_err(ProfileError::from)?;
+    let presence = Presence { status: tracker.status(claims.user_id), last_seen_at: Some(chrono::Utc::now()) };
+    return Ok(Response::success("Heartbeat received", presence));
+}
+
+/// Server-sent events; the caller counts as connected for as long as this stays open. Only
+/// comments are sent for now, each one also refreshing `last_seen_at`.
+#[get("/stream")]
+pub fn stream(
+    _limit: RateLimit,
+    store: &State<Store>,
+    tracker: &State<PresenceTracker>,
+    settings: &State<Settings>,
+    claims: Claims,
+    mut shutdown: Shutdown,
+) -> EventStream![] {
+    let store = store.inner().clone();
+    let user_id = claims.user_id;
+    let connection = tracker.connect(user_id);
+    let mut ping = interval(settings.presence.ping_interval());
+    return EventStream! {
+        let _connection = connection;
+        loop {
+            select! {
+                _ = ping.tick() => {},
+                _ = &mut shutdown => break,
+            };
+            if let Err(e) = store.touch_last_seen(user_id).await {
+                warn!(error = %e, user_id, "could not record last seen");
+            }
+            yield Event::comment("ping");
+        }
+    };
+}
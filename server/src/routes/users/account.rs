@@ -0,0 +1,27 @@
+use macros::api_route;
+use rocket::{State, serde::json::Json};
+use shared::{ApiResult, FieldError, Response, api::{DeleteAccount, ExportAccount, Req, Res}};
+
+use crate::{db::{auth::jwt::Claims, store::Store, users::account::{self, AccountError}}, rate_limit::RateLimit};
+
+#[api_route(ExportAccount)]
+pub async fn export_account(_limit: RateLimit, store: &State<Store>, claims: Claims) -> ApiResult<Res<ExportAccount>> {
+    let export = account::export_account(store, claims.user_id).await?;
+    return Ok(Response::success("Account exported", export));
+}
+
+#[api_route(DeleteAccount, data = "<payload>")]
+pub async fn delete_account(
+    _limit: RateLimit,
+    store: &State<Store>,
+    payload: Json<Req<DeleteAccount>>,
+    claims: Claims,
+) -> ApiResult<Res<DeleteAccount>> {
+    account::delete_account(store, claims.user_id, &payload.password).await.map_err(|e| match e {
+        AccountError::WrongPassword => {
+            Response::from(AccountError::WrongPassword).with_details(vec![FieldError::new("password", "does not match")])
+        }
+        e => Response::from(e),
+    })?;
+    return Ok(Response::success("Account deleted", ()));
+}
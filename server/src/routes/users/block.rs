@@ -0,0 +1,28 @@
+use macros::api_route;
+use rocket::{State, serde::json::Json};
+use shared::{
+    ApiResult, Response,
+    api::{BlockUser, ListBlocked, Req, Res, UnblockUser},
+    routes::users::{block::BlockedUsers, search::SearchUser},
+};
+
+use crate::{db::{auth::jwt::Claims, store::Store, users::block::{self, BlockError}}, rate_limit::RateLimit};
+
+#[api_route(BlockUser, data = "<payload>")]
+pub async fn block_user(_limit: RateLimit, store: &State<Store>, payload: Json<Req<BlockUser>>, claims: Claims) -> ApiResult<Res<BlockUser>> {
+    block::block(store, claims.user_id, payload.user_id).await?;
+    return Ok(Response::success("User blocked", ()));
+}
+
+#[api_route(UnblockUser, data = "<payload>")]
+pub async fn unblock_user(_limit: RateLimit, store: &State<Store>, payload: Json<Req<UnblockUser>>, claims: Claims) -> ApiResult<Res<UnblockUser>> {
+    store.unblock_user(claims.user_id, payload.user_id).await.map_err(BlockError::from)?;
+    return Ok(Response::success("User unblocked", ()));
+}
+
+#[api_route(ListBlocked)]
+pub async fn list_blocked(_limit: RateLimit, store: &State<Store>, claims: Claims) -> ApiResult<Res<ListBlocked>> {
+    let users = store.list_blocked(claims.user_id).await.map_err(BlockError::from)?;
+    let users = users.into_iter().map(SearchUser::from).collect();
+    return Ok(Response::success("Blocked users fetched", BlockedUsers { users }));
+}
@@ -0,0 +1,41 @@
+use macros::api_route;
+use rocket::{State, serde::json::Json};
+use shared::{
+    ApiResult, Response,
+    api::{ListContacts, RemoveContact, Req, RequestContact, Res, RespondContact},
+    routes::users::contacts::ContactList,
+};
+
+use crate::{db::{auth::jwt::Claims, store::Store, users::contacts::{self, ContactChange, ContactError}}, presence::PresenceTracker, rate_limit::RateLimit};
+
+#[api_route(ListContacts)]
+pub async fn list_contacts(_limit: RateLimit, store: &State<Store>, tracker: &State<PresenceTracker>, claims: Claims) -> ApiResult<Res<ListContacts>> {
+    let rows = store.list_contacts(claims.user_id).await.map_err(ContactError::from)?;
+    let contacts = rows
+        .into_iter()
+        .map(|(row, user)| {
+            let mut contact = row.view(claims.user_id, user);
+            contact.user.presence.status = tracker.status(contact.user.id);
+            contact
+        })
+        .collect();
+    return Ok(Response::success("Contacts fetched", ContactList { contacts }));
+}
+
+#[api_route(RequestContact, data = "<payload>")]
+pub async fn request_contact(_limit: RateLimit, store: &State<Store>, payload: Json<Req<RequestContact>>, claims: Claims) -> ApiResult<Res<RequestContact>> {
+    let contact = contacts::change(store, claims.user_id, payload.user_id, ContactChange::Request).await?;
+    return Ok(Response::success("Contact requested", contact));
+}
+
+#[api_route(RespondContact, data = "<payload>")]
+pub async fn respond_contact(_limit: RateLimit, store: &State<Store>, payload: Json<Req<RespondContact>>, claims: Claims) -> ApiResult<Res<RespondContact>> {
+    let contact = contacts::change(store, claims.user_id, payload.user_id, ContactChange::Respond { accept: payload.accept }).await?;
+    return Ok(Response::success("Contact request answered", contact));
+}
+
+#[api_route(RemoveContact, data = "<payload>")]
+pub async fn remove_contact(_limit: RateLimit, store: &State<Store>, payload: Json<Req<RemoveContact>>, claims: Claims) -> ApiResult<Res<RemoveContact>> {
+    store.remove_contact(claims.user_id, payload.user_id).await.map_err(ContactError::from)?;
+    return Ok(Response::success("Contact removed", ()));
+}
@@ -1 +1,4 @@
-pub mod search;
\ No newline at end of file
+pub mod search;
+pub mod account;
+pub mod profile;
+pub mod block;pub mod contacts;
@@ -0,0 +1,97 @@
+use macros::api_route;
+use rocket::{
+    State,
+    http::{ContentType, Header, Status},
+    serde::json::Json,
+};
+use shared::{
+    ApiResult, ErrorCode, FieldError, Response,
+    api::{GetMe, GetUser, Req, Res, SetAvatar, UpdateMe},
+    db::signup::User,
+    routes::users::profile::{MyProfile, Profile},
+};
+
+use tracing::error;
+
+use crate::{
+    db::{auth::jwt::Claims, store::Store, users::profile},
+    presence::PresenceTracker,
+    rate_limit::RateLimit,
+};
+
+fn invalid_profile(errors: Vec<FieldError>) -> Response<MyProfile> {
+    return Response::bad_request("Invalid profile", None)
+        .with_code(ErrorCode::ValidationFailed)
+        .with_details(errors);
+}
+
+fn profile_of(user: User, tracker: &PresenceTracker) -> Profile {
+    let mut profile = Profile::from(user);
+    profile.presence.status = tracker.status(profile.id);
+    return profile;
+}
+
+fn my_profile_of(user: User, tracker: &PresenceTracker) -> MyProfile {
+    let mut profile = MyProfile::from(user);
+    profile.profile.presence.status = tracker.status(profile.profile.id);
+    return profile;
+}
+
+#[api_route(GetMe)]
+pub async fn get_me(_limit: RateLimit, store: &State<Store>, tracker: &State<PresenceTracker>, claims: Claims) -> ApiResult<Res<GetMe>> {
+    let user = profile::get_profile(store, claims.user_id).await?;
+    return Ok(Response::success("Profile fetched", my_profile_of(user, tracker)));
+}
+
+#[api_route(UpdateMe, data = "<payload>")]
+pub async fn update_me(
+    _limit: RateLimit,
+    store: &State<Store>,
+    tracker: &State<PresenceTracker>,
+    payload: Json<Req<UpdateMe>>,
+    claims: Claims,
+) -> ApiResult<Res<UpdateMe>> {
+    let changes = profile::validate_changes(payload.into_inner()).map_err(invalid_profile)?;
+    let user = profile::save_changes(store, claims.user_id, &changes).await?;
+    return Ok(Response::success("Profile updated", my_profile_of(user, tracker)));
+}
+
+#[api_route(SetAvatar, data = "<payload>")]
+pub async fn set_avatar(
+    _limit: RateLimit,
+    store: &State<Store>,
+    tracker: &State<PresenceTracker>,
+    payload: Json<Req<SetAvatar>>,
+    claims: Claims,
+) -> ApiResult<Res<SetAvatar>> {
+    let avatar = profile::decode_avatar(payload.into_inner()).map_err(|e| invalid_profile(vec![e]))?;
+    let user = profile::save_avatar(store, claims.user_id, avatar.as_ref()).await?;
+    let message = if avatar.is_some() { "Avatar updated" } else { "Avatar removed" };
+    return Ok(Response::success(message, my_profile_of(user, tracker)));
+}
+
+#[api_route(GetUser)]
+pub async fn get_user(_limit: RateLimit, store: &State<Store>, tracker: &State<PresenceTracker>, id: i32, _claims: Claims) -> ApiResult<Res<GetUser>> {
+    let user = profile::get_profile(store, id).await?;
+    return Ok(Response::success("Profile fetched", profile_of(user, tracker)));
+}
+
+/// Avatar URLs change with every upload, so the bytes behind one never do.
+#[derive(Responder)]
+pub struct AvatarImage(Vec<u8>, ContentType, Header<'static>);
+
+/// Public, like any image an `<img>` tag would load. The `v` query parameter of the URL is
+/// only there to bust caches.
+#[get("/<id>/avatar")]
+pub async fn avatar(store: &State<Store>, id: i32) -> Result<Option<AvatarImage>, Status> {
+    let avatar = match store.get_avatar(id).await {
+        Ok(Some(avatar)) => avatar,
+        Ok(None) => return Ok(None),
+        Err(e) => {
+            error!(error = %e, user_id = id, "could not read avatar");
+            return Err(Status::InternalServerError);
+        }
+    };
+    let content_type = ContentType::parse_flexible(&avatar.content_type).unwrap_or(ContentType::Binary);
+    return Ok(Some(AvatarImage(avatar.data, content_type, Header::new("Cache-Control", "public, max-age=31536000, immutable"))));
+}
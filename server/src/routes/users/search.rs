@@ -1,14 +1,24 @@
+use macros::api_route;
 use rocket::State;
-use shared::{Response, routes::users::search::{SearchUser, SearchUserResult}};
-use sqlx::{PgPool};
+use shared::{ApiResult, ErrorCode, FieldError, Response, api::{Req, Res, SearchUsers}, routes::users::search::{SearchUser, SearchUserResult}};
 
-use crate::db::{auth::jwt::Claims, users::search};
+use crate::{db::{auth::jwt::Claims, store::Store, users::search}, presence::PresenceTracker, rate_limit::RateLimit};
 
-#[get("/search?<name>&<page>&<limit>")]
-pub async fn search_users(pool:&State<PgPool>,name:&str,page:i64,limit:i64,_claims: Claims)->Response<SearchUserResult>{
-    let users = search::search_users(pool, name, limit, page).await;
-    match users {
-        Ok(users) => Response::success("Users fetched", SearchUserResult::new(users.into_iter().map(|v|{v.into()}).collect::<Vec<SearchUser>>()) ),
-        Err(err) => Response::internal_error(&err.to_string(), None),
+#[api_route(SearchUsers)]
+pub async fn search_users(_limit: RateLimit, store:&State<Store>,tracker:&State<PresenceTracker>,query:Req<SearchUsers>,claims: Claims)->ApiResult<Res<SearchUsers>>{
+    let mut errors = vec![];
+    if !(1..=100).contains(&query.limit) {
+        errors.push(FieldError::new("limit", "must be between 1 and 100"));
     }
-}
\ No newline at end of file
+    if !errors.is_empty() {
+        return Err(Response::bad_request("Invalid search query", None)
+            .with_code(ErrorCode::ValidationFailed)
+            .with_details(errors));
+    }
+    let (users, next_cursor) = search::search(store, claims.user_id, &query.name, query.limit.into(), query.cursor.as_deref()).await?;
+    return Ok(Response::success("Users fetched", SearchUserResult::new(users.into_iter().map(|v|{
+        let mut user = SearchUser::from(v);
+        user.presence.status = tracker.status(user.id);
+        user
+    }).collect::<Vec<SearchUser>>(), next_cursor)));
+}
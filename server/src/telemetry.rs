@@ -0,0 +1,43 @@
+//! Logging setup and what may be logged.
+//!
+//! Redaction policy: passwords, tokens and user search terms never reach a log line.
+//! - `#[api_route]` handlers and `#[db_func]` queries get spans that skip every argument.
+//! - Request logs carry the path without its query string. Rocket's own per-request lines print
+//!   the full URI, so its `rocket::server` target is always off.
+//! - Request DTOs holding credentials don't implement `Debug`, so they can't be logged by accident.
+//! - Anything sensitive that still has to be held in a `Debug` type, like the admin CLI's
+//!   passwords, goes through [`Redacted`].
+
+use std::fmt;
+
+use tracing_subscriber::EnvFilter;
+
+use crate::config::{LogFormat, LogSettings};
+
+/// Installs the global subscriber. Rocket's own `log` records are forwarded to it.
+pub fn init(settings: &LogSettings) {
+    let filter = EnvFilter::try_from_default_env()
+        .unwrap_or_else(|_| EnvFilter::new(&settings.filter))
+        .add_directive("rocket::server=off".parse().unwrap());
+    let builder = tracing_subscriber::fmt().with_env_filter(filter);
+    match settings.format {
+        LogFormat::Json => builder.json().with_current_span(true).with_span_list(true).init(),
+        LogFormat::Text => builder.init(),
+    }
+}
+
+/// Prints as `[redacted]` however it is formatted.
+#[derive(Clone, PartialEq)]
+pub struct Redacted<T>(pub T);
+
+impl<T> fmt::Debug for Redacted<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return f.write_str("[redacted]");
+    }
+}
+
+impl<T> fmt::Display for Redacted<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return f.write_str("[redacted]");
+    }
+}
@@ -0,0 +1,824 @@
+mod common;
+
+use std::sync::Arc;
+
+use chrono::{Duration, Utc};
+
+use rocket::{
+    figment::{Figment, providers::{Format, Toml}},
+    http::{Header, Status},
+};
+use serde_json::json;
+use shared::{
+    ErrorCode,
+    api::{
+        BlockUser, CreateConversation, DeleteAccount, Endpoint, ExportAccount, GetMe, GetUser, Heartbeat, ListBlocked, ListContacts,
+        ListConversations, Login, Method, MuteConversation, Refresh, RemoveContact, RequestContact, RespondContact, SearchUsers, SetAvatar,
+        Signup, UnblockUser, UpdateMe,
+    },
+    routes::{
+        auth::refresh::{RefreshRequest, RefreshResponse},
+        chat::conversation::{ConversationList, CreateConversationResponse},
+        users::{
+            account::AccountExport,
+            block::BlockedUsers,
+            contacts::{Contact, ContactList, ContactStatus},
+            presence::{Presence, PresenceStatus},
+            profile::{MyProfile, Profile},
+            search::SearchUserResult,
+        },
+    },
+};
+
+use server::{
+    admin::{self, Command},
+    config::{RateLimitSettings, Settings},
+    telemetry::Redacted,
+    db::{
+        auth::{
+            jwt::{Claims, TokenType, encode_claims},
+            keys::JwtKeys,
+        },
+        store::{Store, memory::MemoryStorage},
+    },
+};
+
+use common::{backends, body, client, client_with, client_with_settings, get_authed, login, patch_authed, post, post_authed, settings, signup, user};
+
+#[test]
+fn index_responds() {
+    let client = client();
+    let response = client.get("/").dispatch();
+    assert_eq!(response.status(), Status::Ok);
+    assert_eq!(response.into_string().unwrap(), "Hello World");
+}
+
+#[test]
+fn health_and_version_respond() {
+    let client = client();
+    let response = client.get("/health/live").dispatch();
+    assert_eq!(response.status(), Status::Ok);
+
+    let response = client.get("/health/ready").dispatch();
+    assert_eq!(response.status(), Status::Ok);
+    let readiness = response.into_json::<serde_json::Value>().unwrap();
+    assert_eq!(readiness["ready"], json!(true));
+
+    let response = client.get("/version").dispatch();
+    assert_eq!(response.status(), Status::Ok);
+    let version = response.into_json::<serde_json::Value>().unwrap();
+    assert_eq!(version["version"], json!(env!("CARGO_PKG_VERSION")));
+    assert!(version["git_hash"].is_string());
+}
+
+#[test]
+fn jwks_is_empty_for_hs256() {
+    let client = client();
+    let response = client.get("/.well-known/jwks.json").dispatch();
+    assert_eq!(response.status(), Status::Ok);
+    assert_eq!(response.into_json::<serde_json::Value>().unwrap(), json!({ "keys": [] }));
+}
+
+#[test]
+fn shared_endpoints_are_mounted_where_the_client_calls_them() {
+    fn route<E: Endpoint>() -> (rocket::http::Method, String) {
+        let method = match E::METHOD {
+            Method::Get => rocket::http::Method::Get,
+            Method::Post => rocket::http::Method::Post,
+            Method::Patch => rocket::http::Method::Patch,
+        };
+        return (method, E::path());
+    }
+    let client = client();
+    let mounted = client
+        .rocket()
+        .routes()
+        .map(|r| (r.method, r.uri.path().to_string()))
+        .collect::<Vec<_>>();
+    for expected in [
+        route::<Signup>(),
+        route::<Login>(),
+        route::<Refresh>(),
+        route::<SearchUsers>(),
+        route::<GetMe>(),
+        route::<UpdateMe>(),
+        route::<SetAvatar>(),
+        route::<GetUser>(),
+        route::<ExportAccount>(),
+        route::<DeleteAccount>(),
+        route::<BlockUser>(),
+        route::<UnblockUser>(),
+        route::<ListBlocked>(),
+        route::<ListContacts>(),
+        route::<RequestContact>(),
+        route::<RespondContact>(),
+        route::<RemoveContact>(),
+        route::<CreateConversation>(),
+        route::<ListConversations>(),
+        route::<MuteConversation>(),
+        route::<Heartbeat>(),
+    ] {
+        assert!(mounted.contains(&expected), "{expected:?} is not mounted");
+    }
+}
+
+#[test]
+fn signup_rejects_duplicate_username() {
+    for client in backends() {
+        signup(&client, "alice", "pw");
+        let response = post(&client, "/auth/signup", &json!({"email": "alice", "password": "other"}));
+        assert_eq!(response.status(), Status::BadRequest);
+        assert_eq!(body::<()>(response).code, Some(ErrorCode::UserAlreadyExists));
+    }
+}
+
+#[test]
+fn signup_validates_fields() {
+    let client = client();
+    let response = post(&client, "/auth/signup", &json!({"email": " ", "password": ""}));
+    assert_eq!(response.status(), Status::BadRequest);
+    let body = body::<()>(response);
+    assert_eq!(body.code, Some(ErrorCode::ValidationFailed));
+    let fields = body.details.unwrap().into_iter().map(|d| d.field).collect::<Vec<_>>();
+    assert_eq!(fields, vec!["email", "password"]);
+}
+
+#[test]
+fn login_rejects_wrong_password_and_unknown_user() {
+    for client in backends() {
+        signup(&client, "alice", "pw");
+        for (username, password) in [("alice", "wrong"), ("nobody", "pw")] {
+            let response = post(&client, "/auth/login", &json!({"email": username, "password": password}));
+            assert_eq!(response.status(), Status::Unauthorized);
+            assert_eq!(body::<()>(response).code, Some(ErrorCode::InvalidCredentials));
+        }
+    }
+}
+
+#[test]
+fn refresh_rotates_and_revokes_used_token() {
+    for client in backends() {
+        signup(&client, "alice", "pw");
+        let tokens = login(&client, "alice", "pw");
+
+        let request = RefreshRequest { refresh_token: tokens.refresh_token.clone() };
+        let response = post(&client, "/auth/refresh", &request);
+        assert_eq!(response.status(), Status::Ok);
+        let rotated = body::<RefreshResponse>(response).data.unwrap();
+        assert_ne!(rotated.refresh_token, tokens.refresh_token);
+
+        let response = post(&client, "/auth/refresh", &request);
+        assert_eq!(response.status(), Status::Unauthorized);
+        assert_eq!(body::<()>(response).code, Some(ErrorCode::AuthTokenRevoked));
+    }
+}
+
+#[test]
+fn refresh_rejects_access_token() {
+    for client in backends() {
+        signup(&client, "alice", "pw");
+        let tokens = login(&client, "alice", "pw");
+        let response = post(&client, "/auth/refresh", &RefreshRequest { refresh_token: tokens.access_token });
+        assert_eq!(response.status(), Status::Unauthorized);
+        assert_eq!(body::<()>(response).code, Some(ErrorCode::AuthTokenInvalid));
+    }
+}
+
+#[test]
+fn protected_routes_report_why_auth_failed() {
+    let client = client();
+    let response = client.get("/users/search?name=a&limit=10").dispatch();
+    assert_eq!(response.status(), Status::Unauthorized);
+    assert_eq!(body::<()>(response).code, Some(ErrorCode::AuthTokenMissing));
+
+    let response = get_authed(&client, "/users/search?name=a&limit=10", "not-a-jwt");
+    assert_eq!(response.status(), Status::Unauthorized);
+    assert_eq!(body::<()>(response).code, Some(ErrorCode::AuthTokenMalformed));
+}
+
+#[test]
+fn forged_claims_map_to_stable_codes() {
+    let client = client();
+    let (id, _) = user(&client, "alice");
+    type Edit = fn(&mut Claims);
+    let forge = |edit: Edit| {
+        let mut claims = Claims::new_v2(id, TokenType::Access, Utc::now() + Duration::hours(1));
+        edit(&mut claims);
+        return encode_claims(&claims, &JwtKeys::get().access);
+    };
+
+    let response = get_authed(&client, "/users/me", &forge(|_| {}));
+    assert_eq!(response.status(), Status::Ok);
+
+    let cases: [(Edit, ErrorCode); 3] = [
+        (|c| c.version = 99, ErrorCode::AuthTokenUnsupportedVersion),
+        (|c| c.iss = "someone-else".into(), ErrorCode::AuthTokenInvalid),
+        (|c| c.aud = "someone-else-api".into(), ErrorCode::AuthTokenInvalid),
+    ];
+    for (edit, code) in cases {
+        let response = get_authed(&client, "/users/me", &forge(edit));
+        assert_eq!(response.status(), Status::Unauthorized);
+        assert_eq!(body::<()>(response).code, Some(code));
+    }
+}
+
+#[test]
+fn search_pages_through_matching_users() {
+    for client in backends() {
+        let (_, token) = user(&client, "bobsearcher");
+        for name in ["bob1", "Bob2", "abob", "bob3", "carol"] {
+            signup(&client, name, "pw");
+        }
+        let search = |query: &str| {
+            let response = get_authed(&client, &format!("/users/search?{query}"), &token);
+            assert_eq!(response.status(), Status::Ok);
+            let result = body::<SearchUserResult>(response).data.unwrap();
+            return (result.result.into_iter().map(|u| u.username).collect::<Vec<_>>(), result.next_cursor);
+        };
+
+        // Case doesn't matter, prefix matches come first, and the caller is left out.
+        let (first, cursor) = search("name=BOB&limit=2");
+        assert_eq!(first, vec!["Bob2", "bob1"]);
+        let (second, cursor) = search(&format!("name=BOB&limit=2&cursor={}", cursor.unwrap()));
+        assert_eq!(second, vec!["bob3", "abob"]);
+        assert_eq!(cursor, None);
+    }
+}
+
+#[test]
+fn search_treats_wildcards_literally() {
+    for client in backends() {
+        let (_, token) = user(&client, "searcher");
+        for name in ["a_b", "axb", "100%"] {
+            signup(&client, name, "pw");
+        }
+        let search = |name: &str| {
+            let response = get_authed(&client, &format!("/users/search?name={name}&limit=10"), &token);
+            return body::<SearchUserResult>(response).data.unwrap().result.into_iter().map(|u| u.username).collect::<Vec<_>>();
+        };
+        assert_eq!(search("a_b"), vec!["a_b"]);
+        assert_eq!(search("%25"), vec!["100%"]);
+    }
+}
+
+#[test]
+fn search_validates_paging() {
+    let client = client();
+    let (_, token) = user(&client, "searcher");
+    let response = get_authed(&client, "/users/search?name=a&limit=101", &token);
+    assert_eq!(response.status(), Status::BadRequest);
+    let body = body::<()>(response);
+    assert_eq!(body.code, Some(ErrorCode::ValidationFailed));
+    assert_eq!(body.details.unwrap().len(), 1);
+}
+
+#[test]
+fn conversation_is_reused_for_same_members() {
+    for client in backends() {
+        let (alice, token) = user(&client, "alice");
+        let bob = signup(&client, "bob", "pw");
+        let carol = signup(&client, "carol", "pw");
+
+        let create = |participants: Vec<i32>| {
+            let response = post_authed(&client, "/chat/conversation/create", &token, &json!({"participant_ids": participants}));
+            assert_eq!(response.status(), Status::Ok);
+            return body::<CreateConversationResponse>(response).data.unwrap();
+        };
+        let first = create(vec![bob]);
+        let mut members = first.members.iter().map(|m| m.user_id).collect::<Vec<_>>();
+        members.sort();
+        assert_eq!(members, vec![alice, bob]);
+
+        assert_eq!(create(vec![bob]).conversation_id, first.conversation_id);
+        assert_ne!(create(vec![bob, carol]).conversation_id, first.conversation_id);
+    }
+}
+
+#[test]
+fn conversation_rejects_unknown_or_missing_members() {
+    let client = client();
+    let (_, token) = user(&client, "alice");
+
+    let response = post_authed(&client, "/chat/conversation/create", &token, &json!({"participant_ids": [999]}));
+    assert_eq!(response.status(), Status::BadRequest);
+    assert_eq!(body::<()>(response).code, Some(ErrorCode::ConversationInvalidMembers));
+
+    let response = post_authed(&client, "/chat/conversation/create", &token, &json!({"participant_ids": []}));
+    assert_eq!(response.status(), Status::BadRequest);
+    assert_eq!(body::<()>(response).code, Some(ErrorCode::ValidationFailed));
+}
+
+#[test]
+fn conversation_accepts_first_message() {
+    for client in backends() {
+        let (_, token) = user(&client, "alice");
+        let bob = signup(&client, "bob", "pw");
+        let response = post_authed(
+            &client,
+            "/chat/conversation/create",
+            &token,
+            &json!({"participant_ids": [bob], "first_message": "hello"}),
+        );
+        assert_eq!(response.status(), Status::Ok);
+    }
+}
+
+#[test]
+fn account_export_contains_own_data_only() {
+    for client in backends() {
+        let (alice, token) = user(&client, "alice");
+        let (bob, bob_token) = user(&client, "bob");
+        post_authed(&client, "/chat/conversation/create", &token, &json!({"participant_ids": [bob], "first_message": "hi bob"}));
+        post_authed(&client, "/chat/conversation/create", &bob_token, &json!({"participant_ids": [alice], "first_message": "hi alice"}));
+
+        let response = get_authed(&client, "/users/me/export", &token);
+        assert_eq!(response.status(), Status::Ok);
+        let export = body::<AccountExport>(response).data.unwrap();
+        assert_eq!((export.profile.id, export.profile.username.as_str()), (alice, "alice"));
+        assert_eq!(export.conversations.len(), 1);
+        assert_eq!(export.messages.iter().map(|m| m.text.as_str()).collect::<Vec<_>>(), vec!["hi bob"]);
+    }
+}
+
+#[test]
+fn account_deletion_needs_the_password() {
+    for client in backends() {
+        let (_, token) = user(&client, "alice");
+        let response = post_authed(&client, "/users/me/delete", &token, &json!({"password": "wrong"}));
+        assert_eq!(response.status(), Status::BadRequest);
+        let response = body::<()>(response);
+        assert_eq!(response.code, Some(ErrorCode::ValidationFailed));
+        assert_eq!(response.details.unwrap()[0].field, "password");
+
+        let response = post_authed(&client, "/users/me/delete", &token, &json!({"password": "password"}));
+        assert_eq!(response.status(), Status::Ok);
+        let response = post(&client, "/auth/login", &json!({"email": "alice", "password": "password"}));
+        assert_eq!(body::<()>(response).code, Some(ErrorCode::InvalidCredentials));
+        // The access token outlives the account but finds nothing.
+        let response = get_authed(&client, "/users/me/export", &token);
+        assert_eq!(response.status(), Status::NotFound);
+        // The name is free again.
+        signup(&client, "alice", "new");
+    }
+}
+
+#[test]
+fn profile_fields_are_updated_cleared_and_validated() {
+    let client = client();
+    let (alice, token) = user(&client, "alice");
+    let (_, bob_token) = user(&client, "bob");
+
+    let response = patch_authed(&client, "/users/me", &token, &json!({"display_name": "  Alice A.  ", "bio": "Hi\nthere"}));
+    assert_eq!(response.status(), Status::Ok);
+    let profile = body::<Profile>(response).data.unwrap();
+    assert_eq!(profile.display_name.as_deref(), Some("Alice A."));
+    assert_eq!(profile.bio.as_deref(), Some("Hi\nthere"));
+
+    // Left out keeps the value, empty clears it.
+    let response = patch_authed(&client, "/users/me", &token, &json!({"bio": "", "status_text": "Away"}));
+    let profile = body::<Profile>(response).data.unwrap();
+    assert_eq!((profile.display_name.as_deref(), profile.bio, profile.status_text.as_deref()), (Some("Alice A."), None, Some("Away")));
+
+    let response = patch_authed(&client, "/users/me", &token, &json!({"display_name": "x".repeat(65), "status_text": "a\nb"}));
+    assert_eq!(response.status(), Status::BadRequest);
+    let error = body::<()>(response);
+    assert_eq!(error.code, Some(ErrorCode::ValidationFailed));
+    let fields = error.details.unwrap().into_iter().map(|d| d.field).collect::<Vec<_>>();
+    assert_eq!(fields, vec!["display_name", "status_text"]);
+
+    let response = get_authed(&client, &format!("/users/{alice}"), &bob_token);
+    assert_eq!(response.status(), Status::Ok);
+    assert_eq!(body::<Profile>(response).data.unwrap().display_name.as_deref(), Some("Alice A."));
+    assert_eq!(get_authed(&client, "/users/999", &bob_token).status(), Status::NotFound);
+
+    let response = post_authed(&client, "/chat/conversation/create", &bob_token, &json!({"participant_ids": [alice]}));
+    let members = body::<CreateConversationResponse>(response).data.unwrap().members;
+    let alice_member = members.iter().find(|m| m.user_id == alice).unwrap();
+    assert_eq!(alice_member.name(), "Alice A.");
+}
+
+#[test]
+fn avatars_are_uploaded_served_and_removed() {
+    use base64::{Engine, engine::general_purpose::STANDARD};
+
+    let client = client();
+    let (alice, token) = user(&client, "alice");
+    let png = b"\x89PNG\r\n\x1a\n rest of the image".to_vec();
+
+    let response = post_authed(&client, "/users/me/avatar", &token, &json!({"image": STANDARD.encode(b"not an image")}));
+    assert_eq!(response.status(), Status::BadRequest);
+    let response = post_authed(&client, "/users/me/avatar", &token, &json!({"image": "%%%"}));
+    assert_eq!(response.status(), Status::BadRequest);
+
+    let response = post_authed(&client, "/users/me/avatar", &token, &json!({"image": STANDARD.encode(&png)}));
+    assert_eq!(response.status(), Status::Ok);
+    let url = body::<Profile>(response).data.unwrap().avatar_url.unwrap();
+    assert!(url.starts_with(&format!("/users/{alice}/avatar?v=")));
+
+    // Served without a token, with the sniffed type.
+    let response = client.get(url).dispatch();
+    assert_eq!(response.status(), Status::Ok);
+    assert_eq!(response.content_type(), Some(rocket::http::ContentType::PNG));
+    assert!(response.headers().get_one("Cache-Control").unwrap().contains("max-age"));
+    assert_eq!(response.into_bytes().unwrap(), png);
+
+    let response = post_authed(&client, "/users/me/avatar", &token, &json!({"image": null}));
+    assert_eq!(body::<Profile>(response).data.unwrap().avatar_url, None);
+    assert_eq!(client.get(format!("/users/{alice}/avatar")).dispatch().status(), Status::NotFound);
+}
+
+#[test]
+fn presence_follows_heartbeats_and_hides_last_seen() {
+    let mut settings = settings();
+    settings.presence.away_after_secs = 1;
+    let client = client_with_settings(Arc::new(MemoryStorage::new()), settings);
+    let (alice, alice_token) = user(&client, "alice");
+    let (_, bob_token) = user(&client, "bob");
+    let alice_as_seen_by_bob = || {
+        let response = get_authed(&client, "/chat/conversation/list", &bob_token);
+        let conversations = body::<ConversationList>(response).data.unwrap().conversations;
+        return conversations[0].members.iter().find(|m| m.user_id == alice).unwrap().presence.clone();
+    };
+    post_authed(&client, "/chat/conversation/create", &bob_token, &json!({"participant_ids": [alice]}));
+    assert_eq!(alice_as_seen_by_bob(), Presence::default());
+
+    let response = post_authed(&client, "/presence/heartbeat", &alice_token, &json!({}));
+    assert_eq!(body::<Presence>(response).data.unwrap().status, PresenceStatus::Online);
+    let seen = alice_as_seen_by_bob();
+    assert_eq!(seen.status, PresenceStatus::Online);
+    assert!(seen.last_seen_at.is_some());
+
+    let response = get_authed(&client, "/users/search?name=ali&limit=10", &bob_token);
+    assert_eq!(body::<SearchUserResult>(response).data.unwrap().result[0].presence.status, PresenceStatus::Online);
+
+    patch_authed(&client, "/users/me", &alice_token, &json!({"hide_last_seen": true}));
+    assert_eq!(alice_as_seen_by_bob(), Presence { status: PresenceStatus::Online, last_seen_at: None });
+
+    // Still connected, but idle for longer than `away_after_secs`.
+    std::thread::sleep(std::time::Duration::from_millis(1100));
+    post_authed(&client, "/presence/heartbeat", &alice_token, &json!({"active": false}));
+    assert_eq!(alice_as_seen_by_bob().status, PresenceStatus::Away);
+}
+
+#[test]
+fn an_open_presence_stream_keeps_a_user_online() {
+    let client = client();
+    let (alice, alice_token) = user(&client, "alice");
+    let (_, bob_token) = user(&client, "bob");
+    let status = || {
+        let response = get_authed(&client, &format!("/users/{alice}"), &bob_token);
+        return body::<Profile>(response).data.unwrap().presence.status;
+    };
+
+    let stream = get_authed(&client, "/presence/stream", &alice_token);
+    assert_eq!(stream.status(), Status::Ok);
+    assert_eq!(stream.content_type(), Some(rocket::http::ContentType::EventStream));
+    assert_eq!(status(), PresenceStatus::Online);
+    drop(stream);
+    assert_eq!(status(), PresenceStatus::Offline);
+}
+
+#[test]
+fn blocked_users_cannot_reach_or_find_each_other() {
+    let client = client();
+    let (alice, alice_token) = user(&client, "alice");
+    let (bob, bob_token) = user(&client, "bob");
+    let search = |token: &str, name: &str| {
+        let response = get_authed(&client, &format!("/users/search?name={name}&limit=10"), token);
+        return body::<SearchUserResult>(response).data.unwrap().result.len();
+    };
+
+    let response = post_authed(&client, "/users/block", &alice_token, &json!({"user_id": alice}));
+    assert_eq!(response.status(), Status::BadRequest);
+    let response = post_authed(&client, "/users/block", &alice_token, &json!({"user_id": 999}));
+    assert_eq!(response.status(), Status::NotFound);
+
+    for _ in 0..2 {
+        let response = post_authed(&client, "/users/block", &alice_token, &json!({"user_id": bob}));
+        assert_eq!(response.status(), Status::Ok);
+    }
+    let response = get_authed(&client, "/users/blocked", &alice_token);
+    let blocked = body::<BlockedUsers>(response).data.unwrap().users;
+    assert_eq!(blocked.iter().map(|u| u.id).collect::<Vec<_>>(), vec![bob]);
+    assert_eq!((search(&alice_token, "bob"), search(&bob_token, "alice")), (0, 0));
+
+    // The blocker is told why; the blocked side can't tell alice from a user that doesn't exist.
+    let response = post_authed(&client, "/chat/conversation/create", &alice_token, &json!({"participant_ids": [bob]}));
+    assert_eq!(response.status(), Status::BadRequest);
+    assert_eq!(body::<CreateConversationResponse>(response).code, Some(ErrorCode::UserBlocked));
+    let response = post_authed(
+        &client,
+        "/chat/conversation/create",
+        &bob_token,
+        &json!({"participant_ids": [alice], "first_message": "hi"}),
+    );
+    assert_eq!(body::<CreateConversationResponse>(response).code, Some(ErrorCode::ConversationInvalidMembers));
+
+    post_authed(&client, "/users/unblock", &alice_token, &json!({"user_id": bob}));
+    assert_eq!((search(&alice_token, "bob"), search(&bob_token, "alice")), (1, 1));
+    let response = post_authed(&client, "/chat/conversation/create", &bob_token, &json!({"participant_ids": [alice]}));
+    assert_eq!(response.status(), Status::Ok);
+}
+
+#[test]
+fn contact_requests_are_accepted_declined_and_removed() {
+    for client in backends() {
+        let (alice, alice_token) = user(&client, "alice");
+        let (bob, bob_token) = user(&client, "bob");
+        let (carol, carol_token) = user(&client, "carol");
+        let contacts = |token: &str| {
+            let response = get_authed(&client, "/contacts/list", token);
+            let contacts = body::<ContactList>(response).data.unwrap().contacts;
+            return contacts.into_iter().map(|c| (c.user.id, c.status, c.incoming)).collect::<Vec<_>>();
+        };
+
+        let response = post_authed(&client, "/contacts/request", &alice_token, &json!({"user_id": alice}));
+        assert_eq!(response.status(), Status::BadRequest);
+        let response = post_authed(&client, "/contacts/respond", &alice_token, &json!({"user_id": bob, "accept": true}));
+        assert_eq!(response.status(), Status::NotFound);
+
+        let response = post_authed(&client, "/contacts/request", &alice_token, &json!({"user_id": bob}));
+        let contact = body::<Contact>(response).data.unwrap();
+        assert_eq!((contact.status, contact.incoming), (ContactStatus::Pending, false));
+        assert_eq!(contacts(&bob_token), vec![(alice, ContactStatus::Pending, true)]);
+        // Only the addressee can answer.
+        let response = post_authed(&client, "/contacts/respond", &alice_token, &json!({"user_id": bob, "accept": true}));
+        assert_eq!(response.status(), Status::NotFound);
+        post_authed(&client, "/contacts/respond", &bob_token, &json!({"user_id": alice, "accept": true}));
+        assert_eq!(contacts(&alice_token), vec![(bob, ContactStatus::Accepted, false)]);
+        let response = get_authed(&client, "/users/me/export", &alice_token);
+        assert_eq!(body::<AccountExport>(response).data.unwrap().contacts, vec!["bob"]);
+
+        // A declined request still looks pending to the sender, and asking back accepts it.
+        post_authed(&client, "/contacts/request", &carol_token, &json!({"user_id": alice}));
+        post_authed(&client, "/contacts/respond", &alice_token, &json!({"user_id": carol, "accept": false}));
+        assert_eq!(contacts(&carol_token), vec![(alice, ContactStatus::Pending, false)]);
+        assert_eq!(contacts(&alice_token)[1], (carol, ContactStatus::Declined, true));
+        post_authed(&client, "/contacts/request", &alice_token, &json!({"user_id": carol}));
+        assert_eq!(contacts(&carol_token), vec![(alice, ContactStatus::Accepted, false)]);
+
+        post_authed(&client, "/contacts/remove", &bob_token, &json!({"user_id": alice}));
+        assert_eq!(contacts(&alice_token), vec![(carol, ContactStatus::Accepted, true)]);
+        post_authed(&client, "/users/block", &carol_token, &json!({"user_id": alice}));
+        assert!(contacts(&alice_token).is_empty());
+        let response = post_authed(&client, "/contacts/request", &alice_token, &json!({"user_id": carol}));
+        assert_eq!(response.status(), Status::NotFound);
+    }
+}
+
+#[test]
+fn contacts_only_users_need_an_accepted_contact() {
+    for client in backends() {
+        let (alice, alice_token) = user(&client, "alice");
+        let (bob, bob_token) = user(&client, "bob");
+        let create = |token: &str, participant: i32| {
+            let response = post_authed(&client, "/chat/conversation/create", token, &json!({"participant_ids": [participant]}));
+            return body::<CreateConversationResponse>(response).code;
+        };
+
+        let response = patch_authed(&client, "/users/me", &alice_token, &json!({"contacts_only_dms": true}));
+        assert!(body::<MyProfile>(response).data.unwrap().contacts_only_dms);
+        // Only alice sees her own settings.
+        let response = get_authed(&client, &format!("/users/{alice}"), &bob_token);
+        let profile = body::<serde_json::Value>(response).data.unwrap();
+        assert!(profile.get("contacts_only_dms").is_none() && profile.get("hide_last_seen").is_none());
+        assert_eq!(create(&bob_token, alice), Some(ErrorCode::ContactsOnly));
+        // The setting only limits who can reach alice, and only in direct conversations.
+        assert_eq!(create(&alice_token, bob), None);
+        let (carol, _) = user(&client, "carol");
+        let response = post_authed(&client, "/chat/conversation/create", &bob_token, &json!({"participant_ids": [alice, carol]}));
+        assert_eq!(body::<CreateConversationResponse>(response).code, None);
+
+        post_authed(&client, "/contacts/request", &bob_token, &json!({"user_id": alice}));
+        assert_eq!(create(&bob_token, alice), Some(ErrorCode::ContactsOnly));
+        post_authed(&client, "/contacts/respond", &alice_token, &json!({"user_id": bob, "accept": true}));
+        assert_eq!(create(&bob_token, alice), None);
+    }
+}
+
+#[test]
+fn muting_a_conversation_only_affects_the_caller() {
+    let client = client();
+    let (alice, alice_token) = user(&client, "alice");
+    let (_, bob_token) = user(&client, "bob");
+    let (_, carol_token) = user(&client, "carol");
+    let response = post_authed(&client, "/chat/conversation/create", &bob_token, &json!({"participant_ids": [alice]}));
+    let id: i32 = body::<CreateConversationResponse>(response).data.unwrap().conversation_id.parse().unwrap();
+    let muted = |token: &str| {
+        let response = get_authed(&client, "/chat/conversation/list", token);
+        return body::<ConversationList>(response).data.unwrap().conversations[0].muted;
+    };
+
+    let response = post_authed(&client, "/chat/conversation/mute", &alice_token, &json!({"conversation_id": id, "muted": true}));
+    assert_eq!(response.status(), Status::Ok);
+    assert!(muted(&alice_token));
+    assert!(!muted(&bob_token));
+
+    let response = post_authed(&client, "/chat/conversation/mute", &carol_token, &json!({"conversation_id": id, "muted": true}));
+    assert_eq!(response.status(), Status::NotFound);
+
+    post_authed(&client, "/chat/conversation/mute", &alice_token, &json!({"conversation_id": id, "muted": false}));
+    assert!(!muted(&alice_token));
+}
+
+#[test]
+fn settings_fill_defaults_and_reject_bad_values() {
+    let settings = Settings::from_figment(&Figment::from(Toml::string(r#"database_url = "memory:""#))).unwrap();
+    assert_eq!(settings, Settings::new("memory:"));
+
+    for (toml, key) in [
+        ("", "database_url"),
+        ("database_url = \"memory:\"\nbcrypt_cost = 2", "bcrypt_cost"),
+        ("database_url = \"memory:\"\n[database]\nmax_connections = 0", "database.max_connections"),
+        ("database_url = \"memory:\"\n[tokens]\nrefresh_ttl_secs = 60", "tokens.refresh_ttl_secs"),
+        ("database_url = \"memory:\"\n[cors]\nallowed_origins = [\"example.com\"]", "cors.allowed_origins"),
+        ("database_url = \"memory:\"\n[logging]\nfilter = \"server=loud\"", "logging.filter"),
+        ("database_url = \"memory:\"\n[presence]\naway_after_secs = 0", "presence.away_after_secs"),
+    ] {
+        let error = Settings::from_figment(&Figment::from(Toml::string(toml))).unwrap_err();
+        assert!(error.contains(key), "{key}: {error}");
+    }
+}
+
+#[test]
+fn cors_headers_only_for_allowed_origins() {
+    let mut settings = settings();
+    settings.cors.allowed_origins = vec!["https://chat.example.com".into()];
+    let client = client_with_settings(Arc::new(MemoryStorage::new()), settings);
+
+    let response = client.get("/").header(Header::new("Origin", "https://chat.example.com")).dispatch();
+    assert_eq!(response.headers().get_one("Access-Control-Allow-Origin"), Some("https://chat.example.com"));
+
+    let response = client.get("/").header(Header::new("Origin", "https://evil.example.com")).dispatch();
+    assert_eq!(response.headers().get_one("Access-Control-Allow-Origin"), None);
+
+    let response = client
+        .options("/auth/login")
+        .header(Header::new("Origin", "https://chat.example.com"))
+        .header(Header::new("Access-Control-Request-Method", "POST"))
+        .dispatch();
+    assert_eq!(response.status(), Status::NoContent);
+    assert!(response.headers().get_one("Access-Control-Allow-Headers").unwrap().contains("Authorization"));
+}
+
+#[test]
+fn auth_routes_are_rate_limited() {
+    let mut settings = settings();
+    settings.rate_limits = RateLimitSettings { auth_per_minute: 2, api_per_minute: 0 };
+    let client = client_with_settings(Arc::new(MemoryStorage::new()), settings);
+    signup(&client, "alice", "pw");
+    login(&client, "alice", "pw");
+
+    let response = post(&client, "/auth/login", &json!({"email": "alice", "password": "pw"}));
+    assert_eq!(response.status(), Status::TooManyRequests);
+    assert!(response.headers().get_one("Retry-After").is_some());
+    assert_eq!(body::<()>(response).code, Some(ErrorCode::RateLimited));
+}
+
+#[test]
+fn metrics_count_requests_and_logins() {
+    let client = client();
+    signup(&client, "alice", "pw");
+    login(&client, "alice", "pw");
+    let response = post(&client, "/auth/login", &json!({"email": "alice", "password": "wrong"}));
+    assert_eq!(response.status(), Status::Unauthorized);
+    client.get("/no/such/route").dispatch();
+
+    let response = client.get("/metrics").dispatch();
+    assert_eq!(response.status(), Status::Ok);
+    let text = response.into_string().unwrap();
+    assert!(text.contains(r#"auth_logins_total{result="success"} 1"#), "{text}");
+    assert!(text.contains(r#"auth_logins_total{result="failure"} 1"#), "{text}");
+    assert!(text.contains(r#"http_requests_total{method="POST",route="/auth/login",status="200"} 1"#), "{text}");
+    assert!(text.contains(r#"http_requests_total{method="GET",route="unmatched",status="404"} 1"#), "{text}");
+}
+
+#[test]
+fn metrics_require_the_configured_token() {
+    let mut settings = settings();
+    settings.metrics.bearer_token = Some("scrape-secret".into());
+    let client = client_with_settings(Arc::new(MemoryStorage::new()), settings);
+
+    let response = client.get("/metrics").dispatch();
+    assert_eq!(response.status(), Status::Unauthorized);
+    assert_eq!(body::<()>(response).code, Some(ErrorCode::AuthTokenMissing));
+
+    let response = get_authed(&client, "/metrics", "wrong-secret");
+    assert_eq!(response.status(), Status::Unauthorized);
+    assert_eq!(body::<()>(response).code, Some(ErrorCode::AuthTokenInvalid));
+
+    let response = get_authed(&client, "/metrics", "scrape-secret");
+    assert_eq!(response.status(), Status::Ok);
+}
+
+#[test]
+fn request_ids_are_echoed_or_generated() {
+    let client = client();
+    let response = client.get("/health/live").header(Header::new("X-Request-Id", "deploy-42.a")).dispatch();
+    assert_eq!(response.headers().get_one("X-Request-Id"), Some("deploy-42.a"));
+
+    let response = client.get("/health/live").header(Header::new("X-Request-Id", "no spaces <allowed>")).dispatch();
+    let generated = response.headers().get_one("X-Request-Id").unwrap();
+    assert_eq!(generated.len(), 36, "{generated}");
+
+    let response = client.get("/no/such/route").dispatch();
+    assert!(response.headers().get_one("X-Request-Id").is_some());
+}
+
+
+/// Runs an admin command the way the `admin` binary does.
+fn admin(store: &Store, args: &[&str]) -> Result<String, String> {
+    let args = args.iter().map(|arg| arg.to_string()).collect::<Vec<_>>();
+    let command = Command::parse(&args)?;
+    return rocket::execute(admin::run(store, &settings(), command));
+}
+
+#[test]
+fn admin_commands_are_parsed_strictly() {
+    let parse = |args: &[&str]| Command::parse(&args.iter().map(|arg| arg.to_string()).collect::<Vec<_>>());
+    assert_eq!(
+        parse(&["user", "create", "alice", "--password", "pw"]),
+        Ok(Command::CreateUser { username: "alice".into(), password: Some(Redacted("pw".into())) })
+    );
+    let command = parse(&["user", "reset-password", "alice", "--password", "hunter2"]).unwrap();
+    assert!(!format!("{command:?}").contains("hunter2"));
+    assert_eq!(parse(&["tokens", "cleanup"]), Ok(Command::CleanupTokens));
+    assert_eq!(parse(&["migrate", "down", "--to", "20260109164437", "--yes"]), Ok(Command::MigrateDown { target: Some(20260109164437) }));
+    assert!(parse(&["user", "delete", "alice"]).is_err());
+    assert!(parse(&["migrate", "down"]).is_err());
+    assert!(parse(&["user", "disable", "alice", "--pasword", "pw"]).is_err());
+    assert!(parse(&["user", "create", "alice", "--password"]).is_err());
+    assert!(parse(&["users", "list"]).is_err());
+}
+
+#[test]
+fn disabled_users_cannot_log_in_or_refresh() {
+    let store: Store = Arc::new(MemoryStorage::new());
+    let client = client_with(store.clone());
+    signup(&client, "alice", "pw");
+    let tokens = login(&client, "alice", "pw");
+
+    admin(&store, &["user", "disable", "alice"]).unwrap();
+    let response = post(&client, "/auth/login", &json!({"email": "alice", "password": "pw"}));
+    assert_eq!(response.status(), Status::Unauthorized);
+    assert_eq!(body::<()>(response).code, Some(ErrorCode::AccountDisabled));
+    // A wrong password still looks like any other failed login.
+    let response = post(&client, "/auth/login", &json!({"email": "alice", "password": "wrong"}));
+    assert_eq!(body::<()>(response).code, Some(ErrorCode::InvalidCredentials));
+    let response = post(&client, "/auth/refresh", &RefreshRequest { refresh_token: tokens.refresh_token });
+    assert_eq!(body::<()>(response).code, Some(ErrorCode::AuthTokenRevoked));
+
+    admin(&store, &["user", "enable", "alice"]).unwrap();
+    login(&client, "alice", "pw");
+    assert!(admin(&store, &["user", "disable", "nobody"]).is_err());
+}
+
+#[test]
+fn admin_resets_passwords_and_revokes_tokens() {
+    let store: Store = Arc::new(MemoryStorage::new());
+    let client = client_with(store.clone());
+    signup(&client, "alice", "old");
+    let first = login(&client, "alice", "old");
+    let second = login(&client, "alice", "old");
+
+    let output = admin(&store, &["user", "revoke-tokens", "alice"]).unwrap();
+    assert_eq!(output, "Revoked 2 token families of alice");
+    for tokens in [first, second] {
+        let response = post(&client, "/auth/refresh", &RefreshRequest { refresh_token: tokens.refresh_token });
+        assert_eq!(body::<()>(response).code, Some(ErrorCode::AuthTokenRevoked));
+    }
+
+    let tokens = login(&client, "alice", "old");
+    admin(&store, &["user", "reset-password", "alice", "--password", "new"]).unwrap();
+    let response = post(&client, "/auth/refresh", &RefreshRequest { refresh_token: tokens.refresh_token });
+    assert_eq!(body::<()>(response).code, Some(ErrorCode::AuthTokenRevoked));
+    let response = post(&client, "/auth/login", &json!({"email": "alice", "password": "old"}));
+    assert_eq!(response.status(), Status::Unauthorized);
+    login(&client, "alice", "new");
+
+    let output = admin(&store, &["user", "create", "bob"]).unwrap();
+    let password = output.rsplit(' ').next().unwrap();
+    login(&client, "bob", password);
+    assert_eq!(admin(&store, &["user", "create", "bob"]), Err("a user named bob already exists".into()));
+}
+
+#[test]
+fn admin_seeds_lists_and_deletes() {
+    let store: Store = Arc::new(MemoryStorage::new());
+    let client = client_with(store.clone());
+    admin(&store, &["seed"]).unwrap();
+    assert_eq!(admin(&store, &["seed"]).unwrap(), "No new users, 0 new conversations");
+    login(&client, "alice", "demo-password");
+
+    let listed = admin(&store, &["conversations", "list"]).unwrap();
+    assert_eq!(listed.lines().count(), 3, "{listed}");
+    assert!(listed.contains("\"Weekend plans\": alice (1), bob (2), carol (3)"), "{listed}");
+    let listed = admin(&store, &["conversations", "list", "--user", "dave"]).unwrap();
+    assert_eq!(listed, "#3 untitled: bob (2), dave (4)");
+
+    admin(&store, &["user", "delete", "dave", "--yes"]).unwrap();
+    assert_eq!(admin(&store, &["conversations", "list", "--user", "dave"]), Err("no user named dave".into()));
+    let response = post(&client, "/auth/login", &json!({"email": "dave", "password": "demo-password"}));
+    assert_eq!(response.status(), Status::Unauthorized);
+    assert!(admin(&store, &["migrate", "down", "--yes"]).is_err());
+}
@@ -0,0 +1,7 @@
+//! `#[api_route]` must reject handlers whose signature doesn't match their `shared::api` endpoint.
+
+#[test]
+fn mismatched_handlers_fail_to_compile() {
+    let cases = trybuild::TestCases::new();
+    cases.compile_fail("tests/api_route/*.rs");
+}
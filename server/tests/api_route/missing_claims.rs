@@ -0,0 +1,14 @@
+use macros::api_route;
+use shared::{ApiResult, api::{GetMe, Res}};
+
+// `#[api_route]` refers to this through `crate::`, as it does inside the server.
+mod request_id {
+    pub use server::request_id::RequestId;
+}
+
+#[api_route(GetMe)]
+async fn get_me() -> ApiResult<Res<GetMe>> {
+    unimplemented!()
+}
+
+fn main() {}
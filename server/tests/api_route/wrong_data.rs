@@ -0,0 +1,15 @@
+use macros::api_route;
+use rocket::serde::json::Json;
+use shared::{ApiResult, api::{Login, Res}, routes::users::profile::UpdateProfileRequest};
+
+// `#[api_route]` refers to these through `crate::`, as it does inside the server.
+mod request_id {
+    pub use server::request_id::RequestId;
+}
+
+#[api_route(Login, data = "<payload>")]
+async fn login(payload: Json<UpdateProfileRequest>) -> ApiResult<Res<Login>> {
+    unimplemented!("{:?}", payload.display_name)
+}
+
+fn main() {}
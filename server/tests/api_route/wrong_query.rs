@@ -0,0 +1,22 @@
+use db::auth::jwt::Claims;
+use macros::api_route;
+use shared::{ApiResult, api::{Res, SearchUsers}};
+
+// `#[api_route]` refers to these through `crate::`, as it does inside the server.
+mod request_id {
+    pub use server::request_id::RequestId;
+}
+mod db {
+    pub mod auth {
+        pub mod jwt {
+            pub use server::db::auth::jwt::Claims;
+        }
+    }
+}
+
+#[api_route(SearchUsers)]
+async fn search_users(query: String, _claims: Claims) -> ApiResult<Res<SearchUsers>> {
+    unimplemented!("{query}")
+}
+
+fn main() {}
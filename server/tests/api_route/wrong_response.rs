@@ -0,0 +1,22 @@
+use db::auth::jwt::Claims;
+use macros::api_route;
+use shared::{ApiResult, api::GetMe, routes::users::presence::Presence};
+
+// `#[api_route]` refers to these through `crate::`, as it does inside the server.
+mod request_id {
+    pub use server::request_id::RequestId;
+}
+mod db {
+    pub mod auth {
+        pub mod jwt {
+            pub use server::db::auth::jwt::Claims;
+        }
+    }
+}
+
+#[api_route(GetMe)]
+async fn get_me(_claims: Claims) -> ApiResult<Presence> {
+    unimplemented!()
+}
+
+fn main() {}
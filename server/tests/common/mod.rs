@@ -0,0 +1,131 @@
+#![allow(dead_code)]
+
+use std::sync::{Arc, OnceLock};
+
+use rocket::{
+    http::{ContentType, Header, Status},
+    local::blocking::{Client, LocalResponse},
+};
+use serde::{Serialize, de::DeserializeOwned};
+use shared::{
+    ResponseStruct,
+    routes::auth::{login::LoginResponse, signup::SignupRequest},
+};
+
+use server::{
+    config::{RateLimitSettings, Settings},
+    db::{
+        auth::keys::{JwtKeys, KeySet},
+        store::{Storage, Store, memory::MemoryStorage, sqlite::SqliteStorage},
+    },
+};
+
+/// Installs fixed HS256 keys once per test binary.
+pub fn init_keys() {
+    JwtKeys::init_with(|| JwtKeys {
+        access: KeySet::hs256("access-test", b"access test secret"),
+        refresh: KeySet::hs256("refresh-test", b"refresh test secret"),
+    });
+}
+
+/// Defaults, except for the cheapest bcrypt cost and no rate limits.
+pub fn settings() -> Settings {
+    let mut settings = Settings::new("memory:");
+    settings.bcrypt_cost = 4;
+    settings.rate_limits = RateLimitSettings { auth_per_minute: 0, api_per_minute: 0 };
+    return settings;
+}
+
+pub fn client_with_settings(store: Store, settings: Settings) -> Client {
+    init_keys();
+    return Client::tracked(server::rocket(store, settings)).expect("valid rocket instance");
+}
+
+/// Every route mounted on `store`, served in-process.
+pub fn client_with(store: Store) -> Client {
+    return client_with_settings(store, settings());
+}
+
+/// A fresh in-memory backend, so each test starts from an empty database.
+pub fn client() -> Client {
+    return client_with(Arc::new(MemoryStorage::new()));
+}
+
+/// Owns the tasks of every SQLite pool. An in-memory database is gone once its connection
+/// closes, which would happen if the runtime that opened it shut down.
+static SQLITE_RUNTIME: OnceLock<rocket::tokio::runtime::Runtime> = OnceLock::new();
+
+/// A fresh `sqlite::memory:` database with `migrations_sqlite` applied, the same way `client`
+/// runs without a database server.
+pub fn sqlite_client() -> Client {
+    let runtime = SQLITE_RUNTIME.get_or_init(|| rocket::tokio::runtime::Runtime::new().unwrap());
+    let store = runtime.block_on(async {
+        let store = SqliteStorage::connect("sqlite::memory:", &Settings::new("sqlite::memory:").database).await;
+        store.migrate_up().await.expect("sqlite migrations apply");
+        store
+    });
+    return client_with(Arc::new(store));
+}
+
+/// One client per backend that needs no database server, for tests that must pass on each.
+pub fn backends() -> [Client; 2] {
+    return [client(), sqlite_client()];
+}
+
+pub fn body<T: DeserializeOwned + Send + 'static>(response: LocalResponse) -> ResponseStruct<Option<T>> {
+    return response.into_json().expect("response is a ResponseStruct");
+}
+
+pub fn post<'c, B: Serialize>(client: &'c Client, uri: &str, payload: &B) -> LocalResponse<'c> {
+    return client.post(uri.to_string()).header(ContentType::JSON).json(payload).dispatch();
+}
+
+pub fn post_authed<'c, B: Serialize>(client: &'c Client, uri: &str, token: &str, payload: &B) -> LocalResponse<'c> {
+    return client
+        .post(uri.to_string())
+        .header(ContentType::JSON)
+        .header(bearer(token))
+        .json(payload)
+        .dispatch();
+}
+
+pub fn patch_authed<'c, B: Serialize>(client: &'c Client, uri: &str, token: &str, payload: &B) -> LocalResponse<'c> {
+    return client
+        .patch(uri.to_string())
+        .header(ContentType::JSON)
+        .header(bearer(token))
+        .json(payload)
+        .dispatch();
+}
+
+pub fn get_authed<'c>(client: &'c Client, uri: &str, token: &str) -> LocalResponse<'c> {
+    return client.get(uri.to_string()).header(bearer(token)).dispatch();
+}
+
+pub fn bearer(token: &str) -> Header<'static> {
+    return Header::new("Authorization", format!("Bearer {token}"));
+}
+
+fn credentials(username: &str, password: &str) -> SignupRequest {
+    return SignupRequest { email: username.into(), password: password.into() };
+}
+
+/// Creates the account and returns its id.
+pub fn signup(client: &Client, username: &str, password: &str) -> i32 {
+    let response = post(client, "/auth/signup", &credentials(username, password));
+    assert_eq!(response.status(), Status::Ok);
+    return body::<shared::db::signup::IdOnly>(response).data.unwrap().id;
+}
+
+pub fn login(client: &Client, username: &str, password: &str) -> LoginResponse {
+    let response = post(client, "/auth/login", &credentials(username, password));
+    assert_eq!(response.status(), Status::Ok);
+    return body::<LoginResponse>(response).data.unwrap();
+}
+
+/// Signs up and logs in, returning the user id and an access token.
+pub fn user(client: &Client, username: &str) -> (i32, String) {
+    let id = signup(client, username, "password");
+    let tokens = login(client, username, "password");
+    return (id, tokens.access_token);
+}
@@ -0,0 +1,92 @@
+use chrono::{Duration, Utc};
+use jsonwebtoken::{Algorithm, decode_header};
+
+use server::db::auth::{
+    jwt::{Claims, JWTError, TokenType, decode_claims, encode_claims},
+    keys::KeySet,
+};
+
+fn fixture(name: &str) -> String {
+    return format!("{}/tests/fixtures/keys/{name}", env!("CARGO_MANIFEST_DIR"));
+}
+
+/// Signs as `kid` with `<prefix>_<kid>.pem` and verifies with every listed `<prefix>_<kid>.pub.pem`.
+fn key_set(algorithm: Algorithm, prefix: &str, kid: &str, verify_kids: &[&str]) -> KeySet {
+    let paths: Vec<(&str, String)> = verify_kids
+        .iter()
+        .map(|verify_kid| (*verify_kid, fixture(&format!("{prefix}_{verify_kid}.pub.pem"))))
+        .collect();
+    let verify_keys: Vec<(&str, &str)> = paths.iter().map(|(kid, path)| (*kid, path.as_str())).collect();
+    return KeySet::from_pem_files(algorithm, kid, &fixture(&format!("{prefix}_{kid}.pem")), &verify_keys)
+        .expect("valid fixture keys");
+}
+
+fn access_token(keys: &KeySet) -> String {
+    let claims = Claims::new_v2(1, TokenType::Access, Utc::now() + Duration::hours(1));
+    return encode_claims(&claims, keys);
+}
+
+const ASYMMETRIC: [(Algorithm, &str); 2] = [(Algorithm::RS256, "rsa"), (Algorithm::EdDSA, "ed25519")];
+
+#[test]
+fn tokens_signed_with_a_retired_kid_verify_after_rotation() {
+    for (algorithm, prefix) in ASYMMETRIC {
+        let before = key_set(algorithm, prefix, "old", &["old"]);
+        let old_token = access_token(&before);
+        assert_eq!(decode_header(&old_token).unwrap().kid.as_deref(), Some("old"));
+
+        let after = key_set(algorithm, prefix, "new", &["new", "old"]);
+        let new_token = access_token(&after);
+        assert_eq!(decode_header(&new_token).unwrap().kid.as_deref(), Some("new"));
+
+        assert_eq!(decode_claims(&old_token, &after, TokenType::Access).unwrap().user_id, 1);
+        assert_eq!(decode_claims(&new_token, &after, TokenType::Access).unwrap().user_id, 1);
+        assert_eq!(decode_claims(&new_token, &before, TokenType::Access).err(), Some(JWTError::Other));
+    }
+}
+
+#[test]
+fn tokens_with_an_unknown_kid_are_rejected() {
+    for (algorithm, prefix) in ASYMMETRIC {
+        let old_token = access_token(&key_set(algorithm, prefix, "old", &["old"]));
+        let dropped = key_set(algorithm, prefix, "new", &["new"]);
+        assert_eq!(decode_claims(&old_token, &dropped, TokenType::Access).err(), Some(JWTError::Other));
+    }
+
+    let token = access_token(&KeySet::hs256("retired", b"secret"));
+    let current = KeySet::hs256("current", b"secret");
+    assert_eq!(decode_claims(&token, &current, TokenType::Access).err(), Some(JWTError::Other));
+}
+
+#[test]
+fn jwks_publishes_only_public_keys() {
+    for (algorithm, prefix) in ASYMMETRIC {
+        let keys = key_set(algorithm, prefix, "new", &["new", "old"]);
+        let jwks = serde_json::to_value(keys.public_jwks()).unwrap();
+        let jwks = jwks.as_array().unwrap();
+        assert_eq!(jwks.len(), 2);
+        for (jwk, kid) in jwks.iter().zip(["new", "old"]) {
+            assert_eq!(jwk["kid"], kid);
+            assert_eq!(jwk["use"], "sig");
+            let mut fields: Vec<&str> = jwk.as_object().unwrap().keys().map(String::as_str).collect();
+            fields.sort();
+            let expected = match algorithm {
+                Algorithm::RS256 => vec!["alg", "e", "kid", "kty", "n", "use"],
+                _ => vec!["alg", "crv", "kid", "kty", "use", "x"],
+            };
+            assert_eq!(fields, expected, "{prefix} JWK must not carry private parameters");
+        }
+    }
+
+    assert!(KeySet::hs256("hs", b"secret").public_jwks().is_empty());
+}
+
+#[test]
+fn eddsa_verify_keys_must_be_ed25519() {
+    let signing_key = fixture("ed25519_new.pem");
+    for public_key in ["x25519.pub.pem", "rsa_new.pub.pem"] {
+        let path = fixture(public_key);
+        let keys = KeySet::from_pem_files(Algorithm::EdDSA, "new", &signing_key, &[("new", &path)]);
+        assert!(keys.is_err(), "{public_key} accepted as an Ed25519 key");
+    }
+}
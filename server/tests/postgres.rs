@@ -0,0 +1,520 @@
+//! Runs against a throwaway database per test. `#[sqlx::test]` creates it on the Postgres in
+//! `DATABASE_URL` (read from `.env` too) and applies `migrations/` first.
+
+mod common;
+
+use std::sync::{Arc, atomic::Ordering};
+
+use base64::{Engine, engine::general_purpose::STANDARD};
+use rocket::{
+    http::{ContentType, Status},
+    local::asynchronous::{Client, LocalResponse},
+};
+use serde::de::DeserializeOwned;
+use serde_json::{Value, json};
+use shared::{
+    ErrorCode, ResponseStruct,
+    db::signup::IdOnly,
+    routes::{
+        auth::{login::LoginResponse, refresh::RefreshResponse},
+        chat::conversation::CreateConversationResponse,
+        users::{
+            account::AccountExport,
+            block::BlockedUsers,
+            contacts::{ContactList, ContactStatus},
+            presence::{Presence, PresenceStatus},
+            profile::{MyProfile, Profile},
+            search::SearchUserResult,
+        },
+    },
+};
+use sqlx::PgPool;
+
+use server::{
+    admin::{self, Command},
+    jobs::token_cleanup::TOKEN_CLEANUP_METRICS,
+    db::{
+        chat::conversation::{ConversationSummary, CreateConversationError},
+        store::{Storage, Store, postgres::PostgresStorage},
+        users::{block::Block, contacts::ContactChange, profile::ProfileChanges},
+    },
+};
+
+async fn client(pool: PgPool) -> Client {
+    common::init_keys();
+    let store = Arc::new(PostgresStorage::new(pool));
+    return Client::tracked(server::rocket(store, common::settings())).await.expect("valid rocket instance");
+}
+
+async fn body<T: DeserializeOwned + Send + 'static>(response: LocalResponse<'_>) -> ResponseStruct<Option<T>> {
+    return response.into_json().await.expect("response is a ResponseStruct");
+}
+
+async fn post<'c>(client: &'c Client, uri: &str, token: Option<&str>, payload: Value) -> LocalResponse<'c> {
+    let mut request = client.post(uri.to_string()).header(ContentType::JSON).json(&payload);
+    if let Some(token) = token {
+        request = request.header(common::bearer(token));
+    }
+    return request.dispatch().await;
+}
+
+async fn signup(client: &Client, username: &str) -> i32 {
+    let response = post(client, "/auth/signup", None, json!({"email": username, "password": "pw"})).await;
+    assert_eq!(response.status(), Status::Ok);
+    return body::<IdOnly>(response).await.data.unwrap().id;
+}
+
+async fn login(client: &Client, username: &str) -> LoginResponse {
+    let response = post(client, "/auth/login", None, json!({"email": username, "password": "pw"})).await;
+    assert_eq!(response.status(), Status::Ok);
+    return body::<LoginResponse>(response).await.data.unwrap();
+}
+
+async fn count(pool: &PgPool, table: &str) -> i64 {
+    return sqlx::query_scalar(&format!("SELECT COUNT(*) FROM {table}"))
+        .fetch_one(pool)
+        .await
+        .unwrap();
+}
+
+#[sqlx::test]
+async fn signup_then_login(pool: PgPool) {
+    let client = client(pool.clone()).await;
+    signup(&client, "alice").await;
+
+    let response = post(&client, "/auth/signup", None, json!({"email": "alice", "password": "pw"})).await;
+    assert_eq!(response.status(), Status::BadRequest);
+    assert_eq!(body::<()>(response).await.code, Some(ErrorCode::UserAlreadyExists));
+    assert_eq!(count(&pool, "users").await, 1);
+
+    let response = post(&client, "/auth/login", None, json!({"email": "alice", "password": "nope"})).await;
+    assert_eq!(response.status(), Status::Unauthorized);
+    assert_eq!(body::<()>(response).await.code, Some(ErrorCode::InvalidCredentials));
+
+    let tokens = login(&client, "alice").await;
+    assert!(!tokens.access_token.is_empty());
+    assert_eq!(count(&pool, "token_family").await, 1);
+}
+
+#[sqlx::test]
+async fn refresh_rotation_revokes_previous_token(pool: PgPool) {
+    let client = client(pool.clone()).await;
+    signup(&client, "alice").await;
+    let tokens = login(&client, "alice").await;
+
+    let response = post(&client, "/auth/refresh", None, json!({"refresh_token": tokens.refresh_token})).await;
+    assert_eq!(response.status(), Status::Ok);
+    let rotated = body::<RefreshResponse>(response).await.data.unwrap();
+
+    let response = post(&client, "/auth/refresh", None, json!({"refresh_token": tokens.refresh_token})).await;
+    assert_eq!(response.status(), Status::Unauthorized);
+    assert_eq!(body::<()>(response).await.code, Some(ErrorCode::AuthTokenRevoked));
+
+    let response = post(&client, "/auth/refresh", None, json!({"refresh_token": rotated.refresh_token})).await;
+    assert_eq!(response.status(), Status::Ok);
+
+    let active: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM token_family_rel WHERE status = 'ACTIVE'")
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert_eq!(active, 1);
+    assert_eq!(count(&pool, "token_family").await, 1);
+}
+
+#[sqlx::test]
+async fn search_users_pages(pool: PgPool) {
+    let client = client(pool).await;
+    for name in ["bob1", "Bob2", "abob", "bob_3", "bobx3", "carol"] {
+        signup(&client, name).await;
+    }
+    let token = login(&client, "carol").await.access_token;
+
+    let mut seen = vec![];
+    let mut cursor: Option<String> = None;
+    loop {
+        let uri = match &cursor {
+            Some(cursor) => format!("/users/search?name=bob&limit=2&cursor={cursor}"),
+            None => "/users/search?name=bob&limit=2".to_string(),
+        };
+        let response = client.get(uri).header(common::bearer(&token)).dispatch().await;
+        assert_eq!(response.status(), Status::Ok);
+        let result = body::<SearchUserResult>(response).await.data.unwrap();
+        assert!(result.result.len() <= 2);
+        seen.extend(result.result.into_iter().map(|u| u.username));
+        cursor = result.next_cursor;
+        if cursor.is_none() {
+            break;
+        }
+    }
+    assert_eq!(seen, vec!["Bob2", "bob1", "bob_3", "bobx3", "abob"]);
+
+    let response = client.get("/users/search?name=b_&limit=10").header(common::bearer(&token)).dispatch().await;
+    let result = body::<SearchUserResult>(response).await.data.unwrap().result;
+    assert_eq!(result.into_iter().map(|u| u.username).collect::<Vec<_>>(), vec!["bob_3"]);
+    let response = client.get("/users/search?name=carol&limit=10").header(common::bearer(&token)).dispatch().await;
+    assert!(body::<SearchUserResult>(response).await.data.unwrap().result.is_empty());
+}
+
+#[sqlx::test]
+async fn conversation_with_same_members_is_reused(pool: PgPool) {
+    let client = client(pool.clone()).await;
+    signup(&client, "alice").await;
+    let bob = signup(&client, "bob").await;
+    let carol = signup(&client, "carol").await;
+    let token = login(&client, "alice").await.access_token;
+
+    let mut ids = vec![];
+    for participants in [vec![bob], vec![bob], vec![bob, carol]] {
+        let response = post(&client, "/chat/conversation/create", Some(&token), json!({"participant_ids": participants})).await;
+        assert_eq!(response.status(), Status::Ok);
+        ids.push(body::<CreateConversationResponse>(response).await.data.unwrap().conversation_id);
+    }
+    assert_eq!(ids[0], ids[1]);
+    assert_ne!(ids[0], ids[2]);
+    assert_eq!(count(&pool, "conversation").await, 2);
+    assert_eq!(count(&pool, "conversation_member").await, 5);
+}
+
+#[sqlx::test]
+async fn conversation_with_unknown_user_writes_nothing(pool: PgPool) {
+    let client = client(pool.clone()).await;
+    signup(&client, "alice").await;
+    let token = login(&client, "alice").await.access_token;
+
+    let response = post(&client, "/chat/conversation/create", Some(&token), json!({"participant_ids": [424242]})).await;
+    assert_eq!(response.status(), Status::BadRequest);
+    assert_eq!(body::<()>(response).await.code, Some(ErrorCode::ConversationInvalidMembers));
+    assert_eq!(count(&pool, "conversation").await, 0);
+    assert_eq!(count(&pool, "conversation_member").await, 0);
+}
+
+#[sqlx::test]
+async fn failed_first_message_rolls_back_conversation(pool: PgPool) {
+    let client = client(pool.clone()).await;
+    let alice = signup(&client, "alice").await;
+    let bob = signup(&client, "bob").await;
+    let outsider = signup(&client, "mallory").await;
+
+    // The conversation rows are inserted before the sender check fails, so this only passes if
+    // the whole transaction is rolled back.
+    let store = PostgresStorage::new(pool.clone());
+    let result = store
+        .create_conversation(None, vec![alice, bob], Some((outsider, "hi".into())))
+        .await;
+    assert!(matches!(result, Err(CreateConversationError::InvalidUsers)));
+    assert_eq!(count(&pool, "conversation").await, 0);
+    assert_eq!(count(&pool, "conversation_member").await, 0);
+    assert_eq!(count(&pool, "message").await, 0);
+
+    let created = store
+        .create_conversation(None, vec![alice, bob], Some((alice, "hi".into())))
+        .await;
+    assert!(created.is_ok());
+    assert_eq!(count(&pool, "message").await, 1);
+}
+
+#[sqlx::test]
+async fn ready_once_every_migration_is_applied(pool: PgPool) {
+    let client = client(pool.clone()).await;
+    let latest = server::db::store::postgres::MIGRATOR.iter().map(|m| m.version).max();
+
+    let response = client.get("/health/ready").dispatch().await;
+    assert_eq!(response.status(), Status::Ok);
+    let readiness: Value = response.into_json().await.unwrap();
+    assert_eq!(readiness["latest_migration"].as_i64(), latest);
+
+    let metrics = client.get("/metrics").dispatch().await.into_string().await.unwrap();
+    assert!(metrics.contains("db_pool_connections "), "{metrics}");
+
+    sqlx::query("DELETE FROM _sqlx_migrations WHERE version = $1")
+        .bind(latest)
+        .execute(&pool)
+        .await
+        .unwrap();
+    let response = client.get("/health/ready").dispatch().await;
+    assert_eq!(response.status(), Status::ServiceUnavailable);
+    let readiness: Value = response.into_json().await.unwrap();
+    assert_eq!(readiness["pending_migrations"], json!(1));
+}
+
+#[sqlx::test]
+async fn admin_disables_and_deletes_users(pool: PgPool) {
+    let client = client(pool.clone()).await;
+    let store: Store = Arc::new(PostgresStorage::new(pool.clone()));
+    let settings = common::settings();
+    let alice = signup(&client, "alice").await;
+    let bob = signup(&client, "bob").await;
+    login(&client, "alice").await;
+    assert!(store.create_conversation(None, vec![alice, bob], Some((bob, "hi".into()))).await.is_ok());
+
+    admin::run(&store, &settings, Command::DisableUser { username: "alice".into() }).await.unwrap();
+    let response = post(&client, "/auth/login", None, json!({"email": "alice", "password": "pw"})).await;
+    assert_eq!(body::<()>(response).await.code, Some(ErrorCode::AccountDisabled));
+    let active: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM token_family_rel WHERE status = 'ACTIVE'")
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert_eq!(active, 0);
+
+    let listed = admin::run(&store, &settings, Command::ListConversations { username: Some("alice".into()) }).await.unwrap();
+    assert!(listed.ends_with("untitled: alice (1), bob (2)"), "{listed}");
+
+    admin::run(&store, &settings, Command::DeleteUser { username: "bob".into() }).await.unwrap();
+    assert_eq!(count(&pool, "users").await, 1);
+    assert_eq!(count(&pool, "conversation_member").await, 1);
+    // Alice keeps the conversation history, without a sender on Bob's message.
+    let senders: Vec<Option<i32>> = sqlx::query_scalar("SELECT sender_member_id FROM message")
+        .fetch_all(&pool)
+        .await
+        .unwrap();
+    assert_eq!(senders, vec![None]);
+}
+
+#[sqlx::test]
+async fn account_export_lists_own_messages(pool: PgPool) {
+    let client = client(pool.clone()).await;
+    signup(&client, "alice").await;
+    let bob = signup(&client, "bob").await;
+    let token = login(&client, "alice").await.access_token;
+    let response = post(&client, "/chat/conversation/create", Some(&token), json!({"participant_ids": [bob], "first_message": "hi bob"})).await;
+    assert_eq!(response.status(), Status::Ok);
+
+    let response = client.get("/users/me/export").header(common::bearer(&token)).dispatch().await;
+    assert_eq!(response.status(), Status::Ok);
+    let export = body::<AccountExport>(response).await.data.unwrap();
+    assert_eq!(export.profile.username, "alice");
+    assert_eq!(export.conversations.len(), 1);
+    assert_eq!(export.conversations[0].members.len(), 2);
+    assert_eq!(export.messages.iter().map(|m| m.text.as_str()).collect::<Vec<_>>(), vec!["hi bob"]);
+
+    let response = post(&client, "/users/me/delete", Some(&token), json!({"password": "pw"})).await;
+    assert_eq!(response.status(), Status::Ok);
+    assert_eq!(count(&pool, "users").await, 1);
+    assert_eq!(count(&pool, "message").await, 1);
+}
+
+#[sqlx::test]
+async fn profiles_and_avatars_round_trip(pool: PgPool) {
+    let store: Store = Arc::new(PostgresStorage::new(pool.clone()));
+    let client = client(pool.clone()).await;
+    let alice = signup(&client, "alice").await;
+    let bob = signup(&client, "bob").await;
+    let token = login(&client, "alice").await.access_token;
+
+    let response = client
+        .patch("/users/me")
+        .header(ContentType::JSON)
+        .header(common::bearer(&token))
+        .json(&json!({"display_name": "Alice", "status_text": "Around"}))
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::Ok);
+    let response = client
+        .patch("/users/me")
+        .header(ContentType::JSON)
+        .header(common::bearer(&token))
+        .json(&json!({"status_text": ""}))
+        .dispatch()
+        .await;
+    let profile = body::<Profile>(response).await.data.unwrap();
+    assert_eq!((profile.display_name.as_deref(), profile.status_text), (Some("Alice"), None));
+
+    let gif = b"GIF89a tiny".to_vec();
+    let response = post(&client, "/users/me/avatar", Some(&token), json!({"image": STANDARD.encode(&gif)})).await;
+    assert_eq!(response.status(), Status::Ok);
+    let url = body::<Profile>(response).await.data.unwrap().avatar_url.unwrap();
+    let response = client.get(url).dispatch().await;
+    assert_eq!(response.content_type(), Some(ContentType::GIF));
+    assert_eq!(response.into_bytes().await.unwrap(), gif);
+
+    post(&client, "/chat/conversation/create", Some(&token), json!({"participant_ids": [bob]})).await;
+    let conversations = store.list_conversations(Some(bob)).await.unwrap();
+    let member = conversations[0].members.iter().find(|m| m.user_id == alice).unwrap();
+    assert_eq!(member.name(), "Alice");
+    assert!(member.avatar_url.is_some());
+
+    assert!(store.delete_user(alice).await.unwrap());
+    assert_eq!(count(&pool, "user_avatar").await, 0);
+}
+
+#[sqlx::test]
+async fn last_seen_is_stored_and_can_be_hidden(pool: PgPool) {
+    let store: Store = Arc::new(PostgresStorage::new(pool.clone()));
+    let client = client(pool.clone()).await;
+    let alice = signup(&client, "alice").await;
+    let bob = signup(&client, "bob").await;
+    let token = login(&client, "alice").await.access_token;
+    post(&client, "/chat/conversation/create", Some(&token), json!({"participant_ids": [bob]})).await;
+
+    let response = post(&client, "/presence/heartbeat", Some(&token), json!({"active": true})).await;
+    assert_eq!(body::<Presence>(response).await.data.unwrap().status, PresenceStatus::Online);
+    let last_seen = |conversations: Vec<ConversationSummary>| {
+        return conversations[0].members.iter().find(|m| m.user_id == alice).unwrap().presence.last_seen_at;
+    };
+    assert!(last_seen(store.list_conversations(Some(bob)).await.unwrap()).is_some());
+
+    let changes = ProfileChanges { hide_last_seen: Some(true), ..Default::default() };
+    assert!(store.update_profile(alice, &changes).await.unwrap().unwrap().hide_last_seen);
+    assert_eq!(last_seen(store.list_conversations(Some(bob)).await.unwrap()), None);
+}
+
+#[sqlx::test]
+async fn blocks_and_mutes_are_stored(pool: PgPool) {
+    let store: Store = Arc::new(PostgresStorage::new(pool.clone()));
+    let client = client(pool.clone()).await;
+    let alice = signup(&client, "alice").await;
+    let bob = signup(&client, "bob").await;
+    let carol = signup(&client, "carol").await;
+    let token = login(&client, "alice").await.access_token;
+
+    post(&client, "/users/block", Some(&token), json!({"user_id": bob})).await;
+    assert!(!store.block_user(alice, 999).await.unwrap());
+    assert_eq!(store.find_blocks(bob, &[alice, carol]).await.unwrap(), vec![Block { blocker_id: alice, blocked_id: bob }]);
+    let response = client.get("/users/blocked").header(common::bearer(&token)).dispatch().await;
+    assert_eq!(body::<BlockedUsers>(response).await.data.unwrap().users[0].id, bob);
+    let response = client.get("/users/search?name=b&limit=10").header(common::bearer(&token)).dispatch().await;
+    assert!(body::<SearchUserResult>(response).await.data.unwrap().result.is_empty());
+
+    let response = post(&client, "/chat/conversation/create", Some(&token), json!({"participant_ids": [bob, carol]})).await;
+    assert_eq!(body::<CreateConversationResponse>(response).await.code, Some(ErrorCode::UserBlocked));
+    assert_eq!(count(&pool, "conversation").await, 0);
+
+    let response = post(&client, "/chat/conversation/create", Some(&token), json!({"participant_ids": [carol]})).await;
+    let id: i32 = body::<CreateConversationResponse>(response).await.data.unwrap().conversation_id.parse().unwrap();
+    assert!(store.set_conversation_muted(id, alice, true).await.unwrap());
+    assert!(!store.set_conversation_muted(id, bob, true).await.unwrap());
+    assert!(store.list_conversations(Some(alice)).await.unwrap()[0].muted);
+    assert!(!store.list_conversations(Some(carol)).await.unwrap()[0].muted);
+
+    assert!(store.delete_user(bob).await.unwrap());
+    assert_eq!(count(&pool, "user_block").await, 0);
+}
+
+#[sqlx::test]
+async fn contacts_are_stored_once_per_pair(pool: PgPool) {
+    let store: Store = Arc::new(PostgresStorage::new(pool.clone()));
+    let client = client(pool.clone()).await;
+    let alice = signup(&client, "alice").await;
+    let bob = signup(&client, "bob").await;
+    let token = login(&client, "alice").await.access_token;
+    let bob_token = login(&client, "bob").await.access_token;
+
+    post(&client, "/contacts/request", Some(&token), json!({"user_id": bob})).await;
+    post(&client, "/contacts/request", Some(&bob_token), json!({"user_id": alice})).await;
+    assert_eq!(count(&pool, "contact").await, 1);
+    let (row, _) = store.list_contacts(bob).await.unwrap().remove(0);
+    assert_eq!((row.requester_id, row.status()), (alice, ContactStatus::Accepted));
+    let response = client.get("/contacts/list").header(common::bearer(&bob_token)).dispatch().await;
+    let contacts = body::<ContactList>(response).await.data.unwrap().contacts;
+    assert_eq!((contacts[0].user.id, contacts[0].incoming), (alice, true));
+
+    let response = client
+        .patch("/users/me")
+        .header(common::bearer(&bob_token))
+        .header(ContentType::JSON)
+        .body(json!({"contacts_only_dms": true}).to_string())
+        .dispatch()
+        .await;
+    assert!(body::<MyProfile>(response).await.data.unwrap().contacts_only_dms);
+    let response = post(&client, "/chat/conversation/create", Some(&token), json!({"participant_ids": [bob]})).await;
+    assert_eq!(response.status(), Status::Ok);
+    assert!(!store.needs_contact(alice, bob).await.unwrap());
+    assert!(!store.needs_contact(bob, alice).await.unwrap());
+
+    // Asking each other at the same time still leaves one accepted row.
+    let carol = signup(&client, "carol").await;
+    assert!(store.needs_contact(carol, bob).await.unwrap());
+    let (first, second) = rocket::tokio::join!(
+        store.change_contact(alice, carol, ContactChange::Request),
+        store.change_contact(carol, alice, ContactChange::Request),
+    );
+    assert!(first.is_ok() && second.is_ok());
+    let (row, _) = store.list_contacts(carol).await.unwrap().remove(0);
+    assert_eq!(row.status(), ContactStatus::Accepted);
+    let reversed = sqlx::query("INSERT INTO contact (requester_id, addressee_id, status) VALUES ($1, $2, 'pending')")
+        .bind(row.addressee_id)
+        .bind(row.requester_id)
+        .execute(&pool)
+        .await;
+    assert!(reversed.is_err());
+
+    assert!(store.delete_user(bob).await.unwrap());
+    assert!(store.delete_user(carol).await.unwrap());
+    assert_eq!(count(&pool, "contact").await, 0);
+}
+
+/// Moves every refresh token of `username`, and optionally their families, `days` into the past.
+async fn backdate_tokens(pool: &PgPool, username: &str, days: i32, families_too: bool) {
+    sqlx::query(
+        "UPDATE token t SET created_at = NOW() - make_interval(days => $2)
+        FROM token_family_rel tfr JOIN token_family tf ON tf.id = tfr.token_family_id JOIN users u ON u.id = tf.user_id
+        WHERE tfr.token_id = t.id AND u.username = $1",
+    )
+    .bind(username)
+    .bind(days)
+    .execute(pool)
+    .await
+    .unwrap();
+    if families_too {
+        sqlx::query(
+            "UPDATE token_family tf SET created_at = NOW() - make_interval(days => $2)
+            FROM users u WHERE u.id = tf.user_id AND u.username = $1",
+        )
+        .bind(username)
+        .bind(days)
+        .execute(pool)
+        .await
+        .unwrap();
+    }
+}
+
+#[sqlx::test]
+async fn token_cleanup_drops_expired_and_rotated_tokens_and_empty_families(pool: PgPool) {
+    let client = client(pool.clone()).await;
+    let store: Store = Arc::new(PostgresStorage::new(pool.clone()));
+    // A 3 day refresh TTL and 24 hours of retention: tokens go after 4 days, or 1 day once rotated out.
+    let settings = common::settings();
+    // Logging in rotates the token issued at signup out of the family.
+    for username in ["alice", "bob", "carol"] {
+        signup(&client, username).await;
+        login(&client, username).await;
+    }
+    // Alice rotated two days ago, Bob's family is past TTL + retention and Carol's is fresh.
+    backdate_tokens(&pool, "alice", 2, false).await;
+    backdate_tokens(&pool, "bob", 5, true).await;
+
+    let metrics = &TOKEN_CLEANUP_METRICS;
+    let runs = metrics.runs.load(Ordering::Relaxed);
+    let tokens_removed = metrics.tokens_removed.load(Ordering::Relaxed);
+    let families_removed = metrics.families_removed.load(Ordering::Relaxed);
+    let command = Command::parse(&["tokens".into(), "cleanup".into()]).unwrap();
+    let output = admin::run(&store, &settings, command).await.unwrap();
+    assert_eq!(output, "Removed 3 tokens and 1 token families");
+
+    let remaining: Vec<(String, String)> = sqlx::query_as(
+        "SELECT u.username, tfr.status FROM token t
+        JOIN token_family_rel tfr ON tfr.token_id = t.id
+        JOIN token_family tf ON tf.id = tfr.token_family_id
+        JOIN users u ON u.id = tf.user_id
+        ORDER BY t.id",
+    )
+    .fetch_all(&pool)
+    .await
+    .unwrap();
+    let remaining: Vec<(&str, &str)> = remaining.iter().map(|(user, status)| (user.as_str(), status.as_str())).collect();
+    assert_eq!(remaining, vec![("alice", "ACTIVE"), ("carol", "expired"), ("carol", "ACTIVE")]);
+    assert_eq!(count(&pool, "token").await, 3);
+    let families: Vec<String> = sqlx::query_scalar(
+        "SELECT u.username FROM token_family tf JOIN users u ON u.id = tf.user_id ORDER BY u.username",
+    )
+    .fetch_all(&pool)
+    .await
+    .unwrap();
+    assert_eq!(families, vec!["alice", "carol"]);
+
+    assert_eq!(metrics.runs.load(Ordering::Relaxed) - runs, 1);
+    assert_eq!(metrics.tokens_removed.load(Ordering::Relaxed) - tokens_removed, 3);
+    assert_eq!(metrics.families_removed.load(Ordering::Relaxed) - families_removed, 1);
+    assert_eq!(metrics.failures.load(Ordering::Relaxed), 0);
+    assert!(metrics.last_run_unix.load(Ordering::Relaxed) > 0);
+}
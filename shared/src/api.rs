@@ -0,0 +1,263 @@
+use serde::{Serialize, de::DeserializeOwned};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Method {
+    Get,
+    Post,
+    Patch,
+}
+
+/// One HTTP endpoint, implemented by the marker types generated from [`endpoint!`].
+///
+/// The server mounts its handler at `SCOPE` with `ROUTE` as the Rocket path, and the client sends
+/// `Request` to [`Endpoint::path`] and reads back `Response`, so both ends share one definition.
+pub trait Endpoint {
+    const METHOD: Method;
+    /// Mount point on the server, e.g. `/auth`.
+    const SCOPE: &'static str;
+    /// Rocket path below `SCOPE`, including the query template for GET endpoints.
+    const ROUTE: &'static str;
+    /// Whether the request needs a bearer access token.
+    const AUTH: bool;
+    /// JSON body for POST, query string for GET.
+    type Request: Serialize + DeserializeOwned + Send + Sync + 'static;
+    type Response: Serialize + DeserializeOwned + Send + 'static;
+
+    /// Full request path without the query template, e.g. `/users/search`.
+    fn path() -> String {
+        let route = Self::ROUTE.split('?').next().unwrap();
+        return format!("{}{}", Self::SCOPE, route);
+    }
+
+    /// [`Endpoint::path`] with each `<name>` segment filled from the request field of that name,
+    /// e.g. `/users/<id>` becomes `/users/7`.
+    fn path_for(request: &Self::Request) -> String {
+        let path = Self::path();
+        if !path.contains('<') {
+            return path;
+        }
+        let fields = serde_json::to_value(request).unwrap();
+        return path
+            .split('/')
+            .map(|segment| match segment.strip_prefix('<').and_then(|s| s.strip_suffix('>')) {
+                Some(name) => match &fields[name] {
+                    serde_json::Value::String(value) => value.clone(),
+                    value => value.to_string(),
+                },
+                None => segment.to_string(),
+            })
+            .collect::<Vec<_>>()
+            .join("/");
+    }
+}
+
+/// Implemented by `#[macros::api_route]` for the Rocket route serving an endpoint.
+pub trait ApiRoute {
+    type Endpoint: Endpoint;
+}
+
+pub type Req<E> = <E as Endpoint>::Request;
+pub type Res<E> = <E as Endpoint>::Response;
+
+/// The list of API endpoints.
+///
+/// `endpoint!(Name => path::to::callback { args })` expands to
+/// `callback! { Name method auth "/scope" "/route" (Request) -> (Response) { args } }`, where
+/// `method` is `get`, `post` or `patch` and `auth` is `auth` or `public`. This crate uses it to
+/// generate the [`Endpoint`] types; `#[macros::api_route]` uses it to write the server's Rocket
+/// route attribute, so a path only ever appears here.
+#[macro_export]
+macro_rules! endpoint {
+    (Signup => $($then:ident)::+ { $($args:tt)* }) => {
+        $($then)::+! { Signup post public "/auth" "/signup"
+            ($crate::routes::auth::signup::SignupRequest) -> ($crate::routes::auth::signup::SignupResponse)
+            { $($args)* } }
+    };
+    (Login => $($then:ident)::+ { $($args:tt)* }) => {
+        $($then)::+! { Login post public "/auth" "/login"
+            ($crate::routes::auth::login::LoginRequest) -> ($crate::routes::auth::login::LoginResponse)
+            { $($args)* } }
+    };
+    (Refresh => $($then:ident)::+ { $($args:tt)* }) => {
+        $($then)::+! { Refresh post public "/auth" "/refresh"
+            ($crate::routes::auth::refresh::RefreshRequest) -> ($crate::routes::auth::refresh::RefreshResponse)
+            { $($args)* } }
+    };
+    (SearchUsers => $($then:ident)::+ { $($args:tt)* }) => {
+        $($then)::+! { SearchUsers get auth "/users" "/search?<query..>"
+            ($crate::routes::users::search::SearchQuery) -> ($crate::routes::users::search::SearchUserResult)
+            { $($args)* } }
+    };
+    (GetMe => $($then:ident)::+ { $($args:tt)* }) => {
+        $($then)::+! { GetMe get auth "/users" "/me"
+            (()) -> ($crate::routes::users::profile::MyProfile)
+            { $($args)* } }
+    };
+    (UpdateMe => $($then:ident)::+ { $($args:tt)* }) => {
+        $($then)::+! { UpdateMe patch auth "/users" "/me"
+            ($crate::routes::users::profile::UpdateProfileRequest) -> ($crate::routes::users::profile::MyProfile)
+            { $($args)* } }
+    };
+    (SetAvatar => $($then:ident)::+ { $($args:tt)* }) => {
+        $($then)::+! { SetAvatar post auth "/users" "/me/avatar"
+            ($crate::routes::users::profile::SetAvatarRequest) -> ($crate::routes::users::profile::MyProfile)
+            { $($args)* } }
+    };
+    (GetUser => $($then:ident)::+ { $($args:tt)* }) => {
+        $($then)::+! { GetUser get auth "/users" "/<id>"
+            ($crate::routes::users::profile::ProfileQuery) -> ($crate::routes::users::profile::Profile)
+            { $($args)* } }
+    };
+    (ExportAccount => $($then:ident)::+ { $($args:tt)* }) => {
+        $($then)::+! { ExportAccount get auth "/users" "/me/export"
+            (()) -> ($crate::routes::users::account::AccountExport)
+            { $($args)* } }
+    };
+    (DeleteAccount => $($then:ident)::+ { $($args:tt)* }) => {
+        $($then)::+! { DeleteAccount post auth "/users" "/me/delete"
+            ($crate::routes::users::account::DeleteAccountRequest) -> (())
+            { $($args)* } }
+    };
+    (BlockUser => $($then:ident)::+ { $($args:tt)* }) => {
+        $($then)::+! { BlockUser post auth "/users" "/block"
+            ($crate::routes::users::block::BlockRequest) -> (())
+            { $($args)* } }
+    };
+    (UnblockUser => $($then:ident)::+ { $($args:tt)* }) => {
+        $($then)::+! { UnblockUser post auth "/users" "/unblock"
+            ($crate::routes::users::block::BlockRequest) -> (())
+            { $($args)* } }
+    };
+    (ListBlocked => $($then:ident)::+ { $($args:tt)* }) => {
+        $($then)::+! { ListBlocked get auth "/users" "/blocked"
+            (()) -> ($crate::routes::users::block::BlockedUsers)
+            { $($args)* } }
+    };
+    (ListContacts => $($then:ident)::+ { $($args:tt)* }) => {
+        $($then)::+! { ListContacts get auth "/contacts" "/list"
+            (()) -> ($crate::routes::users::contacts::ContactList)
+            { $($args)* } }
+    };
+    (RequestContact => $($then:ident)::+ { $($args:tt)* }) => {
+        $($then)::+! { RequestContact post auth "/contacts" "/request"
+            ($crate::routes::users::contacts::ContactRequest) -> ($crate::routes::users::contacts::Contact)
+            { $($args)* } }
+    };
+    (RespondContact => $($then:ident)::+ { $($args:tt)* }) => {
+        $($then)::+! { RespondContact post auth "/contacts" "/respond"
+            ($crate::routes::users::contacts::RespondContactRequest) -> ($crate::routes::users::contacts::Contact)
+            { $($args)* } }
+    };
+    (RemoveContact => $($then:ident)::+ { $($args:tt)* }) => {
+        $($then)::+! { RemoveContact post auth "/contacts" "/remove"
+            ($crate::routes::users::contacts::ContactRequest) -> (())
+            { $($args)* } }
+    };
+    (CreateConversation => $($then:ident)::+ { $($args:tt)* }) => {
+        $($then)::+! { CreateConversation post auth "/chat/conversation" "/create"
+            ($crate::routes::chat::conversation::CreateConversationRequest) -> ($crate::routes::chat::conversation::CreateConversationResponse)
+            { $($args)* } }
+    };
+    (ListConversations => $($then:ident)::+ { $($args:tt)* }) => {
+        $($then)::+! { ListConversations get auth "/chat/conversation" "/list"
+            (()) -> ($crate::routes::chat::conversation::ConversationList)
+            { $($args)* } }
+    };
+    (MuteConversation => $($then:ident)::+ { $($args:tt)* }) => {
+        $($then)::+! { MuteConversation post auth "/chat/conversation" "/mute"
+            ($crate::routes::chat::conversation::MuteConversationRequest) -> (())
+            { $($args)* } }
+    };
+    (Heartbeat => $($then:ident)::+ { $($args:tt)* }) => {
+        $($then)::+! { Heartbeat post auth "/presence" "/heartbeat"
+            ($crate::routes::users::presence::HeartbeatRequest) -> ($crate::routes::users::presence::Presence)
+            { $($args)* } }
+    };
+}
+
+macro_rules! define_endpoint {
+    ($name:ident $method:ident $auth:ident $scope:literal $route:literal ($req:ty) -> ($res:ty) { $(#[$doc:meta])* }) => {
+        $(#[$doc])*
+        pub struct $name;
+
+        impl Endpoint for $name {
+            const METHOD: Method = define_endpoint!(@method $method);
+            const SCOPE: &'static str = $scope;
+            const ROUTE: &'static str = $route;
+            const AUTH: bool = define_endpoint!(@auth $auth);
+            type Request = $req;
+            type Response = $res;
+        }
+    };
+    (@method get) => { Method::Get };
+    (@method post) => { Method::Post };
+    (@method patch) => { Method::Patch };
+    (@auth auth) => { true };
+    (@auth public) => { false };
+}
+
+endpoint!(Signup => define_endpoint {
+    /// `POST /auth/signup`
+});
+endpoint!(Login => define_endpoint {
+    /// `POST /auth/login`, returns a fresh token pair.
+});
+endpoint!(Refresh => define_endpoint {
+    /// `POST /auth/refresh`, rotates the refresh token.
+});
+endpoint!(SearchUsers => define_endpoint {
+    /// `GET /users/search?name=&limit=&cursor=`
+});
+endpoint!(GetMe => define_endpoint {
+    /// `GET /users/me`, the caller's own profile.
+});
+endpoint!(UpdateMe => define_endpoint {
+    /// `PATCH /users/me`
+});
+endpoint!(SetAvatar => define_endpoint {
+    /// `POST /users/me/avatar`. The image itself is served at `Profile::avatar_url`.
+});
+endpoint!(GetUser => define_endpoint {
+    /// `GET /users/<id>`
+});
+endpoint!(ExportAccount => define_endpoint {
+    /// `GET /users/me/export`, the caller's data as one JSON document.
+});
+endpoint!(DeleteAccount => define_endpoint {
+    /// `POST /users/me/delete`. Messages the caller sent stay in their conversations without a sender.
+});
+endpoint!(BlockUser => define_endpoint {
+    /// `POST /users/block`. Neither side can start a conversation with the other afterwards, and
+    /// they drop out of each other's search results.
+});
+endpoint!(UnblockUser => define_endpoint {
+    /// `POST /users/unblock`, succeeds whether or not the user was blocked.
+});
+endpoint!(ListBlocked => define_endpoint {
+    /// `GET /users/blocked`, everyone the caller blocked.
+});
+endpoint!(ListContacts => define_endpoint {
+    /// `GET /contacts/list`, accepted contacts and open requests in both directions.
+});
+endpoint!(RequestContact => define_endpoint {
+    /// `POST /contacts/request`. Accepts instead when the other user already asked the caller.
+});
+endpoint!(RespondContact => define_endpoint {
+    /// `POST /contacts/respond`, accepts or declines a request sent to the caller.
+});
+endpoint!(RemoveContact => define_endpoint {
+    /// `POST /contacts/remove`, drops a contact or withdraws a request.
+});
+endpoint!(CreateConversation => define_endpoint {
+    /// `POST /chat/conversation/create`
+});
+endpoint!(ListConversations => define_endpoint {
+    /// `GET /chat/conversation/list`, the caller's conversations with their members' presence.
+});
+endpoint!(MuteConversation => define_endpoint {
+    /// `POST /chat/conversation/mute`, mutes or unmutes a conversation for the caller.
+});
+endpoint!(Heartbeat => define_endpoint {
+    /// `POST /presence/heartbeat`, keeps the caller online between realtime pings. Returns their
+    /// own presence.
+});
@@ -1,13 +1,25 @@
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 
-#[derive(Serialize,Deserialize,Debug)]
+#[derive(Serialize,Deserialize,Debug,Clone)]
 pub struct User {
     pub id: i32,
     pub username: String,
     pub hash_password: String,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
+    /// Set while an admin has the account disabled.
+    pub disabled_at: Option<DateTime<Utc>>,
+    pub display_name: Option<String>,
+    pub bio: Option<String>,
+    pub status_text: Option<String>,
+    /// When the current avatar was uploaded, `None` without one.
+    pub avatar_updated_at: Option<DateTime<Utc>>,
+    /// Last heartbeat or realtime ping.
+    pub last_seen_at: Option<DateTime<Utc>>,
+    pub hide_last_seen: bool,
+    /// Only accepted contacts may start conversations with this user.
+    pub contacts_only_dms: bool,
 }
 
 
@@ -0,0 +1,54 @@
+use serde::{Deserialize, Serialize};
+
+/// Stable, machine-readable error codes sent in `ResponseStruct::code`.
+///
+/// Clients should branch on these rather than on `message`, which is for humans and may change.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum ErrorCode {
+    /// Something failed on the server. Details are only in the server logs.
+    Internal,
+    /// The request body or query failed validation; see `ResponseStruct::details`.
+    ValidationFailed,
+    NotFound,
+    UserAlreadyExists,
+    InvalidCredentials,
+    /// The password was right but an admin has disabled the account.
+    AccountDisabled,
+    /// One or more `participant_ids` do not belong to an existing user.
+    ConversationInvalidMembers,
+    /// The caller blocked one of the users they tried to reach. Unblocking them will help.
+    UserBlocked,
+    /// One of the users only accepts conversations from their accepted contacts.
+    ContactsOnly,
+    /// No `Authorization: Bearer` header was sent.
+    AuthTokenMissing,
+    /// The token could not be parsed at all.
+    AuthTokenMalformed,
+    /// The token is well formed but past its `exp`. Refreshing will help.
+    AuthTokenExpired,
+    /// The refresh token was already rotated or its family was revoked. Log in again.
+    AuthTokenRevoked,
+    /// Bad signature, wrong issuer/audience/type or unknown key.
+    AuthTokenInvalid,
+    /// The token was issued with a claims version this server no longer accepts.
+    AuthTokenUnsupportedVersion,
+    /// Too many requests from this address. Wait for the `Retry-After` seconds and try again.
+    RateLimited,
+}
+
+/// A validation problem with one request field.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct FieldError {
+    pub field: String,
+    pub message: String,
+}
+
+impl FieldError {
+    pub fn new(field: &str, message: &str) -> Self {
+        return Self {
+            field: field.into(),
+            message: message.into(),
+        };
+    }
+}
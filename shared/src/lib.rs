@@ -1,5 +1,8 @@
+pub mod api;
 pub mod db;
+pub mod error;
 pub mod routes;
+pub use error::{ErrorCode, FieldError};
 use serde::{Deserialize, Serialize, de::DeserializeOwned};
 
 #[derive(Debug,Clone, Copy)]
@@ -54,9 +57,15 @@ pub enum Response<T> {
     #[cfg_attr(feature = "server", response(status = 401))]
     // #[response(status = 401)]
     Unauthorized(WebBox<ResponseStruct<Option<T>>>),
+    #[cfg_attr(feature = "server", response(status = 429))]
+    TooManyRequests(WebBox<ResponseStruct<Option<T>>>),
 }
 
 
+/// Lets routes return early with `?` on any `#[db_err]` error.
+#[cfg(feature = "server")]
+pub type ApiResult<T> = Result<Response<T>, Response<T>>;
+
 #[cfg(feature = "server")]
 impl<T> Response<T>
 where
@@ -71,12 +80,38 @@ where
     pub fn bad_request(message: &str, data: Option<T>) -> Self {
         Response::BadRequest(Json(ResponseStruct::new(false, message, data)))
     }
+    /// Always tagged [`ErrorCode::Internal`]. Keep `message` generic and log the cause instead.
     pub fn internal_error(message: &str, data: Option<T>) -> Self {
-        Response::InternalError(Json(ResponseStruct::new(false, message, data)))
+        Response::InternalError(Json(ResponseStruct::new(false, message, data))).with_code(ErrorCode::Internal)
     }
     pub fn unauthorized(message: &str, data: Option<T>) -> Self {
         Response::Unauthorized(Json(ResponseStruct::new(false, message, data)))
     }
+    pub fn too_many_requests(message: &str, data: Option<T>) -> Self {
+        Response::TooManyRequests(Json(ResponseStruct::new(false, message, data)))
+    }
+    pub fn with_code(mut self, code: ErrorCode) -> Self {
+        match &mut self {
+            Response::Success(body) => body.code = Some(code),
+            Response::NotFound(body) => body.code = Some(code),
+            Response::BadRequest(body) => body.code = Some(code),
+            Response::InternalError(body) => body.code = Some(code),
+            Response::Unauthorized(body) => body.code = Some(code),
+            Response::TooManyRequests(body) => body.code = Some(code),
+        }
+        return self;
+    }
+    pub fn with_details(mut self, details: Vec<FieldError>) -> Self {
+        match &mut self {
+            Response::Success(body) => body.details = Some(details),
+            Response::NotFound(body) => body.details = Some(details),
+            Response::BadRequest(body) => body.details = Some(details),
+            Response::InternalError(body) => body.details = Some(details),
+            Response::Unauthorized(body) => body.details = Some(details),
+            Response::TooManyRequests(body) => body.details = Some(details),
+        }
+        return self;
+    }
 }
 
 #[cfg(feature = "server")]
@@ -84,6 +119,10 @@ where
 pub struct ResponseStruct<T> {
     pub success: bool,
     pub message: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub code: Option<ErrorCode>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub details: Option<Vec<FieldError>>,
     pub data: T,
 }
 
@@ -92,6 +131,10 @@ pub struct ResponseStruct<T> {
 pub struct ResponseStruct<T> {
     pub success: bool,
     pub message: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub code: Option<ErrorCode>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub details: Option<Vec<FieldError>>,
     pub data: Option<T>,
 }
 
@@ -104,6 +147,8 @@ where
         return Self {
             success,
             message: message.into(),
+            code: None,
+            details: None,
             data,
         };
     }
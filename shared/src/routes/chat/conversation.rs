@@ -1,16 +1,45 @@
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 
+use crate::routes::users::{presence::Presence, profile::avatar_url};
+
 
 #[derive(Serialize,Deserialize)]
 pub struct CreateConversationRequest {
     pub participant_ids: Vec<i32>,
+    /// Sent by the caller in the same transaction that creates the conversation.
+    #[serde(default)]
+    pub first_message: Option<String>,
 }
 
 
-#[derive(Serialize,Deserialize)]
+#[derive(Serialize,Deserialize,Debug,Clone)]
 pub struct ConversationMember {
     pub user_id: i32,
     pub username: String,
+    #[serde(default)]
+    pub display_name: Option<String>,
+    #[serde(default)]
+    pub avatar_url: Option<String>,
+    #[serde(default)]
+    pub presence: Presence,
+}
+
+impl ConversationMember {
+    pub fn new(
+        user_id: i32,
+        username: String,
+        display_name: Option<String>,
+        avatar_updated_at: Option<DateTime<Utc>>,
+        presence: Presence,
+    ) -> Self {
+        return Self { user_id, username, display_name, avatar_url: avatar_url(user_id, avatar_updated_at), presence };
+    }
+
+    /// What to show for this member: the display name, or the username without one.
+    pub fn name(&self) -> &str {
+        return self.display_name.as_deref().unwrap_or(&self.username);
+    }
 }
 
 #[derive(Serialize,Deserialize)]
@@ -18,4 +47,26 @@ pub struct CreateConversationResponse {
     pub conversation_id: String,
     pub title: Option<String>,
     pub members: Vec<ConversationMember>,
-}
\ No newline at end of file
+}
+
+#[derive(Serialize,Deserialize,Debug,Clone)]
+pub struct ConversationListItem {
+    pub id: i32,
+    pub title: Option<String>,
+    pub members: Vec<ConversationMember>,
+    /// Whether the caller muted this conversation.
+    #[serde(default)]
+    pub muted: bool,
+}
+
+#[derive(Serialize,Deserialize,Debug,Clone)]
+pub struct ConversationList {
+    pub conversations: Vec<ConversationListItem>,
+}
+
+/// Muting only affects the caller; the other members still get notified.
+#[derive(Serialize,Deserialize)]
+pub struct MuteConversationRequest {
+    pub conversation_id: i32,
+    pub muted: bool,
+}
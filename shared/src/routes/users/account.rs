@@ -0,0 +1,54 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::routes::chat::conversation::ConversationMember;
+
+#[derive(Serialize,Deserialize)]
+pub struct DeleteAccountRequest {
+    /// The current password, asked again so a stolen access token can't delete the account.
+    pub password: String,
+}
+
+#[derive(Serialize,Deserialize,Debug,Clone)]
+pub struct ExportedProfile {
+    pub id: i32,
+    pub username: String,
+    pub display_name: Option<String>,
+    pub bio: Option<String>,
+    pub status_text: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Serialize,Deserialize,Debug,Clone)]
+pub struct ExportedConversation {
+    pub id: i32,
+    pub title: Option<String>,
+    pub members: Vec<ConversationMember>,
+    #[serde(default)]
+    pub muted: bool,
+}
+
+#[derive(Serialize,Deserialize,Debug,Clone)]
+pub struct ExportedMessage {
+    pub id: i32,
+    pub conversation_id: i32,
+    pub text: String,
+    pub sent_at: DateTime<Utc>,
+}
+
+/// Everything stored about the caller: their profile, the conversations they are in, the
+/// messages they sent and who they blocked. Other members' messages are not included.
+#[derive(Serialize,Deserialize,Debug,Clone)]
+pub struct AccountExport {
+    pub exported_at: DateTime<Utc>,
+    pub profile: ExportedProfile,
+    pub conversations: Vec<ExportedConversation>,
+    pub messages: Vec<ExportedMessage>,
+    /// Usernames of everyone the caller blocked.
+    #[serde(default)]
+    pub blocked_users: Vec<String>,
+    /// Usernames of the caller's accepted contacts.
+    #[serde(default)]
+    pub contacts: Vec<String>,
+}
@@ -0,0 +1,13 @@
+use serde::{Deserialize, Serialize};
+
+use crate::routes::users::search::SearchUser;
+
+#[derive(Serialize,Deserialize)]
+pub struct BlockRequest {
+    pub user_id: i32,
+}
+
+#[derive(Serialize,Deserialize,Clone)]
+pub struct BlockedUsers {
+    pub users: Vec<SearchUser>,
+}
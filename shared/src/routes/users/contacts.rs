@@ -0,0 +1,60 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::routes::users::search::SearchUser;
+
+#[derive(Serialize,Deserialize,Debug,Clone,Copy,PartialEq,Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ContactStatus {
+    /// Waiting for the addressee to answer.
+    Pending,
+    Accepted,
+    /// Only the addressee sees this; to the requester the request still looks pending.
+    Declined,
+}
+
+impl ContactStatus {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ContactStatus::Pending => return "pending",
+            ContactStatus::Accepted => return "accepted",
+            ContactStatus::Declined => return "declined",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "pending" => return Some(ContactStatus::Pending),
+            "accepted" => return Some(ContactStatus::Accepted),
+            "declined" => return Some(ContactStatus::Declined),
+            _ => return None,
+        }
+    }
+}
+
+/// The caller's relation to `user`.
+#[derive(Serialize,Deserialize,Debug,Clone)]
+pub struct Contact {
+    pub user: SearchUser,
+    pub status: ContactStatus,
+    /// Whether `user` sent the request rather than the caller.
+    pub incoming: bool,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Serialize,Deserialize,Debug,Clone)]
+pub struct ContactList {
+    pub contacts: Vec<Contact>,
+}
+
+#[derive(Serialize,Deserialize)]
+pub struct ContactRequest {
+    pub user_id: i32,
+}
+
+#[derive(Serialize,Deserialize)]
+pub struct RespondContactRequest {
+    /// Who sent the request.
+    pub user_id: i32,
+    pub accept: bool,
+}
@@ -0,0 +1,39 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize,Deserialize,Debug,Clone,Copy,PartialEq,Eq,Default)]
+#[serde(rename_all = "snake_case")]
+pub enum PresenceStatus {
+    /// Connected and recently active.
+    Online,
+    /// Connected, but idle for longer than the server's away timeout.
+    Away,
+    #[default]
+    Offline,
+}
+
+#[derive(Serialize,Deserialize,Debug,Clone,Default,PartialEq)]
+pub struct Presence {
+    pub status: PresenceStatus,
+    /// `None` when the user hides it or was never seen.
+    pub last_seen_at: Option<DateTime<Utc>>,
+}
+
+impl Presence {
+    /// What the database knows; the server fills in `status` from live connections.
+    pub fn offline(last_seen_at: Option<DateTime<Utc>>, hide_last_seen: bool) -> Self {
+        let last_seen_at = if hide_last_seen { None } else { last_seen_at };
+        return Presence { status: PresenceStatus::Offline, last_seen_at };
+    }
+}
+
+fn active_by_default() -> bool {
+    return true;
+}
+
+#[derive(Serialize,Deserialize)]
+pub struct HeartbeatRequest {
+    /// `false` while the user is idle, so they show as away.
+    #[serde(default = "active_by_default")]
+    pub active: bool,
+}
@@ -0,0 +1,85 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::{db::signup::User, routes::users::presence::Presence};
+
+/// Where a user's avatar is served, versioned by upload time so clients can cache it forever.
+pub fn avatar_url(user_id: i32, avatar_updated_at: Option<DateTime<Utc>>) -> Option<String> {
+    return avatar_updated_at.map(|at| format!("/users/{user_id}/avatar?v={}", at.timestamp_millis()));
+}
+
+/// What anyone signed in can see about a user.
+#[derive(Serialize,Deserialize,Debug,Clone)]
+pub struct Profile {
+    pub id: i32,
+    pub username: String,
+    pub display_name: Option<String>,
+    pub bio: Option<String>,
+    /// A short free text status such as "On holiday".
+    pub status_text: Option<String>,
+    pub avatar_url: Option<String>,
+    pub presence: Presence,
+    pub created_at: DateTime<Utc>,
+}
+
+impl From<User> for Profile {
+    fn from(value: User) -> Self {
+        return Self {
+            id: value.id,
+            avatar_url: avatar_url(value.id, value.avatar_updated_at),
+            presence: Presence::offline(value.last_seen_at, value.hide_last_seen),
+            username: value.username,
+            display_name: value.display_name,
+            bio: value.bio,
+            status_text: value.status_text,
+            created_at: value.created_at,
+        };
+    }
+}
+
+/// The caller's own profile, with the settings nobody else gets to see.
+#[derive(Serialize,Deserialize,Debug,Clone)]
+pub struct MyProfile {
+    #[serde(flatten)]
+    pub profile: Profile,
+    pub hide_last_seen: bool,
+    pub contacts_only_dms: bool,
+}
+
+impl From<User> for MyProfile {
+    fn from(value: User) -> Self {
+        return Self {
+            hide_last_seen: value.hide_last_seen,
+            contacts_only_dms: value.contacts_only_dms,
+            profile: Profile::from(value),
+        };
+    }
+}
+
+#[derive(Serialize,Deserialize)]
+pub struct ProfileQuery {
+    pub id: i32,
+}
+
+/// Fields left out (or `null`) stay as they are; an empty string clears one.
+#[derive(Serialize,Deserialize,Default)]
+pub struct UpdateProfileRequest {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub display_name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bio: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub status_text: Option<String>,
+    /// Hides `Presence::last_seen_at` from everyone else.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hide_last_seen: Option<bool>,
+    /// Only lets accepted contacts start conversations with the caller.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub contacts_only_dms: Option<bool>,
+}
+
+#[derive(Serialize,Deserialize)]
+pub struct SetAvatarRequest {
+    /// Base64 of a PNG, JPEG, GIF or WebP file. `None` removes the avatar.
+    pub image: Option<String>,
+}
@@ -1,36 +1,62 @@
 use serde::{Deserialize, Serialize};
 
-use crate::db::signup::User;
+use crate::{db::signup::User, routes::users::{presence::Presence, profile::avatar_url}};
 
 
 #[derive(Serialize,Deserialize)]
+#[cfg_attr(feature = "server", derive(rocket::FromForm))]
 pub struct SearchQuery {
     pub name: String,
     pub limit: i32,
-    pub page: i32
+    /// `next_cursor` of the previous page; left out for the first one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cursor: Option<String>,
 }
 
-#[derive(Serialize,Deserialize,Clone)]
+#[derive(Serialize,Deserialize,Debug,Clone)]
 pub struct SearchUser {
     pub id: i32,
-    pub username: String
+    pub username: String,
+    #[serde(default)]
+    pub display_name: Option<String>,
+    #[serde(default)]
+    pub avatar_url: Option<String>,
+    #[serde(default)]
+    pub presence: Presence,
 }
 impl From<User> for SearchUser {
     fn from(value: User) -> Self {
-        return Self { id: value.id, username: value.username }
+        return Self {
+            id: value.id,
+            presence: Presence::offline(value.last_seen_at, value.hide_last_seen),
+            avatar_url: avatar_url(value.id, value.avatar_updated_at),
+            username: value.username,
+            display_name: value.display_name,
+        }
+    }
+}
+
+impl SearchUser {
+    /// The display name, or the username without one.
+    pub fn name(&self) -> &str {
+        return self.display_name.as_deref().unwrap_or(&self.username);
     }
 }
 
 
 #[derive(Serialize,Deserialize)]
 pub struct  SearchUserResult {
-    pub result: Vec<SearchUser>
+    pub result: Vec<SearchUser>,
+    /// Set when there are more results; pass it back as `SearchQuery::cursor`.
+    #[serde(default)]
+    pub next_cursor: Option<String>,
 }
 
 impl SearchUserResult {
-    pub fn new(users: Vec<SearchUser>) -> Self {
+    pub fn new(users: Vec<SearchUser>, next_cursor: Option<String>) -> Self {
         return Self {
-            result:users
+            result:users,
+            next_cursor,
         }
     }
 }
\ No newline at end of file
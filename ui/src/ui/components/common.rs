@@ -1,5 +1,39 @@
 use crate::raylib::prelude::*;
-use std::{cell::RefCell, collections::HashMap, rc::Rc, sync::Arc};
+use std::{cell::RefCell, collections::HashMap, rc::Rc, sync::{Arc, OnceLock}};
+
+/// Defaults for every text component. Set once through `UIRoot::start`.
+#[derive(Debug, Clone, Copy)]
+pub struct TextStyle {
+    /// Size used when a component doesn't set one.
+    pub font_size: i32,
+    /// Multiplies every font size, including explicit ones.
+    pub font_scale: f32,
+    pub text_color: Color,
+}
+
+impl Default for TextStyle {
+    fn default() -> Self {
+        return TextStyle {
+            font_size: 24,
+            font_scale: 1.0,
+            text_color: Color::BLACK,
+        };
+    }
+}
+
+static TEXT_STYLE: OnceLock<TextStyle> = OnceLock::new();
+
+pub fn set_text_style(style: TextStyle) {
+    TEXT_STYLE.set(style).ok().expect("Text style already set");
+}
+
+pub fn text_style() -> TextStyle {
+    return TEXT_STYLE.get().copied().unwrap_or_default();
+}
+
+pub fn scale_font(size: i32) -> i32 {
+    return (size as f32 * text_style().font_scale).round() as i32;
+}
 
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum Length {
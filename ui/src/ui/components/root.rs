@@ -1,18 +1,43 @@
 use crate::components::common::*;
 use raylib::{
     color::Color,
-    ffi::{KeyboardKey, MouseButton},
+    ffi::{KeyboardKey, MouseButton, TraceLogLevel},
     prelude::{RaylibDraw, RaylibDrawHandle},
 };
-use std::{cell::RefCell, collections::HashMap, rc::Rc, sync::mpsc, time::Instant, vec};
+use std::{cell::RefCell, collections::HashMap, rc::Rc, sync::{Mutex, mpsc}, time::{Duration, Instant}, vec};
+
+pub struct WindowOptions {
+    pub dim: (i32, i32),
+    pub title: String,
+    /// Drawn behind everything.
+    pub background: Color,
+    pub text_style: TextStyle,
+    pub log_level: TraceLogLevel,
+}
+
+/// When the user last pressed a key, clicked, scrolled or moved the mouse.
+static LAST_INPUT: Mutex<Option<Instant>> = Mutex::new(None);
 
 pub struct UIRoot {}
 impl UIRoot {
-    pub fn start(builder: Box<dyn Fn() -> Component>, dim: (i32, i32), title: &str, rebuild_signal: mpsc::Receiver<()>) {
+    /// How long the window has gone without user input. Safe to call from any thread.
+    pub fn idle_time() -> Duration {
+        match *LAST_INPUT.lock().unwrap() {
+            Some(at) => at.elapsed(),
+            None => Duration::ZERO,
+        }
+    }
+
+    pub fn start(builder: Box<dyn Fn() -> Component>, options: WindowOptions, rebuild_signal: mpsc::Receiver<()>) {
+        let WindowOptions { dim, title, background, text_style, log_level } = options;
+        set_text_style(text_style);
+        unsafe {
+            raylib::ffi::SetTraceLogLevel(log_level as i32);
+        }
         let (mut rl, thread) = raylib::init()
             .height(dim.1)
             .width(dim.0)
-            .title(title)
+            .title(&title)
             .build();
 
         rl.set_target_fps(60);
@@ -44,6 +69,11 @@ impl UIRoot {
             let wheel_move = d.get_mouse_wheel_move_v();
             let scroll_y = wheel_move.y;
 
+            let mouse_delta = d.get_mouse_delta();
+            if key.is_some() || left_mouse_pressed || scroll_y != 0.0 || mouse_delta.x != 0.0 || mouse_delta.y != 0.0 {
+                LAST_INPUT.lock().unwrap().replace(Instant::now());
+            }
+
             let scroll_event = ScrollEvent {
                 pos: (mouse_pos.x as i32, mouse_pos.y as i32),
                 delta: scroll_y as i32,
@@ -84,15 +114,15 @@ impl UIRoot {
                 UIRoot::measure_dimensions(main_child.clone(), dim);
                 UIRoot::measure_positions(main_child.clone());
                 UIRoot::measure_overflows(main_child.clone(), dim, &mut scroll_map);
-                UIRoot::draw(&mut d, main_child.clone());
+                UIRoot::draw(&mut d, main_child.clone(), background);
                 should_rebuild_ui = false;
 
             }
         }
     }
 
-    fn draw(draw_handle: &mut RaylibDrawHandle, root_child: Component) {
-        draw_handle.clear_background(Color::BLACK);
+    fn draw(draw_handle: &mut RaylibDrawHandle, root_child: Component, background: Color) {
+        draw_handle.clear_background(background);
 
         let child = root_child.borrow();
         let mut abs_draw = { child.draw(draw_handle) };
@@ -4,7 +4,7 @@ use crate::components::common::Component;
 use crate::raylib::{color::Color, prelude::RaylibDrawHandle};
 
 use crate::components::{
-    common::{AbsoluteDraw, Alignment, Base, KeyEvent, Length, MouseEvent, tabbed_print},
+    common::{AbsoluteDraw, Alignment, Base, KeyEvent, Length, MouseEvent, scale_font, tabbed_print, text_style},
     layout::{Layout, LayoutProps},
     raw_text::RawText,
 };
@@ -21,6 +21,7 @@ pub struct TextInputProps {
 }
 
 impl TextInputProps {
+    /// Text follows `text_style()`; the white background is meant to be replaced with `bg_color`.
     pub fn new() -> Self {
         let layout = Layout::get_col_builder()
             .dim((Length::FIT, Length::FIT))
@@ -36,9 +37,9 @@ impl TextInputProps {
         Self {
             layout,
             content: "".to_string(),
-            font_size: 24,
+            font_size: text_style().font_size,
             wrap: true,
-            text_color: Color::BLACK,
+            text_color: text_style().text_color,
         }
     }
 
@@ -150,7 +151,7 @@ impl TextInputProps {
         Rc::new(RefCell::new(TextInput {
             layout: layout.get_layout(),
             content: self.content.clone(),
-            font_size: self.font_size,
+            font_size: scale_font(self.font_size),
             wrap: self.wrap,
             text_color: self.text_color,
         }))
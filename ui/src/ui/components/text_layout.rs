@@ -1,6 +1,6 @@
 use std::{cell::RefCell, ffi::CString, rc::Rc};
 
-use crate::components::common::{Component, get_draw_dim};
+use crate::components::common::{Component, get_draw_dim, scale_font, text_style};
 use crate::raylib::color::Color;
 
 use crate::components::{
@@ -38,10 +38,10 @@ impl TextLayoutProps {
             .overflow_y(false);
         return Self {
             layout: layout,
-            font_size: 24,
+            font_size: text_style().font_size,
             wrap: true,
             content: String::from(""),
-            text_color: Color::BLACK,
+            text_color: text_style().text_color,
         };
     }
     pub fn content(mut self, content: &str) -> Self {
@@ -132,7 +132,7 @@ impl TextLayoutProps {
         let layout = self.layout;
         return Rc::new(RefCell::new(TextLayout {
             layout: layout.get_layout(),
-            font_size: self.font_size,
+            font_size: scale_font(self.font_size),
             wrap: self.wrap,
             content: self.content,
             text_color: self.text_color,